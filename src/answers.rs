@@ -0,0 +1,137 @@
+use itertools::Itertools;
+use log::info;
+
+use crate::formula::Formula;
+use crate::interval;
+use crate::operator::Relation;
+
+// Answers of an answer list unmatched on either side, e.g. a missing root `x = 2`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnswerDiff {
+    pub missing: Vec<Formula>,
+    pub extra: Vec<Formula>,
+}
+
+impl AnswerDiff {
+    pub fn is_empty(&self) -> bool {
+        return self.missing.is_empty() && self.extra.is_empty();
+    }
+}
+
+// Alternative answers and the variable solved for, with signs of `\pm` resolved
+// e.g. `x = 2, x = -3` and `x \in \{2, -3\}` => (Some(x), [2, -3]), `x = 1 \pm 2` => (Some(x), [1 + 2, 1 - 2])
+fn answers(formula: &Formula) -> (Option<String>, Vec<Formula>) {
+    let (var, elements) = match formula {
+        Formula::List(answers) | Formula::Or(answers) => (None, answers.clone()),
+        Formula::Set(elements) => (None, elements.clone()),
+        Formula::In(element, set) => match (interval::variable(element), &**set) {
+            (Some(var), Formula::Set(elements)) => (Some(var), elements.clone()),
+            _ => (None, vec![formula.clone()]),
+        },
+        f => (None, vec![f.clone()]),
+    };
+
+    let elements = elements.iter().flat_map(Formula::expand_signs).collect_vec();
+
+    if var.is_some() {
+        return (var, elements);
+    }
+
+    // `x = 2` or `2 = x` => (x, 2)
+    let solution = |f: &Formula| match f {
+        Formula::Relation(Relation::Eq, l, r) => match (interval::variable(l), interval::variable(r)) {
+            (Some(var), _) => Some((var, (**r).clone())),
+            (None, Some(var)) => Some((var, (**l).clone())),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    return match elements.iter().map(solution).collect::<Option<Vec<_>>>() {
+        Some(solutions) if solutions.iter().map(|(var, _)| var).all_equal() && !solutions.is_empty() => {
+            let var = solutions[0].0.clone();
+            (Some(var), solutions.into_iter().map(|(_, value)| value).collect_vec())
+        }
+        _ => (None, elements),
+    };
+}
+
+// Compare answer lists as multisets when either side is one or has `\pm`, e.g. `x = 2, x = -3` and `x = -3 \text{ or } x = 2`
+// Each unmatched answer of `expected` is missing and each unmatched one of `actual` is extra
+pub fn compare(expected: &Formula, actual: &Formula) -> Option<AnswerDiff> {
+    let is_list = |f: &Formula| matches!(f, Formula::List(_)) || f.has_plus_minus();
+
+    if !is_list(expected) && !is_list(actual) {
+        return None;
+    }
+
+    let (expected_var, expected_answers) = answers(expected);
+    let (actual_var, actual_answers) = answers(actual);
+
+    // Answers for different variables never match
+    let is_same_var = match (&expected_var, &actual_var) {
+        (Some(l), Some(r)) => l == r,
+        _ => true,
+    };
+
+    let mut unmatched = actual_answers.iter().collect_vec();
+    let mut missing = Vec::new();
+
+    for answer in &expected_answers {
+        match unmatched.iter().position(|a| is_same_var && *a == answer) {
+            Some(i) => {
+                unmatched.remove(i);
+            }
+            None => missing.push(with_var(answer, &expected_var)),
+        }
+    }
+
+    let diff = AnswerDiff {
+        missing,
+        extra: unmatched.into_iter().map(|a| with_var(a, &actual_var)).collect_vec(),
+    };
+
+    diff.missing.iter().for_each(|f| info!("Answers: missing => {:?}", f));
+    diff.extra.iter().for_each(|f| info!("Answers: extra => {:?}", f));
+    return Some(diff);
+}
+
+// `2` with x => `x = 2`
+fn with_var(answer: &Formula, var: &Option<String>) -> Formula {
+    return match var {
+        Some(var) => Formula::Relation(
+            Relation::Eq,
+            Box::new(Formula::TS(var.clone())),
+            Box::new(answer.clone()),
+        ),
+        None => answer.clone(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answers::*;
+
+    fn parse(s: &str) -> Formula {
+        return Formula::parse(s).unwrap();
+    }
+
+    #[test]
+    fn compare_test() {
+        let diff = compare(&parse("x = 2, x = -3"), &parse("x = -3 \\text{ or } x = 2")).unwrap();
+        assert!(diff.is_empty());
+
+        let diff = compare(&parse("x = 1, x = 2, x = 3"), &parse("x = 3, x = 4")).unwrap();
+        assert_eq!(diff.missing, vec![parse("x = 1"), parse("x = 2")]);
+        assert_eq!(diff.extra, vec![parse("x = 4")]);
+
+        let diff = compare(&parse("x = 2, x = 2"), &parse("x \\in \\{2\\}")).unwrap();
+        assert_eq!(diff.missing, vec![parse("x = 2")]);
+        assert!(diff.extra.is_empty());
+
+        assert_eq!(compare(&parse("x = 2"), &parse("x = 2")), None);
+
+        let diff = compare(&parse("x = 1 \\pm 2"), &parse("x = 3")).unwrap();
+        assert_eq!(diff.missing, vec![parse("x = 1 - 2")]);
+    }
+}
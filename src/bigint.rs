@@ -0,0 +1,352 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use crate::errors::ParseFormulaError;
+
+// Arbitrary-precision integer for exact evaluation, e.g. `30!` or `\binom{100}{50}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>, // Base 2^32 digits from the lowest, without leading zeros
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        return Self::from(0);
+    }
+
+    pub fn one() -> Self {
+        return Self::from(1);
+    }
+
+    fn new(negative: bool, magnitude: Vec<u32>) -> Self {
+        let mut magnitude = magnitude;
+
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+
+        return Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        };
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.magnitude.is_empty();
+    }
+
+    pub fn is_negative(&self) -> bool {
+        return self.negative;
+    }
+
+    pub fn abs(&self) -> Self {
+        return Self::new(false, self.magnitude.clone());
+    }
+
+    // e.g. 2^40 => None
+    pub fn to_u32(&self) -> Option<u32> {
+        return match self.magnitude.as_slice() {
+            _ if self.negative => None,
+            [] => Some(0),
+            [digit] => Some(*digit),
+            _ => None,
+        };
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let value = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &digit| acc * 4294967296.0 + digit as f64);
+        return if self.negative { -value } else { value };
+    }
+
+    // Power by a small exponent, e.g. 2^10 => 1024
+    pub fn pow(&self, exponent: u32) -> Self {
+        return (0..exponent).fold(Self::one(), |acc, _| acc * self.clone());
+    }
+
+    // Quotient rounded toward zero and the remainder with the sign of `self`, or None for a zero divisor
+    // e.g. -7 and 2 => (-3, -1)
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        if divisor.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+        return Some((
+            Self::new(self.negative != divisor.negative, quotient),
+            Self::new(self.negative, remainder),
+        ));
+    }
+
+    // Quotient rounded toward negative infinity, e.g. -7 and 2 => -4
+    pub fn div_floor(&self, divisor: &Self) -> Option<Self> {
+        let (quotient, remainder) = self.div_rem(divisor)?;
+
+        if !remainder.is_zero() && remainder.negative != divisor.negative {
+            return Some(quotient - Self::one());
+        }
+
+        return Some(quotient);
+    }
+
+    // Remainder with the sign of the divisor, e.g. -7 and 3 => 2
+    pub fn modulo(&self, divisor: &Self) -> Option<Self> {
+        let (_, remainder) = self.div_rem(divisor)?;
+
+        if !remainder.is_zero() && remainder.negative != divisor.negative {
+            return Some(remainder + divisor.clone());
+        }
+
+        return Some(remainder);
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        return Self::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32]);
+    }
+}
+
+// Decimal integers, e.g. `-120`
+impl FromStr for BigInt {
+    type Err = ParseFormulaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ParseFormulaError);
+        }
+
+        let magnitude = digits.chars().fold(Vec::new(), |magnitude, c| {
+            let digit = c.to_digit(10).unwrap_or(0);
+            return add_magnitude(&mul_small(&magnitude, 10), &[digit]);
+        });
+
+        return Ok(Self::new(negative, magnitude));
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Decimal digits in chunks of 9 from the lowest
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_magnitude(&magnitude, &[1_000_000_000]);
+            chunks.push(remainder.first().copied().unwrap_or(0));
+            magnitude = quotient;
+        }
+
+        let mut s = if self.negative { "-".to_string() } else { String::new() };
+        s.push_str(&chunks.pop().unwrap_or(0).to_string());
+        chunks
+            .iter()
+            .rev()
+            .for_each(|chunk| s.push_str(&format!("{:09}", chunk)));
+        return write!(f, "{}", s);
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        };
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        return Self::new(!self.negative, self.magnitude);
+    }
+}
+
+impl Add for BigInt {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+        }
+
+        // Subtract the smaller magnitude from the larger one
+        return match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => Self::new(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+            _ => Self::new(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+        };
+    }
+}
+
+impl Sub for BigInt {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        return self + -other;
+    }
+}
+
+impl Mul for BigInt {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut digits = vec![0u64; self.magnitude.len() + other.magnitude.len() + 1];
+
+        for (i, &l) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, &r) in other.magnitude.iter().enumerate() {
+                let value = digits[i + j] + l as u64 * r as u64 + carry;
+                digits[i + j] = value & 0xffff_ffff;
+                carry = value >> 32;
+            }
+
+            digits[i + other.magnitude.len()] += carry;
+        }
+
+        let magnitude = digits.into_iter().map(|d| d as u32).collect();
+        return Self::new(self.negative != other.negative, magnitude);
+    }
+}
+
+fn cmp_magnitude(l: &[u32], r: &[u32]) -> Ordering {
+    return l.len().cmp(&r.len()).then_with(|| l.iter().rev().cmp(r.iter().rev()));
+}
+
+fn add_magnitude(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::new();
+    let mut carry = 0u64;
+
+    for i in 0..l.len().max(r.len()) {
+        let value = *l.get(i).unwrap_or(&0) as u64 + *r.get(i).unwrap_or(&0) as u64 + carry;
+        digits.push(value as u32);
+        carry = value >> 32;
+    }
+
+    digits.push(carry as u32);
+    return trim(digits);
+}
+
+// `l - r` for `l >= r`
+fn sub_magnitude(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::new();
+    let mut borrow = 0i64;
+
+    for (i, &digit) in l.iter().enumerate() {
+        let mut value = digit as i64 - *r.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+
+        if value < 0 {
+            value += 1 << 32;
+            borrow = 1;
+        }
+
+        digits.push(value as u32);
+    }
+
+    return trim(digits);
+}
+
+fn mul_small(l: &[u32], r: u32) -> Vec<u32> {
+    let mut digits = Vec::new();
+    let mut carry = 0u64;
+
+    for &digit in l {
+        let value = digit as u64 * r as u64 + carry;
+        digits.push(value as u32);
+        carry = value >> 32;
+    }
+
+    digits.push(carry as u32);
+    return trim(digits);
+}
+
+// Binary long division, e.g. 7 and 2 => (3, 1)
+fn div_rem_magnitude(l: &[u32], r: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(l, r) == Ordering::Less {
+        return (vec![], l.to_vec());
+    }
+
+    let mut quotient = vec![0u32; l.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+
+    for bit in (0..l.len() * 32).rev() {
+        // remainder = remainder * 2 + the next bit
+        remainder = add_magnitude(&mul_small(&remainder, 2), &[(l[bit / 32] >> (bit % 32)) & 1]);
+
+        if cmp_magnitude(&remainder, r) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, r);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    return (trim(quotient), remainder);
+}
+
+fn trim(mut digits: Vec<u32>) -> Vec<u32> {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    return digits;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bigint::BigInt;
+
+    fn big(s: &str) -> BigInt {
+        return s.parse().unwrap();
+    }
+
+    #[test]
+    fn arithmetic_test() {
+        assert_eq!(big("4294967295") + big("1"), big("4294967296"));
+        assert_eq!(big("-5") + big("3"), big("-2"));
+        assert_eq!(big("3") - big("5"), BigInt::from(-2));
+        assert_eq!(
+            big("123456789012345678901234567890") * big("-987654321"),
+            big("-121932631124828532112482853211126352690")
+        );
+        assert_eq!(BigInt::from(2).pow(100).to_string(), "1267650600228229401496703205376");
+        assert!(big("-3") < big("2"));
+        assert!("1.5".parse::<BigInt>().is_err());
+    }
+
+    #[test]
+    fn division_test() {
+        let (quotient, remainder) = big("1267650600228229401496703205377").div_rem(&big("1024")).unwrap();
+        assert_eq!(quotient, BigInt::from(2).pow(90));
+        assert_eq!(remainder, big("1"));
+        assert_eq!(big("-7").div_rem(&big("2")), Some((big("-3"), big("-1"))));
+        assert_eq!(big("-7").div_floor(&big("2")), Some(big("-4")));
+        assert_eq!(big("-7").modulo(&big("3")), Some(big("2")));
+        assert_eq!(big("7").div_rem(&BigInt::zero()), None);
+    }
+}
@@ -2,16 +2,29 @@ use std::{env, process::exit};
 
 use log::{error, info};
 
-use latex_equivalencer::exec;
+use latex_equivalencer::config::Config;
+use latex_equivalencer::exec_with_config;
 use latex_equivalencer::logger;
 
 fn main() {
     logger::init();
 
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let mut config = Config::default();
+
+    if args.len() == 5 && args[1] == "--config" {
+        config = match Config::from_file(&args[2]) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{} => {:?}", e, args[2]);
+                exit(1);
+            }
+        };
+        args.drain(1..3);
+    }
 
     if args.len() != 3 {
-        error!("Give just 2 arguments except for `target/*`.");
+        error!("Give just 2 arguments except for `target/*` (and `--config <path>` optionally).");
         exit(1);
     }
 
@@ -19,6 +32,6 @@ fn main() {
     let rhs = &args[2];
     info!("Inputs => {{ lhs => {:?}, rhs => {:?} }}", lhs, rhs);
 
-    let result = exec(lhs, rhs);
+    let result = exec_with_config(lhs, rhs, &config);
     info!("Result => {:?}", result);
 }
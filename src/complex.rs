@@ -0,0 +1,531 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use itertools::Itertools;
+use log::debug;
+
+use crate::bigint::BigInt;
+use crate::config::Config;
+use crate::formula::Formula;
+use crate::integer;
+use crate::numeric::{Sampler, MIN_VALID_SAMPLES, SAMPLES, TOLERANCE};
+use crate::symbol::Constant;
+
+// Integer powers up to this are multiplied out, so that `i^2` is exactly -1
+const MAX_INTEGER_POWER: f64 = 64.0;
+
+// A part smaller than this relative to the other is a rounding error, e.g. the imaginary part of `e^{i \pi}`
+const NEGLIGIBLE: f64 = 1e-12;
+
+// Functions of a complex argument with a real value, e.g. `\Re z`
+const PART_FUNCTIONS: [&str; 3] = ["\\Re", "\\Im", "\\arg"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        return Self { re, im };
+    }
+
+    fn real(re: f64) -> Self {
+        return Self::new(re, 0.0);
+    }
+
+    pub fn abs(&self) -> f64 {
+        return self.re.hypot(self.im);
+    }
+
+    // Principal argument in (-π, π], where adding zero turns `-0.0` into `0.0`, e.g. -1 => π
+    pub fn arg(&self) -> f64 {
+        return (self.im + 0.0).atan2(self.re);
+    }
+
+    pub fn conj(&self) -> Self {
+        return Self::new(self.re, -self.im);
+    }
+
+    fn is_zero(&self) -> bool {
+        return self.re == 0.0 && self.im == 0.0;
+    }
+
+    fn recip(self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+
+        let norm = self.re * self.re + self.im * self.im;
+        return Some(Self::new(self.re / norm, -self.im / norm));
+    }
+
+    fn exp(self) -> Self {
+        let modulus = self.re.exp();
+        return Self::new(modulus * self.im.cos(), modulus * self.im.sin());
+    }
+
+    // Principal logarithm
+    fn ln(self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+
+        return Some(Self::new(self.abs().ln(), self.arg()));
+    }
+
+    // Integer powers are multiplied out, others go through the principal logarithm, e.g. `i^2` => -1
+    fn pow(self, exponent: Self) -> Option<Self> {
+        if exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= MAX_INTEGER_POWER {
+            let power = (0..exponent.re.abs() as usize).fold(Self::real(1.0), |acc, _| acc * self);
+            return if exponent.re < 0.0 { power.recip() } else { Some(power) };
+        }
+
+        if self.is_zero() {
+            return if exponent.re > 0.0 { Some(self) } else { None };
+        }
+
+        return Some((exponent * self.ln()?).exp());
+    }
+
+    fn sin(self) -> Self {
+        let (p, q) = ((Self::new(0.0, 1.0) * self).exp(), (Self::new(0.0, -1.0) * self).exp());
+        return (p - q) * Self::new(0.0, -0.5);
+    }
+
+    fn cos(self) -> Self {
+        let (p, q) = ((Self::new(0.0, 1.0) * self).exp(), (Self::new(0.0, -1.0) * self).exp());
+        return (p + q) * Self::real(0.5);
+    }
+
+    fn sinh(self) -> Self {
+        return (self.exp() - (-self).exp()) * Self::real(0.5);
+    }
+
+    fn cosh(self) -> Self {
+        return (self.exp() + (-self).exp()) * Self::real(0.5);
+    }
+
+    fn is_close(&self, other: &Self) -> bool {
+        return (*self - *other).abs() <= TOLERANCE * (1.0 + self.abs().max(other.abs()));
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        return Self::new(self.re + other.re, self.im + other.im);
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        return Self::new(self.re - other.re, self.im - other.im);
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        return Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        );
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        return Self::new(-self.re, -self.im);
+    }
+}
+
+impl Formula {
+    // Evaluate with `vars` bound to complex values, e.g. `(1 + i)^2` => 2i
+    pub fn eval_complex(&self, config: &Config, vars: &BTreeMap<String, Complex>) -> Option<Complex> {
+        let eval = |f: &Self| f.eval_complex(config, vars);
+
+        let value = match self {
+            Self::TS(s) if Self::is_number(s) => s.parse::<f64>().ok().map(Complex::real),
+            Self::TS(s) => vars.get(s).copied(),
+            Self::Const(s) => match config.constants.get(s) {
+                Some(Constant::Real(value)) => Some(Complex::real(*value)),
+                Some(Constant::Imaginary) => Some(Complex::new(0.0, 1.0)),
+                None => None,
+            },
+            Self::Neg(formula) => eval(formula).map(|z| -z),
+            Self::Recip(formula) => eval(formula)?.recip(),
+            Self::Abs(formula) | Self::Norm(formula) => eval(formula).map(|z| Complex::real(z.abs())),
+            Self::Conj(formula) => eval(formula).map(|z| z.conj()),
+            Self::Add(formulas) => formulas
+                .iter()
+                .try_fold(Complex::real(0.0), |acc, f| Some(acc + eval(f)?)),
+            Self::Mul(formulas) => formulas
+                .iter()
+                .try_fold(Complex::real(1.0), |acc, f| Some(acc * eval(f)?)),
+            Self::Pow(base, exponent) => eval(base)?.pow(eval(exponent)?),
+            Self::Func(name, formula) => Self::apply_complex(name, eval(formula)?),
+            _ => None,
+        };
+
+        return value.filter(|z| z.re.is_finite() && z.im.is_finite());
+    }
+
+    // `\sin` and i => i \sinh 1
+    fn apply_complex(name: &str, z: Complex) -> Option<Complex> {
+        return match name {
+            "\\sin" => Some(z.sin()),
+            "\\cos" => Some(z.cos()),
+            "\\tan" => Some(z.sin() * z.cos().recip()?),
+            "\\cot" => Some(z.cos() * z.sin().recip()?),
+            "\\sec" => z.cos().recip(),
+            "\\csc" => z.sin().recip(),
+            "\\sinh" => Some(z.sinh()),
+            "\\cosh" => Some(z.cosh()),
+            "\\tanh" => Some(z.sinh() * z.cosh().recip()?),
+            "\\exp" => Some(z.exp()),
+            "\\ln" | "\\log" => z.ln(),
+            "\\Re" => Some(Complex::real(z.re)),
+            "\\Im" => Some(Complex::real(z.im)),
+            "\\arg" if !z.is_zero() => Some(Complex::real(z.arg())),
+            // Inverse trigonometric functions on their real domain only
+            _ if z.im == 0.0 => Self::apply(name, z.re).map(Complex::real),
+            _ => None,
+        };
+    }
+
+    // Reduce powers of the imaginary unit and bring constant parts into `a + b i` form
+    // e.g. `i^2 x` => `-x`, `(1 + i)^2 + z` => `2 i + z`, `|3 + 4i|` => `5`
+    pub fn complex_normal_form(self, config: &Config) -> Self {
+        if written_unit(&self, config).is_some() && self.free_vars().is_empty() {
+            if let Some((re, im)) = exact_gaussian(&self, config) {
+                return Self::from_gaussian(re, im, &imaginary_unit(&self, config));
+            }
+
+            if let Some(z) = self.eval_complex(config, &BTreeMap::new()) {
+                return Self::from_complex(z, &imaginary_unit(&self, config));
+            }
+        }
+
+        let unit_count = |f: &Self| match f {
+            Self::Const(s) if is_imaginary(s, config) => Some((s.clone(), 1)),
+            Self::Pow(base, exponent) => match (&**base, &**exponent) {
+                (Self::Const(s), Self::TS(n)) if is_imaginary(s, config) => Some((s.clone(), n.parse::<usize>().ok()?)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        return match self {
+            // i^3 => -i
+            Self::Pow(_, _) => {
+                let formula = self.map_children(|f| f.complex_normal_form(config));
+
+                match unit_count(&formula) {
+                    Some((unit, count)) => Self::unit_power(&unit, count),
+                    None => formula,
+                }
+            }
+            // i x i => -x, i^3 x => -i x
+            Self::Mul(factors) if factors.iter().any(|f| unit_count(f).is_some()) => {
+                let (units, rest): (Vec<_>, Vec<_>) = factors.into_iter().partition(|f| unit_count(f).is_some());
+                let counts = units.iter().filter_map(unit_count).collect_vec();
+                let count = counts.iter().map(|(_, n)| n).sum::<usize>();
+                let mut rest = rest.into_iter().map(|f| f.complex_normal_form(config)).collect_vec();
+
+                if count % 2 == 1 {
+                    rest.insert(0, Self::Const(counts[0].0.clone()));
+                }
+
+                let product = match rest.len() {
+                    0 => Self::TS("1".to_string()),
+                    1 => rest.remove(0),
+                    _ => Self::Mul(rest),
+                };
+
+                if count % 4 < 2 {
+                    product
+                } else {
+                    Self::Neg(Box::new(product))
+                }
+            }
+            formula => formula.map_children(|f| f.complex_normal_form(config)),
+        };
+    }
+
+    // i^n by n mod 4, e.g. i^6 => -1
+    fn unit_power(unit: &str, count: usize) -> Self {
+        let i = Self::Const(unit.to_string());
+        let one = Self::TS("1".to_string());
+
+        return match count % 4 {
+            0 => one,
+            1 => i,
+            2 => Self::Neg(Box::new(one)),
+            _ => Self::Neg(Box::new(i)),
+        };
+    }
+
+    // 3 - 2i => Add([3, Neg(Mul([2, i]))]) with negligible parts dropped
+    fn from_complex(z: Complex, unit: &str) -> Self {
+        let scale = 1.0 + z.abs();
+        let re = if z.re.abs() <= NEGLIGIBLE * scale { 0.0 } else { z.re };
+        let im = if z.im.abs() <= NEGLIGIBLE * scale { 0.0 } else { z.im };
+
+        let i = Self::Const(unit.to_string());
+        let imaginary = if im.abs() == 1.0 {
+            i
+        } else {
+            Self::Mul(vec![Self::number(im.abs()), i])
+        };
+        let imaginary = if im < 0.0 {
+            Self::Neg(Box::new(imaginary))
+        } else {
+            imaginary
+        };
+
+        return match (re == 0.0, im == 0.0) {
+            (_, true) => Self::number(re),
+            (true, false) => imaginary,
+            (false, false) => Self::Add(vec![Self::number(re), imaginary]),
+        };
+    }
+
+    // 2^{70} i + i => Mul([1180591620717411303425, i]) without rounding
+    fn from_gaussian(re: BigInt, im: BigInt, unit: &str) -> Self {
+        let signed = |n: &BigInt, f: Self| if n.is_negative() { Self::Neg(Box::new(f)) } else { f };
+        let i = Self::Const(unit.to_string());
+        let imaginary = if im.abs() == BigInt::one() {
+            i
+        } else {
+            Self::Mul(vec![Self::TS(im.abs().to_string()), i])
+        };
+        let imaginary = signed(&im, imaginary);
+        let real = signed(&re, Self::TS(re.abs().to_string()));
+
+        return match (re.is_zero(), im.is_zero()) {
+            (_, true) => real,
+            (true, false) => imaginary,
+            (false, false) => Self::Add(vec![real, imaginary]),
+        };
+    }
+}
+
+// Exact value of a constant Gaussian integer, e.g. `(1 + i)^2` => (0, 2), or None for other formulas
+fn exact_gaussian(formula: &Formula, config: &Config) -> Option<(BigInt, BigInt)> {
+    if let Some(n) = integer::exact_integer(formula) {
+        return Some((n, BigInt::zero()));
+    }
+
+    let mul = |(a, b): (BigInt, BigInt), (c, d): (BigInt, BigInt)| {
+        return (a.clone() * c.clone() - b.clone() * d.clone(), a * d + b * c);
+    };
+
+    return match formula {
+        Formula::Const(s) if is_imaginary(s, config) => Some((BigInt::zero(), BigInt::one())),
+        Formula::Neg(f) => exact_gaussian(f, config).map(|(re, im)| (-re, -im)),
+        Formula::Add(terms) => terms.iter().try_fold((BigInt::zero(), BigInt::zero()), |(re, im), f| {
+            let (a, b) = exact_gaussian(f, config)?;
+            return Some((re + a, im + b));
+        }),
+        Formula::Mul(factors) => factors.iter().try_fold((BigInt::one(), BigInt::zero()), |acc, f| {
+            return Some(mul(acc, exact_gaussian(f, config)?));
+        }),
+        Formula::Pow(base, exponent) => {
+            let n = integer::exact_integer(exponent)?
+                .to_u32()
+                .filter(|n| *n <= integer::MAX_EXPONENT)?;
+            let base = exact_gaussian(base, config)?;
+            Some((0..n).fold((BigInt::one(), BigInt::zero()), |acc, _| mul(acc, base.clone())))
+        }
+        _ => None,
+    };
+}
+
+fn is_imaginary(name: &str, config: &Config) -> bool {
+    return config.constants.get(name) == Some(&Constant::Imaginary);
+}
+
+// The imaginary unit written in the formula, e.g. `j`, or else the declared one
+fn imaginary_unit(formula: &Formula, config: &Config) -> String {
+    return written_unit(formula, config)
+        .or_else(|| {
+            config
+                .constants
+                .iter()
+                .find(|(_, constant)| **constant == Constant::Imaginary)
+                .map(|(name, _)| name.clone())
+        })
+        .unwrap_or_else(|| "i".to_string());
+}
+
+fn written_unit(formula: &Formula, config: &Config) -> Option<String> {
+    return match formula {
+        Formula::Const(s) if is_imaginary(s, config) => Some(s.clone()),
+        _ => formula.children().into_iter().find_map(|f| written_unit(f, config)),
+    };
+}
+
+// Whether the formula uses the imaginary unit, a conjugate or a part of a complex number
+pub fn is_complex(formula: &Formula, config: &Config) -> bool {
+    return match formula {
+        Formula::Const(s) => is_imaginary(s, config),
+        Formula::Conj(_) => true,
+        Formula::Func(name, _) if PART_FUNCTIONS.contains(&name.as_str()) => true,
+        _ => false,
+    } || formula.children().into_iter().any(|f| is_complex(f, config));
+}
+
+// Variables written as the parts of `a + b i`, which are real, e.g. `x` and `y` in `x + i y`
+fn real_parts(formula: &Formula, config: &Config) -> BTreeSet<String> {
+    let mut vars = formula
+        .children()
+        .into_iter()
+        .flat_map(|f| real_parts(f, config))
+        .collect::<BTreeSet<_>>();
+
+    if let Formula::Add(terms) = formula {
+        let is_imaginary_part = |f: &Formula| match f {
+            Formula::Mul(factors) => {
+                factors
+                    .iter()
+                    .any(|f| matches!(f, Formula::Const(s) if is_imaginary(s, config)))
+                    && !f.free_vars().is_empty()
+            }
+            _ => false,
+        };
+
+        if terms.iter().any(is_imaginary_part) {
+            vars.extend(terms.iter().flat_map(|f| f.free_vars()));
+        }
+    }
+
+    return vars;
+}
+
+// Whether `lhs` and `rhs` agree at complex sample points, or None if neither is defined at any
+// The parts of `a + b i` are sampled on the real line, e.g. `|x + i y|` and `\sqrt{x^2 + y^2}`
+pub fn equivalent(lhs: &Formula, rhs: &Formula, config: &Config) -> Option<bool> {
+    // Constant Gaussian integers are compared exactly, e.g. `2^{70} i` != `2^{70} i + i`
+    if let (Some(l), Some(r)) = (exact_gaussian(lhs, config), exact_gaussian(rhs, config)) {
+        return Some(l == r);
+    }
+
+    let mut vars = lhs.free_vars();
+    vars.extend(rhs.free_vars());
+    let mut real_vars = real_parts(lhs, config);
+    real_vars.extend(real_parts(rhs, config));
+
+    let mut sampler = Sampler::new();
+    let mut valid = 0;
+
+    for _ in 0..SAMPLES {
+        let point = vars
+            .iter()
+            .map(|var| {
+                let re = sampler.next();
+                let im = if real_vars.contains(var) { 0.0 } else { sampler.next() };
+                return (var.clone(), Complex::new(re, im));
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        match (lhs.eval_complex(config, &point), rhs.eval_complex(config, &point)) {
+            (Some(l), Some(r)) if l.is_close(&r) => valid += 1,
+            (None, None) => continue,
+            (l, r) => {
+                debug!("Complex: differ at {:?} => {:?} != {:?}", point, l, r);
+                return Some(false);
+            }
+        }
+    }
+
+    debug!("Complex: {} of {} samples agree", valid, SAMPLES);
+    return if valid == 0 {
+        None
+    } else {
+        Some(valid >= MIN_VALID_SAMPLES)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::complex::*;
+
+    fn eval(s: &str) -> Option<Complex> {
+        return Formula::parse(s)
+            .unwrap()
+            .eval_complex(&Config::default(), &BTreeMap::new());
+    }
+
+    fn normal_form(s: &str) -> Formula {
+        let config = Config::default();
+        return Formula::parse_with_config(s, &config)
+            .unwrap()
+            .complex_normal_form(&config);
+    }
+
+    fn same(lhs: &str, rhs: &str) -> Option<bool> {
+        let config = Config::default();
+        return equivalent(
+            &Formula::parse_with_config(lhs, &config).unwrap(),
+            &Formula::parse_with_config(rhs, &config).unwrap(),
+            &config,
+        );
+    }
+
+    #[test]
+    fn eval_complex_test() {
+        assert_eq!(eval("i^2"), Some(Complex::new(-1.0, 0.0)));
+        assert_eq!(eval("(1 + 2i)(3 - i)"), Some(Complex::new(5.0, 5.0)));
+        assert_eq!(eval("|3 + 4i|"), Some(Complex::new(5.0, 0.0)));
+        assert_eq!(eval("\\overline{1 + i} + \\Im(2 + 3i)"), Some(Complex::new(4.0, -1.0)));
+        assert!(eval("e^{i \\pi}").unwrap().is_close(&Complex::new(-1.0, 0.0)));
+        assert_eq!(eval("\\frac{1}{0 i}"), None);
+    }
+
+    #[test]
+    fn complex_normal_form_test() {
+        let i = || Formula::Const("i".to_string());
+        let ts = |s: &str| Formula::TS(s.to_string());
+
+        let expect = Formula::Add(vec![ts("2"), Formula::Neg(Box::new(Formula::Mul(vec![ts("3"), i()])))]);
+        assert!(Formula::eq_without_expand(&normal_form("(1 - i) + (1 - 2i)"), &expect));
+
+        let expect = Formula::Neg(Box::new(ts("x")));
+        assert!(Formula::eq_without_expand(&normal_form("i x i"), &expect));
+        assert!(Formula::eq_without_expand(&normal_form("i^2 x"), &expect));
+        assert!(Formula::eq_without_expand(&normal_form("x i^4"), &ts("x")));
+
+        let expect = Formula::Neg(Box::new(Formula::Mul(vec![i(), ts("x")])));
+        assert!(Formula::eq_without_expand(&normal_form("i^3 x"), &expect));
+
+        let expect = Formula::Neg(Box::new(i()));
+        assert!(Formula::eq_without_expand(&normal_form("i^3"), &expect));
+        assert!(Formula::eq_without_expand(&normal_form("e^{i \\pi} + 1"), &ts("0")));
+
+        let expect = Formula::Mul(vec![ts("1180591620717411303425"), i()]);
+        assert!(Formula::eq_without_expand(&normal_form("2^{70} i + i"), &expect));
+    }
+
+    #[test]
+    fn equivalent_test() {
+        assert_eq!(same("z \\bar z", "|z|^2"), Some(true));
+        assert_eq!(same("\\overline{z w}", "\\bar z \\bar w"), Some(true));
+        assert_eq!(same("\\bar z", "z"), Some(false));
+        assert_eq!(same("\\Re z", "\\frac{z + \\bar z}{2}"), Some(true));
+        assert_eq!(same("|x + i y|", "\\sqrt{x^2 + y^2}"), Some(true));
+        assert_eq!(same("(x + i y)^2", "x^2 - y^2 + 2 i x y"), Some(true));
+        assert_eq!(same("\\arg(i)", "\\frac{\\pi}{2}"), Some(true));
+        assert_eq!(same("\\text{m}", "\\text{m}"), None);
+        assert_eq!(same("2^{70} i", "2^{70} i + i"), Some(false));
+    }
+}
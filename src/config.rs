@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use log::error;
+
+use crate::errors::{ParseConfigError, ParseMacroError};
+use crate::macros::{self, Macro};
+use crate::symbol::{self, Constant};
+
+// Per-course settings for parsing and comparison
+//
+// A config file consists of `key = value, value, ...` lines:
+//
+// ```text
+// # Physics
+// identifiers = KE, dt, Re
+// constants = g: 9.8, \pi: 3.14
+// imaginary = j
+// unwrapped_fonts = mathrm, mathit
+// noncommutative = A, B, Q
+// pairs = intervals
+// overline = symbol
+// combinatorial = C, P
+// prime_variable = t
+// mode = up_to_constant: x
+// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    // e.g. `\pi` => 3.14..., `e` => 2.71...
+    pub constants: BTreeMap<String, Constant>,
+    // Multi-letter identifiers not split by implicit multiplication, e.g. `KE`
+    pub identifiers: BTreeSet<String>,
+    // Font macros whose argument is taken as a plain identifier, e.g. `mathrm` for `\mathrm{d}` == `d`
+    pub unwrapped_fonts: BTreeSet<String>,
+    // User-defined macros expanded before parsing, e.g. `\R` => `\mathbb{R}`
+    pub macros: BTreeMap<String, Macro>,
+    // Symbols whose products keep their order, e.g. matrices `A`, `B`
+    pub noncommutative: BTreeSet<String>,
+    // Whether `(a, b)` is a tuple or an open interval
+    pub pairs: Pairs,
+    // Whether `\overline{z}` and `\bar z` are conjugates or symbols
+    pub overline: Overline,
+    // Letters read as combinatorial notation rather than symbols, `C` for `C^n_k` == `\binom{n}{k}` and `P` for `P(n, k)`
+    pub combinatorial: BTreeSet<String>,
+    // Variable of differentiation implied by primes, e.g. `t` for `y'` == `\frac{dy}{dt}`
+    pub prime_variable: String,
+    // How two formulas are compared
+    pub mode: Mode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Structural,           // Same formula after normalisation
+    Numeric,              // Same values at sampled points, e.g. `(x + 1)^2` == `x^2 + 2x + 1`
+    UpToConstant(String), // Differ by a constant in the variable, e.g. `\frac{x^2}{2} + C` == `\frac{x^2 + 1}{2}`
+}
+
+// Reading of `(a, b)` outside of a set context like `x \in (a, b)` or `(a, b) \cup (c, d)`, where it is always an interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pairs {
+    Auto,      // An interval if an end is infinite or it is compared with an inequality, a tuple otherwise
+    Tuples,    // Always a tuple, e.g. coordinates `(1, -2)`
+    Intervals, // Always an interval, e.g. solutions `(0, 1)`
+}
+
+// Reading of `\overline{x}` and `\bar x`, while a repeating decimal like `0.\overline{3}` is always a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overline {
+    Conjugate, // The complex conjugate, e.g. `z \bar z` == `|z|^2`
+    Symbol,    // A symbol of its own, e.g. a mean `\bar x` or a segment `\overline{AB}`
+}
+
+impl FromStr for Overline {
+    type Err = ParseConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "conjugate" => Ok(Self::Conjugate),
+            "symbol" => Ok(Self::Symbol),
+            _ => {
+                error!("Config: unknown overline => {:?}", s);
+                Err(ParseConfigError)
+            }
+        };
+    }
+}
+
+impl FromStr for Pairs {
+    type Err = ParseConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "auto" => Ok(Self::Auto),
+            "tuples" => Ok(Self::Tuples),
+            "intervals" => Ok(Self::Intervals),
+            _ => {
+                error!("Config: unknown pairs => {:?}", s);
+                Err(ParseConfigError)
+            }
+        };
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ParseConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `up_to_constant: x`
+        if let Some((mode, var)) = s.split_once(':') {
+            if mode.trim() == "up_to_constant" && !var.trim().is_empty() {
+                return Ok(Self::UpToConstant(var.trim().to_string()));
+            }
+        }
+
+        return match s {
+            "structural" => Ok(Self::Structural),
+            "numeric" => Ok(Self::Numeric),
+            _ => {
+                error!("Config: unknown mode => {:?}", s);
+                Err(ParseConfigError)
+            }
+        };
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Self {
+            constants: symbol::default_constants(),
+            identifiers: BTreeSet::new(),
+            unwrapped_fonts: symbol::default_unwrapped_fonts(),
+            macros: BTreeMap::new(),
+            noncommutative: BTreeSet::new(),
+            pairs: Pairs::Auto,
+            overline: Overline::Conjugate,
+            combinatorial: BTreeSet::new(),
+            prime_variable: symbol::PRIME_VARIABLE.to_string(),
+            mode: Mode::Structural,
+        };
+    }
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ParseConfigError> {
+        let s = fs::read_to_string(path.as_ref()).map_err(|e| {
+            error!("Config: failed to read {:?} => {:?}", path.as_ref(), e);
+            return ParseConfigError;
+        })?;
+
+        return Self::from_str(&s);
+    }
+
+    pub fn is_constant(&self, name: &str) -> bool {
+        return self.constants.contains_key(name);
+    }
+
+    pub fn is_noncommutative(&self, name: &str) -> bool {
+        return self.noncommutative.contains(name);
+    }
+
+    pub fn is_combinatorial(&self, letter: &str) -> bool {
+        return self.combinatorial.contains(letter);
+    }
+
+    pub fn add_identifier(&mut self, identifier: &str) {
+        self.identifiers.insert(identifier.to_string());
+    }
+
+    // e.g. `define_macro("\\abs", Macro::new(1, None, "\\left|#1\\right|"))`
+    pub fn define_macro(&mut self, name: &str, definition: Macro) -> Result<(), ParseMacroError> {
+        if !name.starts_with('\\') || !definition.is_valid() {
+            error!("Config: invalid macro => {:?} {:?}", name, definition);
+            return Err(ParseMacroError);
+        }
+
+        self.macros.insert(name.to_string(), definition);
+        return Ok(());
+    }
+
+    // Load `\newcommand`, `\renewcommand` and `\def` definitions from LaTeX source
+    pub fn load_macros(&mut self, source: &str) -> Result<(), ParseMacroError> {
+        self.macros.extend(macros::parse_definitions(source)?);
+        return Ok(());
+    }
+
+    // Length of the longest declared identifier at the head of `chars`
+    pub fn match_identifier(&self, chars: &[char]) -> Option<usize> {
+        return self
+            .identifiers
+            .iter()
+            .map(|identifier| identifier.chars().collect_vec())
+            .filter(|identifier| chars.starts_with(identifier))
+            .map(|identifier| identifier.len())
+            .max();
+    }
+
+    fn set(&mut self, key: &str, values: Vec<&str>) -> Result<(), ParseConfigError> {
+        match key {
+            "identifiers" => {
+                values.into_iter().for_each(|v| self.add_identifier(v));
+            }
+
+            "unwrapped_fonts" => {
+                self.unwrapped_fonts = values
+                    .into_iter()
+                    .map(|v| v.trim_start_matches('\\').to_string())
+                    .collect();
+            }
+
+            "constants" => {
+                for value in values {
+                    let (name, number) = value.split_once(':').ok_or(ParseConfigError)?;
+                    let number = number.trim().parse::<f64>().map_err(|_| ParseConfigError)?;
+                    self.constants.insert(name.trim().to_string(), Constant::Real(number));
+                }
+            }
+
+            // The imaginary units replace the default `i`, e.g. `j` for engineers who write `i` for a current
+            "imaginary" => {
+                self.constants.retain(|_, constant| *constant != Constant::Imaginary);
+                self.constants
+                    .extend(values.into_iter().map(|v| (v.to_string(), Constant::Imaginary)));
+            }
+
+            "noncommutative" => {
+                self.noncommutative.extend(values.into_iter().map(|v| v.to_string()));
+            }
+
+            "pairs" => match values.as_slice() {
+                [pairs] => self.pairs = pairs.parse()?,
+                _ => return Err(ParseConfigError),
+            },
+
+            "overline" => match values.as_slice() {
+                [overline] => self.overline = overline.parse()?,
+                _ => return Err(ParseConfigError),
+            },
+
+            "combinatorial" => {
+                for value in values {
+                    if !symbol::COMBINATORIAL_LETTERS.contains(&value) {
+                        error!("Config: unknown combinatorial notation => {:?}", value);
+                        return Err(ParseConfigError);
+                    }
+
+                    self.combinatorial.insert(value.to_string());
+                }
+            }
+
+            "prime_variable" => match values.as_slice() {
+                [var] => self.prime_variable = var.to_string(),
+                _ => return Err(ParseConfigError),
+            },
+
+            "mode" => match values.as_slice() {
+                [mode] => self.mode = mode.parse()?,
+                _ => return Err(ParseConfigError),
+            },
+
+            _ => {
+                error!("Config: unknown key => {:?}", key);
+                return Err(ParseConfigError);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl FromStr for Config {
+    type Err = ParseConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Self::default();
+
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                error!("Config: missing `=` => {:?}", line);
+                return ParseConfigError;
+            })?;
+
+            let values = value
+                .split(',')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .collect_vec();
+            config.set(key.trim(), values)?;
+        }
+
+        return Ok(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::config::{Config, Mode, Overline, Pairs};
+    use crate::symbol::Constant;
+
+    #[test]
+    fn from_str_test() {
+        let config = "# Physics\nidentifiers = KE, dt\n\nconstants = g: 9.8 # gravity\n"
+            .parse::<Config>()
+            .unwrap();
+        let expect = BTreeSet::from(["KE".to_string(), "dt".to_string()]);
+        assert_eq!(config.identifiers, expect);
+        assert_eq!(config.constants.get("g"), Some(&Constant::Real(9.8)));
+        assert!(config.is_constant("\\pi"));
+        assert!(config.unwrapped_fonts.contains("mathbf"));
+
+        let config = "unwrapped_fonts = \\mathrm".parse::<Config>().unwrap();
+        assert_eq!(config.unwrapped_fonts, BTreeSet::from(["mathrm".to_string()]));
+
+        let config = "imaginary = j".parse::<Config>().unwrap();
+        assert_eq!(config.constants.get("j"), Some(&Constant::Imaginary));
+        assert!(!config.is_constant("i"));
+
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        assert_eq!(config.mode, Mode::Numeric);
+        assert_eq!(Config::default().mode, Mode::Structural);
+
+        let config = "mode = up_to_constant: t".parse::<Config>().unwrap();
+        assert_eq!(config.mode, Mode::UpToConstant("t".to_string()));
+
+        let config = "noncommutative = A, B".parse::<Config>().unwrap();
+        assert!(config.is_noncommutative("A"));
+        assert!(!config.is_noncommutative("x"));
+
+        let config = "pairs = tuples".parse::<Config>().unwrap();
+        assert_eq!(config.pairs, Pairs::Tuples);
+        assert_eq!(Config::default().pairs, Pairs::Auto);
+
+        let config = "overline = symbol".parse::<Config>().unwrap();
+        assert_eq!(config.overline, Overline::Symbol);
+        assert_eq!(Config::default().overline, Overline::Conjugate);
+
+        let config = "prime_variable = t".parse::<Config>().unwrap();
+        assert_eq!(config.prime_variable, "t");
+
+        let config = "combinatorial = C, P".parse::<Config>().unwrap();
+        assert!(config.is_combinatorial("C") && config.is_combinatorial("P"));
+        assert!(!Config::default().is_combinatorial("C"));
+    }
+
+    #[test]
+    fn from_str_fail_test() {
+        assert!("identifiers KE".parse::<Config>().is_err());
+        assert!("unknown = x".parse::<Config>().is_err());
+        assert!("constants = g".parse::<Config>().is_err());
+        assert!("constants = g: x".parse::<Config>().is_err());
+        assert!("mode = fuzzy".parse::<Config>().is_err());
+        assert!("mode = numeric, structural".parse::<Config>().is_err());
+        assert!("mode = up_to_constant:".parse::<Config>().is_err());
+        assert!("pairs = triples".parse::<Config>().is_err());
+        assert!("overline = mean".parse::<Config>().is_err());
+        assert!("prime_variable = x, t".parse::<Config>().is_err());
+        assert!("combinatorial = Q".parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn match_identifier_test() {
+        let mut config = Config::default();
+        config.add_identifier("d");
+        config.add_identifier("dt");
+        assert_eq!(config.match_identifier(&['d', 't', 'x']), Some(2));
+        assert_eq!(config.match_identifier(&['d', 'x']), Some(1));
+        assert_eq!(config.match_identifier(&['x', 'd']), None);
+    }
+}
@@ -0,0 +1,719 @@
+use itertools::Itertools;
+
+use crate::symbol;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    Paren,    // ( )
+    Bracket,  // [ ]
+    Brace,    // \{ \}
+    Angle,    // \langle \rangle
+    Group,    // { }
+    Abs,      // | |
+    Norm,     // \| \|
+    Floor,    // \lfloor \rfloor
+    Ceil,     // \lceil \rceil
+    Round,    // \lround \rround, written by `resolve_rounding` for `\lfloor x \rceil`
+    Env,      // \begin{pmatrix} \end{pmatrix}
+    Interval, // \lopen \rclosed, written by `resolve_intervals` for `(a, b]`
+}
+
+// Size prefixes, e.g. `\left(`, `\bigl[`
+const SIZES: [&str; 18] = [
+    "left", "right", "big", "bigl", "bigr", "bigm", "Big", "Bigl", "Bigr", "Bigm", "bigg", "biggl", "biggr", "biggm",
+    "Bigg", "Biggl", "Biggr", "Biggm",
+];
+
+// Pipes are resolved into `\lvert`, `\rvert`, `\lVert` and `\rVert` by `resolve_pipes` beforehand
+const OPENS: [(&str, Delimiter); 11] = [
+    ("(", Delimiter::Paren),
+    ("[", Delimiter::Bracket),
+    ("\\{", Delimiter::Brace),
+    ("\\langle", Delimiter::Angle),
+    ("\\lvert", Delimiter::Abs),
+    ("\\lVert", Delimiter::Norm),
+    ("\\lfloor", Delimiter::Floor),
+    ("\\lceil", Delimiter::Ceil),
+    ("\\lround", Delimiter::Round),
+    ("\\lopen", Delimiter::Interval),
+    ("\\lclosed", Delimiter::Interval),
+];
+
+const CLOSES: [(&str, Delimiter); 11] = [
+    (")", Delimiter::Paren),
+    ("]", Delimiter::Bracket),
+    ("\\}", Delimiter::Brace),
+    ("\\rangle", Delimiter::Angle),
+    ("\\rvert", Delimiter::Abs),
+    ("\\rVert", Delimiter::Norm),
+    ("\\rfloor", Delimiter::Floor),
+    ("\\rceil", Delimiter::Ceil),
+    ("\\rround", Delimiter::Round),
+    ("\\ropen", Delimiter::Interval),
+    ("\\rclosed", Delimiter::Interval),
+];
+
+// Text after which `[` closes an interval rather than opening a bracket, e.g. `[0, 1[ \cup [2, 3]`
+const INTERVAL_FOLLOWERS: [&str; 9] = ["\\cup", "\\cap", "\\setminus", ",", ";", ")", "]", "\\}", "\\right"];
+
+// Rounding operator taking a delimited argument, e.g. `\operatorname{round}(x)`
+const ROUND_OPERATOR: &str = "\\operatorname{round}";
+
+// Ambiguous pipes: `|`, `\vert` for Abs and `\|`, `\Vert` for Norm
+const PIPES: [(&str, Delimiter); 4] = [
+    ("|", Delimiter::Abs),
+    ("\\vert", Delimiter::Abs),
+    ("\\|", Delimiter::Norm),
+    ("\\Vert", Delimiter::Norm),
+];
+
+// Opening delimiter at the head of `chars` and its length, e.g. `\left( x` => (Paren, 6)
+pub fn match_open(chars: &[char]) -> Option<(Delimiter, usize)> {
+    if chars.first() == Some(&'{') {
+        return Some((Delimiter::Group, 1));
+    }
+
+    if let Some((_, len)) = match_environment(chars, "\\begin") {
+        return Some((Delimiter::Env, len));
+    }
+
+    return match_sized(chars, &OPENS);
+}
+
+// Closing delimiter at the head of `chars` and its length, e.g. `\right) x` => (Paren, 7)
+pub fn match_close(chars: &[char]) -> Option<(Delimiter, usize)> {
+    if chars.first() == Some(&'}') {
+        return Some((Delimiter::Group, 1));
+    }
+
+    if let Some((_, len)) = match_environment(chars, "\\end") {
+        return Some((Delimiter::Env, len));
+    }
+
+    return match_sized(chars, &CLOSES);
+}
+
+// `(x + 1)` => true, `(x) + (1)` => false
+pub fn is_single_group(chars: &[char]) -> bool {
+    return match_group(chars) == Some(chars.len());
+}
+
+// Length of the delimited group at the head of `chars`, e.g. `[x](y)` => 3
+pub fn match_group(chars: &[char]) -> Option<usize> {
+    let (open, len) = match_open(chars)?;
+    let mut opens = vec![open];
+    let mut i = len;
+
+    while i < chars.len() {
+        if let Some((close, len)) = match_close(&chars[i..]) {
+            if opens.pop() != Some(close) {
+                return None;
+            }
+
+            i += len;
+
+            if opens.is_empty() {
+                return Some(i);
+            }
+
+            continue;
+        }
+
+        if let Some((open, len)) = match_open(&chars[i..]) {
+            opens.push(open);
+            i += len;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    return None;
+}
+
+// Inside of a single group, e.g. `\left( x + 1 \right)` => (Paren, ` x + 1 `)
+pub fn strip_group(chars: &[char]) -> Option<(Delimiter, String)> {
+    if !is_single_group(chars) {
+        return None;
+    }
+
+    let (delimiter, open_len) = match_open(chars)?;
+    let close_len = (0..chars.len())
+        .find(|&i| match_close(&chars[i..]).map(|(_, len)| i + len) == Some(chars.len()))
+        .map(|i| chars.len() - i)?;
+
+    return Some((delimiter, chars[open_len..chars.len() - close_len].iter().collect()));
+}
+
+// Name and inside of a single environment, e.g. `\begin{pmatrix} 1 \end{pmatrix}` => (`pmatrix`, ` 1 `)
+pub fn strip_environment(chars: &[char]) -> Option<(String, String)> {
+    if !is_single_group(chars) {
+        return None;
+    }
+
+    let (name, open_len) = match_environment(chars, "\\begin")?;
+    let close = (open_len..chars.len())
+        .find(|&i| match_environment(&chars[i..], "\\end").map(|(_, len)| i + len) == Some(chars.len()))?;
+    let (close_name, _) = match_environment(&chars[close..], "\\end")?;
+
+    if name != close_name {
+        return None;
+    }
+
+    return Some((name, chars[open_len..close].iter().collect()));
+}
+
+// Closedness of the ends and inside of a single interval written by `resolve_intervals`
+// e.g. `\lopen 0, 1 \rclosed` => (false, ` 0, 1 `, true)
+pub fn strip_interval(chars: &[char]) -> Option<(bool, String, bool)> {
+    let (delimiter, inner) = strip_group(chars)?;
+
+    if delimiter != Delimiter::Interval {
+        return None;
+    }
+
+    let s = chars.iter().collect::<String>();
+    return Some((s.starts_with("\\lclosed"), inner, s.ends_with("\\rclosed")));
+}
+
+// Rewrite each interval whose brackets do not pair up into internal delimiters
+// e.g. `(0, 1]` => `\lopen  0, 1 \rclosed `, `]0, 1[` => `\lopen  0, 1 \ropen `
+//
+// A reversed bracket is an open end, and `[` closes an interval only before the end or a set operator.
+// Intervals with paired brackets like `(0, 1)` are left to the parser.
+pub fn resolve_intervals(s: &str) -> String {
+    let mut chars = s.chars().collect_vec();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let opener = match match_open(&chars[i..]) {
+            Some((open @ (Delimiter::Paren | Delimiter::Bracket), len)) => Some((open, len)),
+            _ => match match_close(&chars[i..]) {
+                Some((Delimiter::Bracket, len)) => Some((Delimiter::Paren, len)),
+                _ => None,
+            },
+        };
+
+        let (open, open_len) = match opener {
+            Some(opener) if i == 0 || chars[i - 1] != '\\' => opener,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let paired = chars[i + open_len - 1] != ']';
+
+        if let Some((start, close_len, closed)) = match_interval_close(&chars[i + open_len..], open, paired) {
+            let start = i + open_len + start;
+            let close = if closed { " \\rclosed " } else { " \\ropen " };
+            chars.splice(start..start + close_len, close.chars());
+
+            let open = if open == Delimiter::Bracket {
+                "\\lclosed "
+            } else {
+                "\\lopen "
+            };
+            chars.splice(i..i + open_len, open.chars());
+            i += open.chars().count();
+            continue;
+        }
+
+        i += 1;
+    }
+
+    return chars.into_iter().collect();
+}
+
+// Start, length and closedness of the closing bracket of an interval with one comma
+// `paired` is false after a reversed opening bracket like `]`, which pairs with no bracket
+fn match_interval_close(chars: &[char], open: Delimiter, paired: bool) -> Option<(usize, usize, bool)> {
+    let mut opens = Vec::new();
+    let mut commas = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest = &chars[i..];
+
+        if opens.is_empty() {
+            let close = match (match_close(rest), match_open(rest)) {
+                (Some((close @ (Delimiter::Paren | Delimiter::Bracket), len)), _) => {
+                    let is_paired = paired && close == open;
+                    Some((len, close == Delimiter::Bracket, is_paired))
+                }
+                (_, Some((Delimiter::Bracket, len))) if is_interval_end(&rest[len..]) => Some((len, false, false)),
+                _ => None,
+            };
+
+            if let Some((len, closed, is_paired)) = close {
+                return Some((i, len, closed)).filter(|_| commas == 1 && !is_paired);
+            }
+
+            if rest[0] == ',' {
+                commas += 1;
+            }
+        }
+
+        if let Some((close, len)) = match_close(rest) {
+            if opens.pop() != Some(close) {
+                return None;
+            }
+
+            i += len;
+            continue;
+        }
+
+        if let Some((open, len)) = match_open(rest) {
+            opens.push(open);
+            i += len;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    return None;
+}
+
+// Whether `[` before `chars` closes an interval
+fn is_interval_end(chars: &[char]) -> bool {
+    let spaces = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let rest = &chars[spaces..];
+    return rest.is_empty()
+        || INTERVAL_FOLLOWERS
+            .iter()
+            .any(|follower| starts_with_word(rest, follower));
+}
+
+// Rewrite roundings into internal delimiters, e.g. `\lfloor x \rceil` => `\lround x \rround`,
+// `\operatorname{round}(x)` => `\lround x\rround`
+//
+// A floor bracket closed by a ceiling one rounds to the nearest integer.
+pub fn resolve_rounding(s: &str) -> String {
+    let mut chars = s.chars().collect_vec();
+    let mut opens = Vec::new(); // Kind, position and length of each open floor or ceiling bracket
+    let mut i = 0;
+
+    while i < chars.len() {
+        if starts_with_word(&chars[i..], ROUND_OPERATOR) {
+            let start = i + ROUND_OPERATOR.chars().count();
+            let group = start + chars[start..].iter().take_while(|c| c.is_whitespace()).count();
+            let end = group + match_group(&chars[group..]).unwrap_or(0);
+
+            if let Some((_, inner)) = strip_group(&chars[group..end]) {
+                chars.splice(i..end, format!("\\lround {}\\rround ", inner).chars());
+                i += "\\lround".len();
+                continue;
+            }
+        }
+
+        if let Some((open @ (Delimiter::Floor | Delimiter::Ceil), len)) = match_open(&chars[i..]) {
+            opens.push((open, i, len));
+            i += len;
+            continue;
+        }
+
+        if let Some((close @ (Delimiter::Floor | Delimiter::Ceil), len)) = match_close(&chars[i..]) {
+            match opens.pop() {
+                Some((Delimiter::Floor, start, open_len)) if close == Delimiter::Ceil => {
+                    let replace = |range: &[char], from: &str, to: &str| {
+                        return range.iter().collect::<String>().replace(from, to).chars().collect_vec();
+                    };
+                    let close_chars = replace(&chars[i..i + len], "\\rceil", "\\rround");
+                    let open_chars = replace(&chars[start..start + open_len], "\\lfloor", "\\lround");
+                    let end = i + close_chars.len() + open_chars.len() - open_len;
+
+                    // The close first, so that the position of the open stays valid
+                    chars.splice(i..i + len, close_chars);
+                    chars.splice(start..start + open_len, open_chars);
+                    i = end;
+                }
+                _ => i += len,
+            }
+            continue;
+        }
+
+        i += 1;
+    }
+
+    return chars.into_iter().collect();
+}
+
+// Resolve each pipe into an opening or closing one, e.g. `||x| - |y||` => `\lvert\lvert x\rvert - ...`
+//
+// A pipe closes the innermost open pipe of the same kind when it follows an operand like `x`, `)` or `\alpha`,
+// and opens a new one otherwise. `\left|` and `\right|` are always opening and closing.
+// In a set, a pipe after an element followed by pairs of pipes separates the condition, e.g. `\{x | |x| < 1\}`
+pub fn resolve_pipes(s: &str) -> String {
+    let chars = s.chars().collect_vec();
+    let mut opens = Vec::new();
+    let mut sets = Vec::new(); // Number of open pipes at each open set brace
+    let mut result = String::new();
+    let mut after_operand = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let size_len = match_size(&chars[i..]);
+        let size = chars[i..i + size_len].iter().collect::<String>();
+        let spaces = match size_len {
+            0 => 0,
+            _ => chars[i + size_len..].iter().take_while(|c| c.is_whitespace()).count(),
+        };
+        let rest = &chars[i + size_len + spaces..];
+
+        if let Some((pipe, len)) = PIPES
+            .iter()
+            .find(|(pipe, _)| starts_with_word(rest, pipe))
+            .map(|(pipe, delimiter)| (*delimiter, pipe.chars().count()))
+        {
+            let is_separator = size.is_empty()
+                && pipe == Delimiter::Abs
+                && after_operand
+                && sets.last() == Some(&opens.len())
+                && count_set_pipes(&chars[i + 1..]) % 2 == 0;
+
+            if is_separator {
+                result.push_str("\\mid ");
+                after_operand = false;
+                i += 1;
+                continue;
+            }
+
+            let is_close = match size.as_str() {
+                "\\left" | "\\bigl" | "\\Bigl" | "\\biggl" | "\\Biggl" => false,
+                "\\right" | "\\bigr" | "\\Bigr" | "\\biggr" | "\\Biggr" => true,
+                _ => after_operand && opens.last() == Some(&pipe),
+            };
+
+            if is_close {
+                opens.pop();
+            } else {
+                opens.push(pipe);
+            }
+
+            result.push_str(match (pipe, is_close) {
+                (Delimiter::Abs, false) => "\\lvert ",
+                (Delimiter::Abs, true) => "\\rvert ",
+                (_, false) => "\\lVert ",
+                (_, true) => "\\rVert ",
+            });
+            after_operand = is_close;
+            i += size_len + spaces + len;
+            continue;
+        }
+
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+
+        // `\alpha`, `\mathrm` or `\cdot`
+        let len = if c == '\\' {
+            (1 + chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .count()
+                .max(1))
+            .min(chars.len() - i)
+        } else {
+            1
+        };
+        let token = chars[i..i + len].iter().collect::<String>();
+
+        match token.as_str() {
+            "\\{" | "\\lbrace" => sets.push(opens.len()),
+            "\\}" | "\\rbrace" => {
+                sets.pop();
+            }
+            _ => {}
+        }
+
+        after_operand = c.is_alphanumeric()
+            || match_close(&chars[i..]).is_some()
+            || (len > 1 && token[1..].chars().all(|c| c.is_ascii_alphabetic()) && symbol::is_symbol_macro(&token[1..]));
+
+        result.push_str(&token);
+        i += len;
+    }
+
+    return result;
+}
+
+// Pipes before the end of the current set, outside of nested sets, e.g. `|x| < 1\} |y|` => 2
+fn count_set_pipes(chars: &[char]) -> usize {
+    let mut depth = 0;
+    let mut count = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('\\', Some('{')) => depth += 1,
+            ('\\', Some('}')) if depth == 0 => break,
+            ('\\', Some('}')) => depth -= 1,
+            ('\\', Some(_)) => {}
+            ('|', _) if depth == 0 => count += 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+
+        // Skip the escaped character, e.g. the second `|` of `\|`
+        i += if chars[i] == '\\' { 2 } else { 1 };
+    }
+
+    return count;
+}
+
+// `()`, `\left( \right)` and `(()[])` have no content
+pub fn is_only_delimiters(chars: &[char]) -> bool {
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match match_open(&chars[i..]).or_else(|| match_close(&chars[i..])) {
+            Some((_, len)) => i += len,
+            None => return false,
+        }
+    }
+
+    return !chars.is_empty();
+}
+
+fn match_sized(chars: &[char], delimiters: &[(&str, Delimiter)]) -> Option<(Delimiter, usize)> {
+    let size_len = match_size(chars);
+    let rest = &chars[size_len..];
+    let spaces = rest.iter().take_while(|c| c.is_whitespace()).count();
+
+    for (s, delimiter) in delimiters {
+        let delimiter_chars = s.chars().collect_vec();
+
+        // `\{` after a size prefix, or spaces only after a size prefix
+        if size_len > 0 && rest[spaces..].starts_with(&delimiter_chars) {
+            return Some((*delimiter, size_len + spaces + delimiter_chars.len()));
+        }
+
+        if size_len == 0 && chars.starts_with(&delimiter_chars) && !is_longer_word(chars, delimiter_chars.len()) {
+            return Some((*delimiter, delimiter_chars.len()));
+        }
+    }
+
+    return None;
+}
+
+// `\begin{pmatrix}` with `\begin` => (`pmatrix`, 16)
+fn match_environment(chars: &[char], command: &str) -> Option<(String, usize)> {
+    if !starts_with_word(chars, command) {
+        return None;
+    }
+
+    let i = command.len();
+    let spaces = chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+
+    if chars.get(i + spaces) != Some(&'{') {
+        return None;
+    }
+
+    let len = chars[i + spaces..].iter().position(|&c| c == '}')?;
+    let name = chars[i + spaces + 1..i + spaces + len].iter().collect::<String>();
+    return Some((name.trim().to_string(), i + spaces + len + 1));
+}
+
+// Length of a size prefix like `\left` or `\Bigl`
+fn match_size(chars: &[char]) -> usize {
+    if chars.first() != Some(&'\\') {
+        return 0;
+    }
+
+    let name_len = chars[1..].iter().take_while(|c| c.is_ascii_alphabetic()).count();
+    let name = chars[1..1 + name_len].iter().collect::<String>();
+
+    if SIZES.contains(&name.as_str()) {
+        return 1 + name_len;
+    }
+
+    return 0;
+}
+
+// `\vert x` starts with `\vert`, but `\vertex` does not
+fn starts_with_word(chars: &[char], word: &str) -> bool {
+    let word_chars = word.chars().collect_vec();
+    return chars.starts_with(&word_chars) && !is_longer_word(chars, word_chars.len());
+}
+
+// `\langlex` is not `\langle` followed by `x`
+fn is_longer_word(chars: &[char], len: usize) -> bool {
+    return chars[0] == '\\'
+        && chars[len - 1].is_ascii_alphabetic()
+        && chars.get(len).map_or(false, |c| c.is_ascii_alphabetic());
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::delimiter::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        return s.chars().collect_vec();
+    }
+
+    #[test]
+    fn match_open_test() {
+        assert_eq!(match_open(&chars("(x")), Some((Delimiter::Paren, 1)));
+        assert_eq!(match_open(&chars("\\left( x")), Some((Delimiter::Paren, 6)));
+        assert_eq!(match_open(&chars("\\left [ x")), Some((Delimiter::Bracket, 7)));
+        assert_eq!(match_open(&chars("\\bigl\\{ x")), Some((Delimiter::Brace, 7)));
+        assert_eq!(match_open(&chars("{x")), Some((Delimiter::Group, 1)));
+        assert_eq!(match_open(&chars("\\langle x")), Some((Delimiter::Angle, 7)));
+        assert_eq!(match_open(&chars("x(")), None);
+        assert_eq!(match_open(&chars("\\leftarrow")), None);
+    }
+
+    #[test]
+    fn match_close_test() {
+        assert_eq!(match_close(&chars(")")), Some((Delimiter::Paren, 1)));
+        assert_eq!(match_close(&chars("\\right)")), Some((Delimiter::Paren, 7)));
+        assert_eq!(match_close(&chars("\\Bigr]")), Some((Delimiter::Bracket, 6)));
+        assert_eq!(match_close(&chars("\\}")), Some((Delimiter::Brace, 2)));
+        assert_eq!(match_close(&chars("(")), None);
+    }
+
+    #[test]
+    fn resolve_rounding_test() {
+        assert_eq!(resolve_rounding("\\lfloor x \\rceil"), "\\lround x \\rround");
+        assert_eq!(
+            resolve_rounding("\\lfloor \\lceil x \\rceil \\rfloor"),
+            "\\lfloor \\lceil x \\rceil \\rfloor"
+        );
+        assert_eq!(
+            resolve_rounding("\\left\\lfloor \\lfloor x \\rfloor \\right\\rceil"),
+            "\\left\\lround \\lfloor x \\rfloor \\right\\rround"
+        );
+        assert_eq!(
+            resolve_rounding("\\operatorname{round}(x + 1)"),
+            "\\lround x + 1\\rround "
+        );
+        assert_eq!(
+            resolve_rounding("\\operatorname{round}(x) + 1"),
+            "\\lround x\\rround  + 1"
+        );
+        assert_eq!(
+            resolve_rounding("2 \\operatorname{round} \\left(x\\right) \\cdot \\operatorname{round}(y)"),
+            "2 \\lround x\\rround  \\cdot \\lround y\\rround "
+        );
+        assert_eq!(resolve_rounding("\\operatorname{round} x"), "\\operatorname{round} x");
+    }
+
+    #[test]
+    fn match_group_test() {
+        assert_eq!(match_group(&chars("(x)y")), Some(3));
+        assert_eq!(match_group(&chars("\\left[(x)\\right]y")), Some(16));
+        assert_eq!(match_group(&chars("(x]")), None);
+        assert_eq!(match_group(&chars("(x")), None);
+    }
+
+    #[test]
+    fn strip_group_test() {
+        assert_eq!(strip_group(&chars("(x)")), Some((Delimiter::Paren, "x".to_string())));
+        assert_eq!(
+            strip_group(&chars("\\left( x \\right)")),
+            Some((Delimiter::Paren, " x ".to_string()))
+        );
+        assert_eq!(
+            strip_group(&chars("\\{x\\}")),
+            Some((Delimiter::Brace, "x".to_string()))
+        );
+        assert_eq!(
+            strip_group(&chars("\\lvert x \\rvert")),
+            Some((Delimiter::Abs, " x ".to_string()))
+        );
+        assert_eq!(strip_group(&chars("(x)(y)")), None);
+    }
+
+    #[test]
+    fn environment_test() {
+        let matrix = chars("\\begin{pmatrix} 1 & (2 \\\\ 3) \\end{pmatrix}");
+        assert_eq!(match_open(&matrix), Some((Delimiter::Env, 15)));
+        assert_eq!(match_close(&chars("\\end {bmatrix}")), Some((Delimiter::Env, 14)));
+        assert_eq!(match_group(&matrix), Some(matrix.len()));
+        assert_eq!(
+            strip_environment(&matrix),
+            Some(("pmatrix".to_string(), " 1 & (2 \\\\ 3) ".to_string()))
+        );
+        assert_eq!(strip_environment(&chars("\\begin{pmatrix} 1 \\end{bmatrix}")), None);
+        assert_eq!(match_open(&chars("\\beginx")), None);
+    }
+
+    #[test]
+    fn is_only_delimiters_test() {
+        assert!(is_only_delimiters(&chars("()")));
+        assert!(is_only_delimiters(&chars("\\left( \\right)")));
+        assert!(is_only_delimiters(&chars("(()[])")));
+        assert!(!is_only_delimiters(&chars("(x)")));
+        assert!(!is_only_delimiters(&chars("")));
+    }
+
+    #[test]
+    fn resolve_intervals_test() {
+        assert_eq!(resolve_intervals("(0, 1]"), "\\lopen 0, 1 \\rclosed ");
+        assert_eq!(resolve_intervals("]0, 1["), "\\lopen 0, 1 \\ropen ");
+        assert_eq!(resolve_intervals("\\left[ 0, 1 \\right)"), "\\lclosed  0, 1  \\ropen ");
+        assert_eq!(
+            resolve_intervals("[0, 1[ \\cup ]2, 3]"),
+            "\\lclosed 0, 1 \\ropen  \\cup \\lopen 2, 3 \\rclosed "
+        );
+        assert_eq!(resolve_intervals("(0, f(1)]"), "\\lopen 0, f(1) \\rclosed ");
+        assert_eq!(resolve_intervals("(0, 1) \\cup [2, 3]"), "(0, 1) \\cup [2, 3]");
+        assert_eq!(resolve_intervals("\\sqrt[3]{x} (x]"), "\\sqrt[3]{x} (x]");
+        assert_eq!(
+            strip_interval(&chars("\\lopen 0, 1 \\rclosed")),
+            Some((false, " 0, 1 ".to_string(), true))
+        );
+        assert_eq!(strip_interval(&chars("(0, 1)")), None);
+    }
+
+    #[test]
+    fn resolve_pipes_test() {
+        assert_eq!(resolve_pipes("|x|"), "\\lvert x\\rvert ");
+        assert_eq!(resolve_pipes("|x|y|z|"), "\\lvert x\\rvert y\\lvert z\\rvert ");
+        assert_eq!(resolve_pipes("|-x|"), "\\lvert -x\\rvert ");
+        assert_eq!(resolve_pipes("\\|v\\|"), "\\lVert v\\rVert ");
+        assert_eq!(resolve_pipes("|\\alpha|"), "\\lvert \\alpha\\rvert ");
+        assert_eq!(resolve_pipes("\\left| x \\right|"), "\\lvert  x \\rvert ");
+    }
+
+    #[test]
+    fn resolve_set_pipes_test() {
+        assert_eq!(resolve_pipes("\\{x | x > 0\\}"), "\\{x \\mid  x > 0\\}");
+        assert_eq!(
+            resolve_pipes("\\{x | |x| < 1\\}"),
+            "\\{x \\mid  \\lvert x\\rvert  < 1\\}"
+        );
+        assert_eq!(resolve_pipes("\\{2|x|\\}"), "\\{2\\lvert x\\rvert \\}");
+        assert_eq!(
+            resolve_pipes("\\{|x| | x \\in A\\}"),
+            "\\{\\lvert x\\rvert  \\mid  x \\in A\\}"
+        );
+    }
+
+    #[test]
+    fn resolve_nested_pipes_test() {
+        assert_eq!(
+            resolve_pipes("||x| - |y||"),
+            "\\lvert \\lvert x\\rvert  - \\lvert y\\rvert \\rvert "
+        );
+        assert_eq!(resolve_pipes("\\||x|\\|"), "\\lVert \\lvert x\\rvert \\rVert ");
+        assert_eq!(
+            resolve_pipes("|2 \\cdot |x||"),
+            "\\lvert 2 \\cdot \\lvert x\\rvert \\rvert "
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use itertools::Itertools;
+
+use crate::formula::{Bound, Formula};
+
+impl Formula {
+    // Derivative with respect to `var`, e.g. `x \sin x` => `\sin x + x \cos x`
+    // Trivial terms such as `0 + 1 \cdot x` are simplified while building the result
+    pub fn diff(&self, var: &str) -> Self {
+        return match self {
+            Self::TS(name) if name == var => one(),
+            Self::TS(_) | Self::Const(_) | Self::Operator(_) | Self::Text(_) | Self::Empty => zero(),
+
+            // -f' for -f
+            Self::Neg(formula) => neg(formula.diff(var)),
+
+            // ±f' for ±f
+            Self::PlusMinus(formula) => Self::PlusMinus(Box::new(formula.diff(var))),
+            Self::MinusPlus(formula) => Self::MinusPlus(Box::new(formula.diff(var))),
+
+            // -f' / f^2 for 1 / f
+            Self::Recip(formula) => neg(mul(vec![
+                formula.diff(var),
+                recip(pow((**formula).clone(), Self::TS("2".to_string()))),
+            ])),
+
+            // f' + g'
+            Self::Add(formulas) => add(formulas.iter().map(|f| f.diff(var)).collect_vec()),
+
+            // f' g + f g'
+            Self::Mul(formulas) => add((0..formulas.len())
+                .map(|n| {
+                    let mut factors = formulas.clone();
+                    factors[n] = formulas[n].diff(var);
+                    mul(factors)
+                })
+                .collect_vec()),
+
+            // f' f / |f|
+            Self::Abs(formula) | Self::Norm(formula) => {
+                mul(vec![formula.diff(var), (**formula).clone(), recip(self.clone())])
+            }
+
+            Self::Pow(base, exponent) => Self::diff_pow(base, exponent, var),
+
+            // \overline{f'} for \overline{f}
+            Self::Conj(formula) => Self::Conj(Box::new(formula.diff(var))),
+
+            // A subscripted or overlined symbol such as `x_1` or `\bar x` is another variable
+            Self::Subscript(_, _) | Self::Overline(_) => zero(),
+
+            // \sum_k f' for \sum_k f
+            Self::Sum(bound, body) if bound.var != var => Self::Sum(bound.clone(), Box::new(body.diff(var))),
+
+            // \prod_k f \cdot \sum_k f' / f
+            Self::Prod(bound, body) if bound.var != var => mul(vec![
+                self.clone(),
+                Self::Sum(
+                    bound.clone(),
+                    Box::new(mul(vec![body.diff(var), recip((**body).clone())])),
+                ),
+            ]),
+
+            Self::Integral(name, bound, body) => self.diff_integral(name, bound, body, var),
+
+            // f'(g) g'
+            Self::Func(name, formula) => mul(vec![Self::diff_function(name, formula), formula.diff(var)]),
+
+            // \frac{d^{n+1} f}{dx^{n+1}} for \frac{d^n f}{dx^n}
+            Self::Derivative(formula, v, order) if v == var => Self::Derivative(formula.clone(), v.clone(), order + 1),
+            Self::Derivative(_, _, _) => Self::Derivative(Box::new(self.clone()), var.to_string(), 1),
+
+            // Kept as it is, since a limit and a derivative do not commute in general, nor a value at a point
+            Self::Limit(_, _, _) | Self::At(_, _) if depends_on(self, var) => {
+                Self::Derivative(Box::new(self.clone()), var.to_string(), 1)
+            }
+
+            // Kept as it is, since they are defined on integers only or jump at integers
+            Self::Factorial(_)
+            | Self::Binomial(_, _)
+            | Self::Permutation(_, _)
+            | Self::Floor(_)
+            | Self::Ceil(_)
+            | Self::Round(_)
+            | Self::Mod(_, _)
+                if depends_on(self, var) =>
+            {
+                Self::Derivative(Box::new(self.clone()), var.to_string(), 1)
+            }
+            Self::Factorial(_)
+            | Self::Binomial(_, _)
+            | Self::Permutation(_, _)
+            | Self::Floor(_)
+            | Self::Ceil(_)
+            | Self::Round(_)
+            | Self::Mod(_, _) => zero(),
+
+            // Element-wise
+            Self::Matrix(_) | Self::Set(_) | Self::Tuple(_) | Self::List(_) => {
+                self.clone().map_children(|f| f.diff(var))
+            }
+
+            // Kept as it is, since sets and propositions have no derivative in general
+            Self::SetBuilder(_, _, _)
+            | Self::Union(_)
+            | Self::Intersection(_)
+            | Self::Difference(_, _)
+            | Self::In(_, _)
+            | Self::Interval(_, _, _, _)
+            | Self::Not(_)
+            | Self::And(_)
+            | Self::Or(_)
+            | Self::Xor(_)
+            | Self::Implies(_, _)
+            | Self::Iff(_, _)
+            | Self::Relation(_, _, _)
+            | Self::Congruence(_, _, _)
+            | Self::Forall(_, _)
+            | Self::Exists(_, _) => Self::Derivative(Box::new(self.clone()), var.to_string(), 1),
+
+            // `var` is bound, e.g. `\sum_x x`, or absent
+            Self::Sum(_, _) | Self::Prod(_, _) | Self::Limit(_, _, _) | Self::At(_, _) => zero(),
+        };
+    }
+
+    fn diff_pow(base: &Self, exponent: &Self, var: &str) -> Self {
+        let base_depends = depends_on(base, var);
+        let exponent_depends = depends_on(exponent, var);
+
+        // n f^(n - 1) f'
+        if !exponent_depends {
+            return mul(vec![
+                exponent.clone(),
+                pow(base.clone(), minus_one(exponent)),
+                base.diff(var),
+            ]);
+        }
+
+        let power = pow(base.clone(), exponent.clone());
+
+        // a^g \ln a g', e.g. e^g g'
+        if !base_depends {
+            return mul(vec![power, ln(base), exponent.diff(var)]);
+        }
+
+        // f^g (g' \ln f + g f' / f)
+        return mul(vec![
+            power,
+            add(vec![
+                mul(vec![exponent.diff(var), ln(base)]),
+                mul(vec![exponent.clone(), base.diff(var), recip(base.clone())]),
+            ]),
+        ]);
+    }
+
+    // `\int f dx` => f, `\int_a^b f(t, x) dt` => f(b, x) b' - f(a, x) a' + \int_a^b f_x dt
+    fn diff_integral(&self, name: &str, bound: &Bound, body: &Self, var: &str) -> Self {
+        if bound.var == var {
+            return match (&bound.lower, &bound.upper, &bound.region) {
+                (None, None, None) => body.clone(),
+                _ => zero(),
+            };
+        }
+
+        // A region depending on the variable, e.g. `\oint_{|z| = r}` for `r`
+        if bound
+            .region
+            .as_ref()
+            .map_or(false, |region| region.free_vars().contains(var))
+        {
+            return Self::Derivative(Box::new(self.clone()), var.to_string(), 1);
+        }
+
+        let body_diff = body.diff(var);
+        let mut terms = Vec::new();
+
+        if !is_zero(&body_diff) {
+            terms.push(Self::Integral(
+                name.to_string(),
+                Box::new(bound.clone()),
+                Box::new(body_diff),
+            ));
+        }
+
+        if let Some(upper) = &bound.upper {
+            terms.push(mul(vec![body.clone().substitute(&bound.var, upper), upper.diff(var)]));
+        }
+
+        if let Some(lower) = &bound.lower {
+            terms.push(neg(mul(vec![
+                body.clone().substitute(&bound.var, lower),
+                lower.diff(var),
+            ])));
+        }
+
+        return add(terms);
+    }
+
+    // Derivative of a function at its argument, e.g. `\sin` and `u` => `\cos u`
+    fn diff_function(name: &str, arg: &Self) -> Self {
+        let func = |name: &str| Self::Func(name.to_string(), Box::new(arg.clone()));
+        let square = |f: Self| pow(f, Self::TS("2".to_string()));
+        let one_minus_square = add(vec![one(), neg(square(arg.clone()))]);
+
+        return match name {
+            "\\sin" => func("\\cos"),
+            "\\cos" => neg(func("\\sin")),
+            "\\tan" => recip(square(func("\\cos"))),
+            "\\cot" => neg(recip(square(func("\\sin")))),
+            "\\sec" => mul(vec![func("\\sec"), func("\\tan")]),
+            "\\csc" => neg(mul(vec![func("\\csc"), func("\\cot")])),
+            "\\arcsin" => recip(pow(one_minus_square, half())),
+            "\\arccos" => neg(recip(pow(one_minus_square, half()))),
+            "\\arctan" => recip(add(vec![one(), square(arg.clone())])),
+            "\\sinh" => func("\\cosh"),
+            "\\cosh" => func("\\sinh"),
+            "\\tanh" => recip(square(func("\\cosh"))),
+            "\\exp" => func("\\exp"),
+            // for a real argument
+            "\\Re" => one(),
+            "\\Im" | "\\arg" => zero(),
+            _ => recip(arg.clone()),
+        };
+    }
+}
+
+fn zero() -> Formula {
+    return Formula::TS("0".to_string());
+}
+
+fn one() -> Formula {
+    return Formula::TS("1".to_string());
+}
+
+fn half() -> Formula {
+    return recip(Formula::TS("2".to_string()));
+}
+
+fn is_zero(formula: &Formula) -> bool {
+    return matches!(formula, Formula::TS(s) if s == "0");
+}
+
+fn is_one(formula: &Formula) -> bool {
+    return matches!(formula, Formula::TS(s) if s == "1");
+}
+
+fn depends_on(formula: &Formula, var: &str) -> bool {
+    return formula.free_vars().contains(var);
+}
+
+// `0 + f` => `f`
+fn add(formulas: Vec<Formula>) -> Formula {
+    let mut terms = formulas.into_iter().filter(|f| !is_zero(f)).collect_vec();
+
+    return match terms.len() {
+        0 => zero(),
+        1 => terms.remove(0),
+        _ => Formula::Add(terms),
+    };
+}
+
+// `0 f` => `0`, `1 f` => `f`, `(-f) g` => `-(f g)`
+fn mul(formulas: Vec<Formula>) -> Formula {
+    if formulas.iter().any(is_zero) {
+        return zero();
+    }
+
+    let mut negative = false;
+    let mut factors = formulas
+        .into_iter()
+        .map(|f| match f {
+            Formula::Neg(f) => {
+                negative = !negative;
+                *f
+            }
+            f => f,
+        })
+        .filter(|f| !is_one(f))
+        .collect_vec();
+
+    let product = match factors.len() {
+        0 => one(),
+        1 => factors.remove(0),
+        _ => Formula::Mul(factors),
+    };
+
+    return if negative { neg(product) } else { product };
+}
+
+// `-0` => `0`, `--f` => `f`
+fn neg(formula: Formula) -> Formula {
+    return match formula {
+        f if is_zero(&f) => f,
+        Formula::Neg(f) => *f,
+        f => Formula::Neg(Box::new(f)),
+    };
+}
+
+fn recip(formula: Formula) -> Formula {
+    return Formula::Recip(Box::new(formula));
+}
+
+// `f^1` => `f`, `f^0` => `1`
+fn pow(base: Formula, exponent: Formula) -> Formula {
+    if is_one(&exponent) {
+        return base;
+    }
+
+    if is_zero(&exponent) {
+        return one();
+    }
+
+    return Formula::Pow(Box::new(base), Box::new(exponent));
+}
+
+// `e` => 1, `a` => `\ln a`
+fn ln(formula: &Formula) -> Formula {
+    if matches!(formula, Formula::Const(s) if s == "e") {
+        return one();
+    }
+
+    return Formula::Func("\\ln".to_string(), Box::new(formula.clone()));
+}
+
+// `3` => `2`, `n` => `n - 1`
+fn minus_one(formula: &Formula) -> Formula {
+    return match formula {
+        Formula::TS(s) if Formula::is_number(s) => Formula::number(s.parse::<f64>().unwrap_or(0.0) - 1.0),
+        Formula::Neg(f) => match &**f {
+            Formula::TS(s) if Formula::is_number(s) => Formula::number(-s.parse::<f64>().unwrap_or(0.0) - 1.0),
+            _ => add(vec![formula.clone(), neg(one())]),
+        },
+        _ => add(vec![formula.clone(), neg(one())]),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formula::Formula;
+
+    fn diff(s: &str, var: &str) -> Formula {
+        return Formula::parse(s).unwrap().diff(var);
+    }
+
+    fn parse(s: &str) -> Formula {
+        return Formula::parse(s).unwrap();
+    }
+
+    #[test]
+    fn diff_polynomial_test() {
+        assert_eq!(diff("x^3", "x"), parse("3 x^2"));
+        assert_eq!(diff("x^2 + 3x + 1", "x"), parse("2x + 3"));
+        assert_eq!(diff("a x", "x"), parse("a"));
+        assert_eq!(diff("y", "x"), parse("0"));
+        assert_eq!(diff("x^{-1}", "x"), parse("-x^{-2}"));
+    }
+
+    #[test]
+    fn diff_product_quotient_test() {
+        assert_eq!(diff("x \\sin x", "x"), parse("\\sin x + x \\cos x"));
+        assert_eq!(diff("1 / x", "x"), parse("-1 / x^2"));
+    }
+
+    #[test]
+    fn diff_chain_test() {
+        assert_eq!(diff("\\sin(x^2)", "x"), parse("\\cos(x^2) \\cdot 2x"));
+        assert_eq!(diff("e^{2x}", "x"), parse("e^{2x} \\cdot 2"));
+        assert_eq!(diff("\\ln x", "x"), parse("1 / x"));
+        assert_eq!(diff("\\sqrt{x}", "x"), parse("\\frac{1}{2} x^{-0.5}"));
+    }
+
+    #[test]
+    fn diff_integral_test() {
+        assert_eq!(diff("\\int \\cos t dt", "t"), parse("\\cos t"));
+        assert_eq!(diff("\\int_0^x t^2 dt", "x"), parse("x^2"));
+    }
+}
@@ -15,3 +15,33 @@ impl error::Error for ParseFormulaError {
         None
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ParseConfigError;
+
+impl fmt::Display for ParseConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse a config")
+    }
+}
+
+impl error::Error for ParseConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseMacroError;
+
+impl fmt::Display for ParseMacroError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse a macro definition")
+    }
+}
+
+impl error::Error for ParseMacroError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
@@ -0,0 +1,632 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::config::Config;
+use crate::formula::{Formula, Side};
+use crate::integer;
+use crate::numeric;
+use crate::symbol::Constant;
+
+// Significant digits kept when a folded value is written back as a terminal symbol
+const FOLD_PRECISION: usize = 12;
+
+// Sums and products with more terms than this are not evaluated
+const MAX_TERMS: f64 = 100000.0;
+
+// Quadrature gives up after evaluating the body this many times
+const MAX_EVALUATIONS: usize = 1 << 16;
+
+// Error relative to the value at which quadrature accepts an interval, tighter than the comparison
+const QUADRATURE_TOLERANCE: f64 = 1e-10;
+
+// Limits are approached by the steps LIMIT_STEP / 2^k for k < LIMIT_STEPS
+const LIMIT_STEP: f64 = 0.1;
+const LIMIT_STEPS: usize = 40;
+
+// Number of latest values extrapolated to the limit
+const LIMIT_ORDER: usize = 5;
+
+// Agreement of the best successive extrapolations at which a limit is accepted
+const LIMIT_TOLERANCE: f64 = numeric::TOLERANCE;
+
+// Agreement of the left and right limits
+const SIDES_TOLERANCE: f64 = 1e-8;
+
+impl Formula {
+    // Evaluate numerically with `vars` bound to values, e.g. `2 x + \pi` with x = 1 => 5.14...
+    pub fn eval(&self, config: &Config, vars: &BTreeMap<String, f64>) -> Option<f64> {
+        return Self::eval_with_constants(self, &config.constants, vars);
+    }
+
+    fn eval_with_constants(&self, constants: &BTreeMap<String, Constant>, vars: &BTreeMap<String, f64>) -> Option<f64> {
+        return match self {
+            Self::TS(s) if Self::is_number(s) => s.parse::<f64>().ok(),
+            Self::TS(s) => vars.get(s).copied(),
+            Self::Const(s) => match constants.get(s) {
+                Some(Constant::Real(value)) => Some(*value),
+                _ => None,
+            },
+            Self::Neg(formula) => formula.eval_with_constants(constants, vars).map(|v| -v),
+            Self::Recip(formula) => match formula.eval_with_constants(constants, vars) {
+                Some(v) if v != 0.0 => Some(1.0 / v),
+                _ => None,
+            },
+            Self::Abs(formula) | Self::Norm(formula) => formula.eval_with_constants(constants, vars).map(f64::abs),
+            // A real number is its own conjugate
+            Self::Conj(formula) => formula.eval_with_constants(constants, vars),
+            Self::Add(formulas) => formulas
+                .iter()
+                .map(|f| f.eval_with_constants(constants, vars))
+                .sum::<Option<f64>>(),
+            Self::Mul(formulas) => formulas
+                .iter()
+                .map(|f| f.eval_with_constants(constants, vars))
+                .product::<Option<f64>>(),
+            Self::Pow(base, exponent) => {
+                let value = base
+                    .eval_with_constants(constants, vars)?
+                    .powf(exponent.eval_with_constants(constants, vars)?);
+                Some(value).filter(|v| v.is_finite())
+            }
+            // Exact values at integers, e.g. `5!` => 120
+            Self::Factorial(n) => integer::factorial(&integer::from_f64(n.eval_with_constants(constants, vars)?)?)
+                .map(|v| v.to_f64())
+                .filter(|v| v.is_finite()),
+            Self::Binomial(n, k) => integer::binomial(
+                &integer::from_f64(n.eval_with_constants(constants, vars)?)?,
+                &integer::from_f64(k.eval_with_constants(constants, vars)?)?,
+            )
+            .map(|v| v.to_f64())
+            .filter(|v| v.is_finite()),
+            Self::Permutation(n, k) => integer::permutation(
+                &integer::from_f64(n.eval_with_constants(constants, vars)?)?,
+                &integer::from_f64(k.eval_with_constants(constants, vars)?)?,
+            )
+            .map(|v| v.to_f64())
+            .filter(|v| v.is_finite()),
+            Self::Floor(formula) => formula.eval_with_constants(constants, vars).map(f64::floor),
+            Self::Ceil(formula) => formula.eval_with_constants(constants, vars).map(f64::ceil),
+            Self::Round(formula) => formula.eval_with_constants(constants, vars).map(|x| (x + 0.5).floor()),
+            // With the sign of the modulus, e.g. `(-7) \bmod 3` => 2, and none for a zero modulus
+            Self::Mod(a, n) => {
+                let (a, n) = (
+                    a.eval_with_constants(constants, vars)?,
+                    n.eval_with_constants(constants, vars)?,
+                );
+                Some(a - n * (a / n).floor()).filter(|v| v.is_finite())
+            }
+            Self::Sum(_, _) => self.eval_big_operator(constants, vars, |values| values.iter().sum()),
+            Self::Prod(_, _) => self.eval_big_operator(constants, vars, |values| values.iter().product()),
+            Self::Integral(_, _, _) => self.eval_integral(constants, vars),
+            Self::Limit(_, _, _) => self.eval_limit(constants, vars),
+            // e.g. `f'(2)` with f = x^2 => 4
+            Self::At(bound, body) => {
+                let mut vars = vars.clone();
+                let point = bound.lower.as_ref()?.eval_with_constants(constants, &vars)?;
+                vars.insert(bound.var.clone(), point);
+                body.eval_with_constants(constants, &vars)
+            }
+            // Explicit formulas of the variable only, e.g. `\frac{d}{dx} x^2`
+            Self::Derivative(formula, var, order) if formula.free_vars().iter().all(|v| v == var) => (0..*order)
+                .fold((**formula).clone(), |f, _| f.diff(var))
+                .eval_with_constants(constants, vars),
+            Self::Derivative(_, _, _) => None,
+            Self::Func(name, formula) => {
+                Self::apply(name, formula.eval_with_constants(constants, vars)?).filter(|v| v.is_finite())
+            }
+            Self::Subscript(_, _)
+            | Self::Overline(_)
+            | Self::Operator(_)
+            | Self::Matrix(_)
+            | Self::Set(_)
+            | Self::Tuple(_)
+            | Self::List(_)
+            | Self::PlusMinus(_)
+            | Self::MinusPlus(_)
+            | Self::SetBuilder(_, _, _)
+            | Self::Union(_)
+            | Self::Intersection(_)
+            | Self::Difference(_, _)
+            | Self::In(_, _)
+            | Self::Interval(_, _, _, _)
+            | Self::Not(_)
+            | Self::And(_)
+            | Self::Or(_)
+            | Self::Xor(_)
+            | Self::Implies(_, _)
+            | Self::Iff(_, _)
+            | Self::Relation(_, _, _)
+            | Self::Congruence(_, _, _)
+            | Self::Forall(_, _)
+            | Self::Exists(_, _)
+            | Self::Text(_)
+            | Self::Empty => None,
+        };
+    }
+
+    // `\sin` and 0 => 0
+    pub(crate) fn apply(name: &str, x: f64) -> Option<f64> {
+        return match name {
+            "\\sin" => Some(x.sin()),
+            "\\cos" => Some(x.cos()),
+            "\\tan" => Some(x.tan()),
+            "\\cot" => Some(1.0 / x.tan()),
+            "\\sec" => Some(1.0 / x.cos()),
+            "\\csc" => Some(1.0 / x.sin()),
+            "\\arcsin" => Some(x.asin()),
+            "\\arccos" => Some(x.acos()),
+            "\\arctan" => Some(x.atan()),
+            "\\sinh" => Some(x.sinh()),
+            "\\cosh" => Some(x.cosh()),
+            "\\tanh" => Some(x.tanh()),
+            "\\exp" => Some(x.exp()),
+            "\\ln" | "\\log" => Some(x.ln()),
+            "\\Re" => Some(x),
+            "\\Im" => Some(0.0),
+            "\\arg" if x > 0.0 => Some(0.0),
+            "\\arg" if x < 0.0 => Some(std::f64::consts::PI),
+            _ => None,
+        };
+    }
+
+    // `\sum_{k=1}^{3} k` => 1 + 2 + 3
+    fn eval_big_operator(
+        &self,
+        constants: &BTreeMap<String, Constant>,
+        vars: &BTreeMap<String, f64>,
+        combine: fn(&[f64]) -> f64,
+    ) -> Option<f64> {
+        let (bound, body) = self.binder()?;
+        let terms = match &bound.region {
+            // Over the elements of a finite set, e.g. `\sum_{k \in \{1, 2, 4\}} k` => 7
+            Some(Self::Set(elements)) => elements
+                .iter()
+                .map(|e| e.eval_with_constants(constants, vars))
+                .collect::<Option<Vec<_>>>()?,
+            Some(_) => return None,
+            None => {
+                let lower = bound.lower.as_ref()?.eval_with_constants(constants, vars)?;
+                let upper = bound.upper.as_ref()?.eval_with_constants(constants, vars)?;
+
+                if lower.fract() != 0.0 || upper.fract() != 0.0 || upper - lower > MAX_TERMS {
+                    return None;
+                }
+
+                (lower as i64..=upper as i64).map(|k| k as f64).collect_vec()
+            }
+        };
+
+        let mut vars = vars.clone();
+        let mut values = Vec::new();
+
+        for k in terms {
+            vars.insert(bound.var.clone(), k);
+            values.push(body.eval_with_constants(constants, &vars)?);
+        }
+
+        return Some(combine(&values));
+    }
+
+    // `\int_0^1 x^2 dx` => 0.333... by adaptive Simpson's rule, which subdivides around kinks like `|x - 0.3|`
+    // Regions and contours like `\oint_C` are not evaluated
+    fn eval_integral(&self, constants: &BTreeMap<String, Constant>, vars: &BTreeMap<String, f64>) -> Option<f64> {
+        let (bound, body) = match self {
+            Self::Integral(name, bound, body) if name != "\\oint" && bound.region.is_none() => (bound, body),
+            _ => return None,
+        };
+        let lower = bound.lower.as_ref()?.eval_with_constants(constants, vars)?;
+        let upper = bound.upper.as_ref()?.eval_with_constants(constants, vars)?;
+
+        if !lower.is_finite() || !upper.is_finite() {
+            return None;
+        }
+
+        let mut vars = vars.clone();
+        let mut evaluations = 0;
+        let mut f = |x: f64| {
+            evaluations += 1;
+            vars.insert(bound.var.clone(), x);
+            return body
+                .eval_with_constants(constants, &vars)
+                .filter(|_| evaluations <= MAX_EVALUATIONS);
+        };
+
+        let (fa, fm, fb) = (f(lower), f((lower + upper) / 2.0), f(upper));
+        let (fa, fm, fb) = (fa?, fm?, fb?);
+        let whole = (upper - lower) / 6.0 * (fa + 4.0 * fm + fb);
+        let tolerance = QUADRATURE_TOLERANCE * (1.0 + whole.abs());
+
+        return Self::simpson(&mut f, (lower, fa), (upper, fb), fm, whole, tolerance).filter(|v| v.is_finite());
+    }
+
+    // Simpson's rule on both halves of [a, b], recursing into a half until it agrees with the whole
+    fn simpson<F: FnMut(f64) -> Option<f64>>(
+        f: &mut F,
+        (a, fa): (f64, f64),
+        (b, fb): (f64, f64),
+        fm: f64,
+        whole: f64,
+        tolerance: f64,
+    ) -> Option<f64> {
+        let m = (a + b) / 2.0;
+        let (flm, frm) = (f((a + m) / 2.0)?, f((m + b) / 2.0)?);
+        let left = (m - a) / 6.0 * (fa + 4.0 * flm + fm);
+        let right = (b - m) / 6.0 * (fm + 4.0 * frm + fb);
+        let delta = left + right - whole;
+
+        // The error of the halves is about a fifteenth of `delta`
+        if delta.abs() <= 15.0 * tolerance || m == a || m == b {
+            return Some(left + right + delta / 15.0);
+        }
+
+        let left = Self::simpson(f, (a, fa), (m, fm), flm, left, tolerance / 2.0)?;
+        let right = Self::simpson(f, (m, fm), (b, fb), frm, right, tolerance / 2.0)?;
+        return Some(left + right);
+    }
+
+    // `\lim_{x \to 0} \frac{\sin x}{x}` => 1 by a sequence approaching the target from each side
+    fn eval_limit(&self, constants: &BTreeMap<String, Constant>, vars: &BTreeMap<String, f64>) -> Option<f64> {
+        let (bound, side, body) = match self {
+            Self::Limit(bound, side, body) => (bound, side, body),
+            _ => return None,
+        };
+        let target = bound.lower.as_ref()?;
+        let infinity = Self::TS("\\infty".to_string());
+
+        // Points at the step `h` from the target, e.g. 1 / h for `\infty`
+        let approaches: Vec<Box<dyn Fn(f64) -> f64>> = match target {
+            Self::TS(_) if target.eq_without_expand(&infinity) => vec![Box::new(|h| 1.0 / h)],
+            Self::Neg(f) if f.eq_without_expand(&infinity) => vec![Box::new(|h| -1.0 / h)],
+            _ => {
+                let a = target.eval_with_constants(constants, vars)?;
+
+                match side {
+                    Side::Both => vec![Box::new(move |h| a - h), Box::new(move |h| a + h)],
+                    Side::Left => vec![Box::new(move |h| a - h)],
+                    Side::Right => vec![Box::new(move |h| a + h)],
+                }
+            }
+        };
+
+        let limits = approaches
+            .iter()
+            .map(|approach| Self::extrapolate(|h| body.eval_at(constants, vars, &bound.var, approach(h))))
+            .collect::<Option<Vec<_>>>()?;
+
+        // The left and right limits must agree
+        if limits
+            .iter()
+            .any(|v| (v - limits[0]).abs() > SIDES_TOLERANCE * (1.0 + v.abs()))
+        {
+            return None;
+        }
+
+        return Some(limits[0]);
+    }
+
+    fn eval_at(
+        &self,
+        constants: &BTreeMap<String, Constant>,
+        vars: &BTreeMap<String, f64>,
+        var: &str,
+        x: f64,
+    ) -> Option<f64> {
+        let mut vars = vars.clone();
+        vars.insert(var.to_string(), x);
+        return self.eval_with_constants(constants, &vars);
+    }
+
+    // Value of `f(h)` as h => 0, by extrapolating the latest values with Neville's algorithm
+    fn extrapolate<F: Fn(f64) -> Option<f64>>(f: F) -> Option<f64> {
+        let mut steps = Vec::new();
+        let mut values = Vec::new();
+        let mut previous: Option<f64> = None;
+        // The estimate closest to the one before, with their difference
+        let mut best: Option<(f64, f64)> = None;
+
+        for k in 0..LIMIT_STEPS {
+            let h = LIMIT_STEP / 2f64.powi(k as i32);
+            steps.push(h);
+            values.push(f(h)?);
+
+            let start = steps.len().saturating_sub(LIMIT_ORDER);
+            let hs = &steps[start..];
+            let mut table = values[start..].to_vec();
+
+            for m in 1..table.len() {
+                for i in 0..table.len() - m {
+                    table[i] = (hs[i] * table[i + 1] - hs[i + m] * table[i]) / (hs[i] - hs[i + m]);
+                }
+            }
+
+            let estimate = table[0];
+
+            if !estimate.is_finite() {
+                return None;
+            }
+
+            if let Some(previous) = previous {
+                let error = (estimate - previous).abs() / (1.0 + estimate.abs());
+
+                if error <= f64::EPSILON {
+                    return Some(estimate);
+                }
+
+                // Estimates drift apart once rounding errors in `f` outweigh the extrapolation, e.g. `(1 + h)^{1 / h}`
+                if matches!(best, Some((best_error, _)) if error > best_error) {
+                    break;
+                }
+
+                best = Some((error, estimate));
+            }
+
+            previous = Some(estimate);
+        }
+
+        return match best {
+            Some((error, estimate)) if error <= LIMIT_TOLERANCE => Some(estimate),
+            _ => None,
+        };
+    }
+
+    // Fold constant sub-formulas into numbers, e.g. `2 \pi x` => `6.28318530718 x`
+    pub fn fold_constants(self, config: &Config) -> Self {
+        return Self::fold_with_constants(self, &config.constants).fold_lone_constants(&config.constants);
+    }
+
+    // Constants left after folding are written as numbers too, e.g. `\pi x` => `3.14159265359 x`
+    fn fold_lone_constants(self, constants: &BTreeMap<String, Constant>) -> Self {
+        return match self {
+            Self::Const(_) => match self.eval_with_constants(constants, &BTreeMap::new()) {
+                Some(v) => Self::number(v),
+                None => self,
+            },
+            _ => self.map_children(|f| f.fold_lone_constants(constants)),
+        };
+    }
+
+    // Fold numeric literals only, e.g. `2 * 3 x` => `6 x`
+    pub(crate) fn fold_numbers(self) -> Self {
+        return Self::fold_with_constants(self, &BTreeMap::new());
+    }
+
+    fn fold_with_constants(self, constants: &BTreeMap<String, Constant>) -> Self {
+        let no_vars = BTreeMap::new();
+
+        return match self {
+            // - 2 => -2
+            Self::Neg(formula) => {
+                let folded = formula.fold_with_constants(constants);
+
+                match folded.eval_with_constants(constants, &no_vars) {
+                    Some(v) if !folded.is_const() => Self::folded_value(Self::Neg(Box::new(folded)), -v),
+                    _ => Self::Neg(Box::new(folded)),
+                }
+            }
+
+            // 1 / 2 => 0.5
+            Self::Recip(formula) => {
+                let folded = formula.fold_with_constants(constants);
+
+                match folded.eval_with_constants(constants, &no_vars) {
+                    Some(v) if v != 0.0 && !folded.is_const() => {
+                        Self::folded_value(Self::Recip(Box::new(folded)), 1.0 / v)
+                    }
+                    _ => Self::Recip(Box::new(folded)),
+                }
+            }
+
+            // |-2| => 2
+            Self::Abs(formula) => {
+                let folded = formula.fold_with_constants(constants);
+
+                match folded.eval_with_constants(constants, &no_vars) {
+                    Some(v) if !folded.is_const() => Self::folded_value(Self::Abs(Box::new(folded)), v.abs()),
+                    _ => Self::Abs(Box::new(folded)),
+                }
+            }
+
+            // 2^3 => 8, \sum_{k=1}^{3} k => 6, \int_0^1 2x dx => 1, \sin 0 => 0, 3! => 6, 7 \bmod 3 => 1
+            Self::Pow(_, _)
+            | Self::Factorial(_)
+            | Self::Binomial(_, _)
+            | Self::Permutation(_, _)
+            | Self::Floor(_)
+            | Self::Ceil(_)
+            | Self::Round(_)
+            | Self::Mod(_, _)
+            | Self::Sum(_, _)
+            | Self::Prod(_, _)
+            | Self::Integral(_, _, _)
+            | Self::Func(_, _)
+            | Self::Limit(_, _, _)
+            | Self::At(_, _) => {
+                let folded = self.map_children(|f| f.fold_with_constants(constants));
+
+                match folded.eval_with_constants(constants, &no_vars) {
+                    Some(v) => Self::folded_value(folded, v),
+                    None => folded,
+                }
+            }
+
+            // x + 1 + 2 => x + 3
+            Self::Add(formulas) => Self::fold_operands(
+                formulas
+                    .into_iter()
+                    .map(|f| f.fold_with_constants(constants))
+                    .collect_vec(),
+                constants,
+                |values| values.iter().sum(),
+                Self::Add,
+            ),
+
+            // 2 x 3 => 6 x
+            Self::Mul(formulas) => Self::fold_operands(
+                formulas
+                    .into_iter()
+                    .map(|f| f.fold_with_constants(constants))
+                    .collect_vec(),
+                constants,
+                |values| values.iter().product(),
+                Self::Mul,
+            ),
+
+            // o.w.
+            _ => self.map_children(|f| f.fold_with_constants(constants)),
+        };
+    }
+
+    fn fold_operands(
+        formulas: Vec<Self>,
+        constants: &BTreeMap<String, Constant>,
+        combine: fn(&[f64]) -> f64,
+        build: fn(Vec<Self>) -> Self,
+    ) -> Self {
+        let no_vars = BTreeMap::new();
+        let (values, mut rest): (Vec<_>, Vec<_>) = formulas
+            .into_iter()
+            .map(|f| (f.eval_with_constants(constants, &no_vars), f))
+            .partition(|(v, _)| v.is_some());
+
+        // Nothing to combine
+        if values.len() < 2 {
+            rest.extend(values);
+            return build(rest.into_iter().map(|(_, f)| f).collect_vec());
+        }
+
+        let value = combine(&values.iter().map(|(v, _)| v.unwrap()).collect_vec());
+        let folded = Self::folded_value(build(values.into_iter().map(|(_, f)| f).collect_vec()), value);
+
+        if rest.is_empty() {
+            return folded;
+        }
+
+        return build(
+            std::iter::once(folded)
+                .chain(rest.into_iter().map(|(_, f)| f))
+                .collect_vec(),
+        );
+    }
+
+    // A lone constant is kept as it is, e.g. `- \pi` => `- \pi`
+    fn is_const(&self) -> bool {
+        return matches!(self, Self::Const(_));
+    }
+
+    // Integers are kept exact and other values are rounded, e.g. `2^{60}` => `1152921504606846976`
+    fn folded_value(formula: Self, value: f64) -> Self {
+        return match integer::exact_integer(&formula) {
+            Some(n) if n.is_negative() => Self::Neg(Box::new(Self::TS(n.abs().to_string()))),
+            Some(n) => Self::TS(n.to_string()),
+            None => Self::number(value),
+        };
+    }
+
+    // 6.0 => `6`, -0.5 => `-0.5`
+    pub(crate) fn number(value: f64) -> Self {
+        let rounded = format!("{:.*e}", FOLD_PRECISION - 1, value)
+            .parse::<f64>()
+            .unwrap_or(value);
+        let s = format!("{}", rounded);
+
+        if let Some(abs) = s.strip_prefix('-') {
+            return Self::Neg(Box::new(Self::TS(abs.to_string())));
+        }
+
+        return Self::TS(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::f64::consts::PI;
+
+    use crate::config::Config;
+    use crate::formula::Formula::{self, *};
+
+    fn ts(s: &str) -> Formula {
+        return TS(s.to_string());
+    }
+
+    fn eval(s: &str, vars: &[(&str, f64)]) -> Option<f64> {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), *v))
+            .collect::<BTreeMap<_, _>>();
+        return Formula::parse(s).unwrap().eval(&Config::default(), &vars);
+    }
+
+    #[test]
+    fn eval_test() {
+        assert_eq!(eval("1 + 2", &[]), Some(3.0));
+        assert_eq!(eval("12 / 4", &[]), Some(3.0));
+        assert_eq!(eval("2 x - y", &[("x", 3.0), ("y", 1.0)]), Some(5.0));
+        assert_eq!(eval("2 \\pi", &[]), Some(2.0 * PI));
+        assert_eq!(eval("\\binom{n}{2} + 3!", &[("n", 5.0)]), Some(16.0));
+        assert_eq!(
+            eval("\\lfloor x \\rfloor + \\lceil x \\rceil", &[("x", -1.5)]),
+            Some(-3.0)
+        );
+        assert_eq!(eval("(-7) \\bmod 3", &[]), Some(2.0));
+    }
+
+    #[test]
+    fn eval_none_test() {
+        assert_eq!(eval("x", &[]), None);
+        assert_eq!(eval("1 / 0", &[]), None);
+        assert_eq!(eval("i", &[]), None);
+        assert_eq!(eval("", &[]), None);
+    }
+
+    #[test]
+    fn eval_integral_test() {
+        let close = |v: Option<f64>, expect: f64| (v.unwrap() - expect).abs() < 1e-9;
+        assert!(close(eval("\\int_0^1 x^2 dx", &[]), 1.0 / 3.0));
+        assert!(close(
+            eval("\\int_0^{\\pi} 2^t dt", &[]),
+            (2f64.powf(PI) - 1.0) / 2f64.ln()
+        ));
+        assert!(close(eval("\\int_0^a x dx", &[("a", 2.0)]), 2.0));
+        assert_eq!(eval("\\int x dx", &[]), None);
+        assert_eq!(eval("\\int_0^1 1 / x dx", &[]), None);
+        assert!(close(eval("\\int_0^1 |x - 0.3| dx", &[]), 0.29));
+        assert_eq!(eval("\\oint_C z dz", &[]), None);
+    }
+
+    #[test]
+    fn fold_numbers_test() {
+        let input = Formula::parse("2 * x * 3").unwrap().fold_numbers();
+        let expect = Mul(vec![ts("6"), ts("x")]);
+        assert!(Formula::eq_without_expand(&input, &expect));
+
+        let input = Formula::parse("x + 1 - 3").unwrap().fold_numbers();
+        let expect = Add(vec![ts("x"), Neg(Box::new(ts("2")))]);
+        assert!(Formula::eq_without_expand(&input, &expect));
+
+        let input = Formula::parse("2^{60} + 1").unwrap().fold_numbers();
+        let expect = ts("1152921504606846977");
+        assert!(Formula::eq_without_expand(&input, &expect));
+    }
+
+    #[test]
+    fn fold_numbers_keep_constants_test() {
+        let input = Formula::parse("2 \\pi").unwrap().fold_numbers();
+        let expect = Mul(vec![ts("2"), Const("\\pi".to_string())]);
+        assert!(Formula::eq_without_expand(&input, &expect));
+    }
+
+    #[test]
+    fn fold_constants_test() {
+        let input = Formula::parse("\\pi + \\pi")
+            .unwrap()
+            .fold_constants(&Config::default());
+        let expect = ts("6.28318530718");
+        assert!(Formula::eq_without_expand(&input, &expect));
+
+        let input = Formula::parse("\\pi x").unwrap().fold_constants(&Config::default());
+        let expect = Mul(vec![ts("3.14159265359"), ts("x")]);
+        assert!(Formula::eq_without_expand(&input, &expect));
+    }
+}
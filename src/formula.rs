@@ -1,116 +1,666 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 
 use itertools::Itertools;
 use log::{debug, error};
 use regex::Regex;
 
+use crate::config::{Config, Overline, Pairs};
+use crate::delimiter::{self, Delimiter};
 use crate::errors::ParseFormulaError;
+use crate::integer;
+use crate::macros;
+use crate::operator::{Operator, Relation};
+use crate::preprocess;
+use crate::symbol;
+
+// Determinants of symbolic matrices larger than this are kept unexpanded, as expansion takes factorial time
+const MAX_EXPANDED_DETERMINANT: usize = 6;
 
 #[derive(Debug, Clone, Eq, PartialOrd, Ord)]
 pub enum Formula {
-    TS(String),          // Terminal Symbol
-    Neg(Box<Formula>),   // Negative Number
-    Recip(Box<Formula>), // Reciprocal Number
-    Add(Vec<Formula>),   // Addition
-    Mul(Vec<Formula>),   // Multiplication
+    TS(String),                                           // Terminal Symbol
+    Const(String),                                        // Mathematical Constant
+    Operator(String),                                     // Non-commutative Symbol, e.g. a matrix `A`
+    Text(String),                                         // Annotation
+    Neg(Box<Formula>),                                    // Negative Number
+    PlusMinus(Box<Formula>),                              // Either sign, `\pm`
+    MinusPlus(Box<Formula>),                              // Either sign opposite to `\pm`, `\mp`
+    Recip(Box<Formula>),                                  // Reciprocal Number
+    Add(Vec<Formula>),                                    // Addition
+    Mul(Vec<Formula>),                                    // Multiplication
+    Abs(Box<Formula>),                                    // Absolute Value
+    Conj(Box<Formula>),                                   // Complex Conjugate, `\overline{z}`
+    Overline(Box<Formula>),                               // Overlined Symbol, e.g. a mean `\bar x`
+    Norm(Box<Formula>),                                   // Norm
+    Floor(Box<Formula>),                                  // Floor, `\lfloor x \rfloor`
+    Ceil(Box<Formula>),                                   // Ceiling, `\lceil x \rceil`
+    Round(Box<Formula>),             // Nearest integer, `\lfloor x \rceil`, `\operatorname{round}(x)`
+    Mod(Box<Formula>, Box<Formula>), // Remainder by a modulus, `a \bmod n`
+    Pow(Box<Formula>, Box<Formula>), // Power
+    Factorial(Box<Formula>),         // Factorial, `n!`
+    Binomial(Box<Formula>, Box<Formula>), // Binomial coefficient, `\binom{n}{k}`
+    Permutation(Box<Formula>, Box<Formula>), // Number of k-permutations of n, `P(n, k)`
+    Subscript(Box<Formula>, Box<Formula>), // Subscript
+    Sum(Box<Bound>, Box<Formula>),   // Summation
+    Prod(Box<Bound>, Box<Formula>),  // Product
+    Integral(String, Box<Bound>, Box<Formula>), // Integral by its macro, e.g. `\oint`
+    Func(String, Box<Formula>),      // Elementary Function
+    Derivative(Box<Formula>, String, usize), // Derivative with the variable and the order
+    Limit(Box<Bound>, Side, Box<Formula>), // Limit with the target as the lower limit
+    At(Box<Bound>, Box<Formula>),    // Value at the lower limit as a point, e.g. `f'(2)`
+    Matrix(Vec<Vec<Formula>>),       // Matrix by rows
+    Set(Vec<Formula>),               // Set by its elements
+    Tuple(Vec<Formula>),             // Ordered elements, e.g. a point `(1, -2)`
+    List(Vec<Formula>),              // Alternative answers, e.g. roots `x = 2, x = -3`
+    SetBuilder(Vec<String>, Box<Formula>, Box<Formula>), // Set by bound variables, an element and a condition
+    Union(Vec<Formula>),             // Union of sets
+    Intersection(Vec<Formula>),      // Intersection of sets
+    Difference(Box<Formula>, Box<Formula>), // Set difference
+    In(Box<Formula>, Box<Formula>),  // Membership
+    Interval(bool, Box<Formula>, Box<Formula>, bool), // Real interval by its endpoints, closed at a true end
+    Not(Box<Formula>),               // Negation
+    And(Vec<Formula>),               // Conjunction
+    Or(Vec<Formula>),                // Disjunction
+    Xor(Vec<Formula>),               // Exclusive Disjunction
+    Implies(Box<Formula>, Box<Formula>), // Implication
+    Iff(Box<Formula>, Box<Formula>), // Equivalence
+    Relation(Relation, Box<Formula>, Box<Formula>), // Comparison
+    Congruence(Box<Formula>, Box<Formula>, Box<Formula>), // Congruence modulo the last, `a \equiv b \pmod{n}`
+    Forall(Box<Bound>, Box<Formula>), // Universal Quantifier
+    Exists(Box<Bound>, Box<Formula>), // Existential Quantifier
     Empty,
 }
 
+// A variable bound by a binder like `\sum_{k=1}^{n}` or `\int_a^b ... dx` with optional limits
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bound {
+    pub var: String,
+    pub lower: Option<Formula>,
+    pub upper: Option<Formula>,
+    pub region: Option<Formula>, // Region, contour or set instead of limits, e.g. `D` in `\iint_D`, `S` in `\sum_{k \in S}`
+}
+
+// Direction from which a limit approaches its target, e.g. `0^+` from the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Side {
+    Both,
+    Left,
+    Right,
+}
+
+impl Bound {
+    // A variable without limits, e.g. `x` in `\forall x`
+    fn new(var: &str) -> Self {
+        return Self {
+            var: var.to_string(),
+            lower: None,
+            upper: None,
+            region: None,
+        };
+    }
+
+    fn map<F: Fn(Formula) -> Formula>(self, f: F) -> Self {
+        return Self {
+            var: self.var,
+            lower: self.lower.map(&f),
+            upper: self.upper.map(&f),
+            region: self.region.map(&f),
+        };
+    }
+
+    fn with_var(self, var: &str) -> Self {
+        return Self {
+            var: var.to_string(),
+            ..self
+        };
+    }
+}
+
 impl Formula {
     fn is_empty(&self) -> bool {
         return matches!(self, Self::Empty);
     }
 
-    fn is_single_paren(s: &str) -> bool {
-        if !Regex::new(r"^\(.*\)$").unwrap().is_match(s) {
-            return false;
+    // `(x + 1)` => (Paren, `x + 1`), `\left[ x \right]` => (Bracket, ` x `)
+    fn strip_single_paren(s: &str) -> Option<(Delimiter, String)> {
+        return delimiter::strip_group(&s.trim().chars().collect_vec());
+    }
+
+    fn is_only_paren(s: &str) -> bool {
+        return delimiter::is_only_delimiters(&s.chars().collect_vec());
+    }
+
+    // `12`, `3.14`, `1.`, `.5`; checked by hand since evaluation calls this at every quadrature point
+    pub(crate) fn is_number(s: &str) -> bool {
+        let (integer, fraction) = match s.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (s, None),
+        };
+        let is_digits = |t: &str| t.chars().all(|c| c.is_ascii_digit());
+
+        return is_digits(integer)
+            && fraction.map_or(true, is_digits)
+            && !(integer.is_empty() && fraction.map_or(true, str::is_empty));
+    }
+
+    pub fn parse(s: &str) -> Result<Self, ParseFormulaError> {
+        return Self::parse_with_config(s, &Config::default());
+    }
+
+    pub fn parse_with_config(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let expanded = macros::expand(s, &config.macros)?;
+        let formula = Self::parse_by_list(&preprocess::preprocess(&expanded), config)?;
+        return Ok(formula.resolve_pairs(config.pairs));
+    }
+
+    // `x = 2, x = -3` => List([x = 2, x = -3]), so is `x = 2 \text{ or } x = -3`
+    // The commas after a quantifier are its own, e.g. `\forall x, P x`
+    fn parse_by_list(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let chars = s.chars().collect_vec();
+
+        if (0..chars.len()).any(|i| {
+            symbol::QUANTIFIERS
+                .iter()
+                .any(|q| Self::starts_with_symbol(&chars[i..], q))
+        }) {
+            return Self::parse_by_logic(s, config);
         }
 
-        let s_end_paren_removed = &s[0..s.len() - 1];
-        let mut paren_depth = 0;
+        let s = Regex::new(r"\\(?:text|textrm|textit|mbox)\s*\{\s*or\s*\}")
+            .unwrap()
+            .replace_all(s, "\\text{or}");
+        let mut answers = Self::split_connective(&s, &symbol::LIST_SEPARATORS, "List", config, Self::parse_by_logic)?;
+
+        return Ok(if answers.len() == 1 {
+            answers.remove(0)
+        } else {
+            Self::List(answers)
+        });
+    }
 
-        for c in s_end_paren_removed.chars() {
-            if c == '(' {
-                paren_depth += 1;
+    // Read pairs as open intervals in a set context and as `pairs` says elsewhere
+    // e.g. `x \in (0, 1)` => In(x, Interval(0, 1)), `(0, \infty)` => Interval(0, \infty) for Auto
+    fn resolve_pairs(self, pairs: Pairs) -> Self {
+        let formula = self.map_children(|f| f.resolve_pairs(pairs));
+        let sets = |sets: Vec<Self>| sets.into_iter().map(Self::into_interval).collect_vec();
+
+        return match formula {
+            Self::In(element, set) => Self::In(element, Box::new(set.into_interval())),
+            Self::Union(operands) => Self::Union(sets(operands)),
+            Self::Intersection(operands) => Self::Intersection(sets(operands)),
+            Self::Difference(l, r) => Self::Difference(Box::new(l.into_interval()), Box::new(r.into_interval())),
+            Self::Tuple(elements) => {
+                let is_infinite = |f: &Self| match f {
+                    Self::Neg(f) => matches!(&**f, Self::TS(s) if s == "\\infty"),
+                    f => matches!(f, Self::TS(s) if s == "\\infty"),
+                };
+
+                match pairs {
+                    Pairs::Intervals => Self::Tuple(elements).into_interval(),
+                    Pairs::Auto if elements.iter().any(is_infinite) => Self::Tuple(elements).into_interval(),
+                    _ => Self::Tuple(elements),
+                }
             }
+            f => f,
+        };
+    }
 
-            if c == ')' {
-                paren_depth -= 1;
+    // `(0, 1)` => Interval(0, 1), other formulas as they are
+    pub(crate) fn into_interval(self) -> Self {
+        return match self {
+            Self::Tuple(mut elements) if elements.len() == 2 => {
+                let upper = elements.pop().unwrap_or(Self::Empty);
+                let lower = elements.pop().unwrap_or(Self::Empty);
+                Self::Interval(false, Box::new(lower), Box::new(upper), false)
             }
+            f => f,
+        };
+    }
+
+    // `p \leftrightarrow q` => Iff(p, q), looser than the other connectives
+    fn parse_by_logic(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        // The scope of a leading quantifier extends to the end, e.g. `\forall x P \to Q` => Forall(x, P \to Q)
+        if let Some(formula) = Self::parse_quantifier(s, config)? {
+            return Ok(formula);
+        }
+
+        let sides = Self::split_connective(s, &symbol::IFF, "Iff", config, Self::parse_by_implies)?;
 
-            if paren_depth == 0 {
-                return false;
+        return match <[Self; 2]>::try_from(sides) {
+            Ok([l, r]) => Ok(Self::Iff(Box::new(l), Box::new(r))),
+            Err(mut sides) if sides.len() == 1 => Ok(sides.remove(0)),
+            Err(_) => {
+                error!("Iff: chained equivalence => {:?}", s);
+                Err(ParseFormulaError)
             }
+        };
+    }
+
+    // `p \to q \to r` => Implies(p, Implies(q, r))
+    fn parse_by_implies(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut sides = Self::split_connective(s, &symbol::IMPLIES, "Implies", config, Self::parse_by_or)?;
+        let mut formula = sides.pop().ok_or(ParseFormulaError)?;
+
+        while let Some(premise) = sides.pop() {
+            formula = Self::Implies(Box::new(premise), Box::new(formula));
         }
 
-        return true;
+        return Ok(formula);
     }
 
-    fn is_only_paren(s: &str) -> bool {
-        return Regex::new(r"^[\(\)]+$").unwrap().is_match(s);
+    // `p \lor q` => Or([p, q])
+    fn parse_by_or(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut operands = Self::split_connective(s, &symbol::OR, "Or", config, Self::parse_by_xor)?;
+        return Ok(if operands.len() == 1 {
+            operands.remove(0)
+        } else {
+            Self::Or(operands)
+        });
     }
 
-    pub fn parse(s: &str) -> Result<Self, ParseFormulaError> {
-        return Self::parse_by_add(s);
+    // `p \oplus q` => Xor([p, q])
+    fn parse_by_xor(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut operands = Self::split_connective(s, &symbol::XOR, "Xor", config, Self::parse_by_and)?;
+        return Ok(if operands.len() == 1 {
+            operands.remove(0)
+        } else {
+            Self::Xor(operands)
+        });
     }
 
-    fn parse_by_add(s: &str) -> Result<Self, ParseFormulaError> {
-        if Self::is_only_paren(s) {
-            return Ok(Self::Empty);
+    // `p \land q` => And([p, q])
+    fn parse_by_and(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut operands = Self::split_connective(s, &symbol::AND, "And", config, Self::parse_by_not)?;
+        return Ok(if operands.len() == 1 {
+            operands.remove(0)
+        } else {
+            Self::And(operands)
+        });
+    }
+
+    // `\neg \neg p` => Not(Not(p))
+    fn parse_by_not(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        if let Some(formula) = Self::parse_quantifier(s, config)? {
+            return Ok(formula);
         }
 
-        if Self::is_single_paren(s) {
-            return Self::parse(&s[1..s.len() - 1]);
+        let chars = s.trim().chars().collect_vec();
+
+        if let Some(not) = symbol::NOT.iter().find(|not| Self::starts_with_symbol(&chars, not)) {
+            let rest = chars[not.chars().count()..].iter().collect::<String>();
+            let formula = Self::parse_by_not(&rest, config)?;
+
+            if formula.is_empty() {
+                error!("Not: missing operand => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+
+            return Ok(Self::Not(Box::new(formula)));
         }
 
-        let mut paren_depth = 0;
-        let mut term = String::new();
-        let mut terms = Vec::new();
+        return Self::parse_by_relation(s, config);
+    }
 
-        for c in s.chars() {
-            if vec!['+', '-'].contains(&c) && paren_depth == 0 {
-                terms.push(term);
-                term = String::new();
+    // `x \le 1` => Relation(Le, x, 1), `0 < x < 1` => And([0 < x, x < 1])
+    fn parse_by_relation(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        if let Some(formula) = Self::parse_congruence(s, config)? {
+            return Ok(formula);
+        }
 
-                if c == '-' {
-                    term.push(c)
-                }
+        let symbols = Relation::ALL.iter().flat_map(|r| r.symbols()).copied().collect_vec();
+        let parts = Self::split_top_level(s, &symbols, &[], "Relation")?;
 
-                continue;
+        if parts.len() == 1 {
+            return Self::parse_by_set(s, config);
+        }
+
+        let sides = parts
+            .iter()
+            .map(|(_, side)| Self::parse_by_set(side, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if sides.iter().any(Self::is_empty) {
+            error!("Relation: missing side => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        let mut relations = parts[1..]
+            .iter()
+            .zip(sides.windows(2))
+            .map(|((symbol, _), pair)| {
+                let relation = symbol.parse::<Relation>()?;
+                return Ok(Self::Relation(
+                    relation,
+                    Box::new(pair[0].clone()),
+                    Box::new(pair[1].clone()),
+                ));
+            })
+            .collect::<Result<Vec<_>, ParseFormulaError>>()?;
+
+        return Ok(if relations.len() == 1 {
+            relations.remove(0)
+        } else {
+            Self::And(relations)
+        });
+    }
+
+    // `a \equiv b \pmod{n}` => Congruence(a, b, n), `f \equiv 0` => f = 0 as an identity
+    fn parse_congruence(s: &str, config: &Config) -> Result<Option<Self>, ParseFormulaError> {
+        let parts = Self::split_top_level(s, &[symbol::CONGRUENCE], &[], "Congruence")?;
+
+        let (l, r) = match parts.as_slice() {
+            [_] => return Ok(None),
+            [(_, l), (_, r)] => (l, r),
+            _ => {
+                error!("Congruence: chained congruence => {:?}", s);
+                return Err(ParseFormulaError);
             }
+        };
 
-            if c == ')' {
-                // close paren before open
-                if paren_depth == 0 {
-                    error!("Add: close paren before open => {:?}", s);
-                    return Err(ParseFormulaError);
-                }
+        let sides = Self::split_top_level(r, &symbol::MODULUS_MACROS, &[], "Congruence")?;
+
+        let (r, modulus) = match sides.as_slice() {
+            [(_, r)] => (r, None),
+            [(_, r), (_, modulus)] => (r, Some(Self::parse_by_add(modulus, config)?)),
+            _ => {
+                error!("Congruence: double moduli => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+        };
+
+        let l = Self::parse_by_add(l, config)?;
+        let r = Self::parse_by_add(r, config)?;
+
+        if l.is_empty() || r.is_empty() || modulus.as_ref().map_or(false, Self::is_empty) {
+            error!("Congruence: missing side => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        return Ok(Some(match modulus {
+            Some(modulus) => Self::Congruence(Box::new(l), Box::new(r), Box::new(modulus)),
+            None => Self::Relation(Relation::Eq, Box::new(l), Box::new(r)),
+        }));
+    }
+
+    // `\forall x \exists y (x < y)` => Forall(x, Exists(y, x < y))
+    // A restriction moves into the body, e.g. `\forall x \in A, P` => Forall(x, x \in A \to P), `\exists x > 0: P` => Exists(x, x > 0 \land P)
+    // `\exists! x P` => Exists(x, P \land Forall(x', P(x') \to x' = x))
+    fn parse_quantifier(s: &str, config: &Config) -> Result<Option<Self>, ParseFormulaError> {
+        let chars = s.trim().chars().collect_vec();
+        let quantifier = match symbol::QUANTIFIERS.iter().find(|q| Self::starts_with_symbol(&chars, q)) {
+            Some(quantifier) => *quantifier,
+            None => return Ok(None),
+        };
+
+        let mut rest = &chars[quantifier.len()..];
+        let is_unique = quantifier == "\\exists" && rest.first() == Some(&'!');
+
+        if is_unique {
+            rest = &rest[1..];
+        }
+
+        let rest = rest.iter().collect::<String>().trim_start().chars().collect_vec();
+        let var_len = Self::match_variable(&rest);
+
+        if var_len == 0 {
+            error!("Quantifier: missing variable => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        let var = rest[..var_len].iter().collect::<String>();
+        let mut body = rest[var_len..].iter().collect::<String>().trim().to_string();
+        let mut restriction = None;
+
+        let restrictions = Relation::ALL
+            .iter()
+            .flat_map(|r| r.symbols())
+            .chain(["\\in"].iter())
+            .collect_vec();
+
+        if restrictions
+            .iter()
+            .any(|r| Self::starts_with_symbol(&body.chars().collect_vec(), r))
+        {
+            let parts = Self::split_top_level(&body, &symbol::QUANTIFIER_SEPARATORS, &[], "Quantifier")?;
+
+            let (r, rest) = if parts.len() < 2 {
+                Self::split_restriction(&body).ok_or_else(|| {
+                    error!("Quantifier: missing body after the restriction => {:?}", s);
+                    return ParseFormulaError;
+                })?
+            } else {
+                let len = parts[0].1.chars().count() + parts[1].0.chars().count();
+                (parts[0].1.clone(), body.chars().skip(len).collect())
+            };
+
+            restriction = Some(Self::parse_by_relation(&format!("{} {}", var, r), config)?);
+            body = rest;
+        } else if let Some(separator) = symbol::QUANTIFIER_SEPARATORS.iter().find(|sep| body.starts_with(**sep)) {
+            body = body[separator.len()..].to_string();
+        }
 
-                paren_depth -= 1;
+        let body = Self::parse_by_logic(&body, config)?;
+
+        if body.is_empty() {
+            error!("Quantifier: missing body => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        let body = match restriction {
+            Some(r) if quantifier == "\\forall" => Self::Implies(Box::new(r), Box::new(body)),
+            Some(r) => Self::And(vec![r, body]),
+            None => body,
+        };
+
+        // A subscripted variable is bound as one symbol, e.g. `x_1` => TS(x_1)
+        let (var, body) = match Self::parse_symbol(&var, config)? {
+            Self::TS(name) => (name, body),
+            subscripted => {
+                let name = var.replace(['{', '}'], "");
+                let body = body.replace(&subscripted, &Self::TS(name.clone()));
+                (name, body)
             }
+        };
+
+        let body = if is_unique {
+            let mut other = format!("{}'", var);
 
-            if c == '(' {
-                paren_depth += 1;
+            while body.free_vars().contains(&other) {
+                other.push('\'');
             }
 
-            term.push(c);
-            debug!(
-                "Add: c => {:?}, term => {:?}, paren_depth => {:?}",
-                c, term, paren_depth
+            let other_body = body.clone().rename(&var, &other);
+            let eq = Self::Relation(
+                Relation::Eq,
+                Box::new(Self::TS(other.clone())),
+                Box::new(Self::TS(var.clone())),
             );
+            let unique = Self::Forall(
+                Box::new(Bound::new(&other)),
+                Box::new(Self::Implies(Box::new(other_body), Box::new(eq))),
+            );
+            Self::And(vec![body, unique])
+        } else {
+            body
+        };
+
+        let bound = Box::new(Bound::new(&var));
+
+        return Ok(Some(match quantifier {
+            "\\forall" => Self::Forall(bound, Box::new(body)),
+            _ => Self::Exists(bound, Box::new(body)),
+        }));
+    }
+
+    // Length of a variable at the head of `chars`, e.g. `x > 0` => 1, `\epsilon > 0` => 8, `x_{12} > 0` => 6
+    fn match_variable(chars: &[char]) -> usize {
+        let len = match chars.first() {
+            Some('\\') if chars.len() > 1 => {
+                let len = 1 + chars[1..].iter().take_while(|c| c.is_ascii_alphabetic()).count();
+                let name = chars[1..len].iter().collect::<String>();
+                if symbol::is_symbol_macro(&name) {
+                    len
+                } else {
+                    return 0;
+                }
+            }
+            Some(c) if c.is_alphabetic() => 1,
+            _ => return 0,
+        };
+
+        return match chars.get(len..) {
+            Some(['_', '{', ..]) => delimiter::match_group(&chars[len + 1..]).map_or(len, |group| len + 1 + group),
+            Some(['_', c, ..]) if c.is_ascii_alphanumeric() => len + 2,
+            _ => len,
+        };
+    }
+
+    // A restriction followed by its body without a separator, e.g. `> 0 (x^2 > 0)` => (`> 0 `, `(x^2 > 0)`)
+    // The body is a parenthesised group at the end or a nested quantifier
+    fn split_restriction(s: &str) -> Option<(String, String)> {
+        let chars = s.trim_end().chars().collect_vec();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let rest = &chars[i..];
+            let group = delimiter::match_group(rest);
+            let is_body = symbol::QUANTIFIERS.iter().any(|q| Self::starts_with_symbol(rest, q))
+                || matches!(delimiter::match_open(rest), Some((Delimiter::Paren, _))) && group == Some(rest.len());
+
+            if i > 0 && is_body {
+                return Some((chars[..i].iter().collect(), rest.iter().collect()));
+            }
+
+            i += group.unwrap_or(1);
         }
 
-        // push last term
-        if !term.is_empty() {
-            terms.push(term.clone());
+        return None;
+    }
+
+    // Split at the symbols of a connective and parse each operand by `parse`
+    fn split_connective(
+        s: &str,
+        symbols: &[&'static str],
+        label: &str,
+        config: &Config,
+        parse: fn(&str, &Config) -> Result<Self, ParseFormulaError>,
+    ) -> Result<Vec<Self>, ParseFormulaError> {
+        let operands = Self::split_top_level(s, symbols, &[], label)?
+            .into_iter()
+            .map(|(_, operand)| parse(&operand, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if operands.len() > 1 && operands.iter().any(Self::is_empty) {
+            error!("{}: missing operand => {:?}", label, s);
+            return Err(ParseFormulaError);
         }
 
-        // some paren has not closed
-        if paren_depth != 0 {
-            error!("Add: some paren has not closed => {:?}", s);
+        return Ok(operands);
+    }
+
+    // `x \in A \cup B` => In(x, Union([A, B])), `A \setminus B \cap C` => Difference(A, Intersection([B, C]))
+    fn parse_by_set(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let members = Self::split_top_level(s, &["\\in"], &[], "In")?;
+
+        if members.len() > 2 {
+            error!("In: chained membership => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        if members.len() == 2 {
+            let element = Self::parse_by_set(&members[0].1, config)?;
+            let set = Self::parse_by_set(&members[1].1, config)?;
+
+            if element.is_empty() || set.is_empty() {
+                error!("In: missing operand => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+
+            return Ok(Self::In(Box::new(element), Box::new(set)));
+        }
+
+        let terms = Self::split_top_level(s, &["\\cup", "\\setminus"], &[], "Union")?;
+        let mut operands = terms
+            .iter()
+            .map(|(_, term)| Self::parse_by_intersection(term, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if operands.len() == 1 {
+            return Ok(operands.remove(0));
+        }
+
+        if operands.iter().any(Self::is_empty) {
+            error!("Union: missing operand => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        let mut operands = terms.iter().map(|(separator, _)| *separator).zip(operands);
+        let (_, mut formula) = operands.next().ok_or(ParseFormulaError)?;
+
+        for (separator, operand) in operands {
+            formula = match (separator, formula) {
+                ("\\cup", Self::Union(mut sets)) => {
+                    sets.push(operand);
+                    Self::Union(sets)
+                }
+                ("\\cup", f) => Self::Union(vec![f, operand]),
+                (_, f) => Self::Difference(Box::new(f), Box::new(operand)),
+            };
+        }
+
+        return Ok(formula);
+    }
+
+    // `A \cap B` => Intersection([A, B])
+    fn parse_by_intersection(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut sets = Self::split_top_level(s, &["\\cap"], &[], "Intersection")?
+            .into_iter()
+            .map(|(_, term)| Self::parse_by_add(&term, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if sets.len() == 1 {
+            return Ok(sets.remove(0));
+        }
+
+        if sets.iter().any(Self::is_empty) {
+            error!("Intersection: missing operand => {:?}", s);
             return Err(ParseFormulaError);
         }
 
+        return Ok(Self::Intersection(sets));
+    }
+
+    fn parse_by_add(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        // `\{\}` is the empty set rather than an empty group
+        if let Some((Delimiter::Brace, inner)) = Self::strip_single_paren(s) {
+            return Self::parse_group(Delimiter::Brace, &inner, config);
+        }
+
+        if Self::is_only_paren(s) {
+            return Ok(Self::Empty);
+        }
+
+        if let Some((name, inner)) = delimiter::strip_environment(&s.trim().chars().collect_vec()) {
+            return Self::parse_environment(&name, &inner, config);
+        }
+
+        if let Some((lower_closed, inner, upper_closed)) = delimiter::strip_interval(&s.trim().chars().collect_vec()) {
+            return Self::parse_interval(lower_closed, &inner, upper_closed, config);
+        }
+
+        if let Some((delimiter, inner)) = Self::strip_single_paren(s) {
+            return Self::parse_group(delimiter, &inner, config);
+        }
+
+        let mut terms = Self::split_top_level(s, &["+", "-", "\\pm", "\\mp"], &[], "Add")?
+            .into_iter()
+            .map(|(separator, term)| match separator {
+                "-" | "\\pm" | "\\mp" => format!("{}{}", separator, term),
+                _ => term,
+            })
+            .collect_vec();
+
         terms = terms
             .into_iter()
             .map(|s| s.trim().to_string())
@@ -122,14 +672,29 @@ impl Formula {
         }
 
         if terms.len() == 1 {
-            if terms[0].starts_with('-') {
-                return Ok(Self::Neg(Box::new(Self::parse_by_mul(terms[0][1..].trim())?)));
+            let chars = terms[0].chars().collect_vec();
+
+            if Self::starts_with_symbol(&chars, "\\pm") || Self::starts_with_symbol(&chars, "\\mp") {
+                let formula = Box::new(Self::parse_by_mul(terms[0][3..].trim(), config)?);
+
+                if formula.is_empty() {
+                    error!("Add: missing operand => {:?}", s);
+                    return Err(ParseFormulaError);
+                }
+
+                return Ok(if terms[0].starts_with("\\pm") {
+                    Self::PlusMinus(formula)
+                } else {
+                    Self::MinusPlus(formula)
+                });
+            } else if terms[0].starts_with('-') {
+                return Ok(Self::Neg(Box::new(Self::parse_by_mul(terms[0][1..].trim(), config)?)));
             } else {
-                return Self::parse_by_mul(&terms[0]);
+                return Self::parse_by_mul(&terms[0], config);
             }
         }
 
-        let result_iter = terms.into_iter().map(|s| Self::parse(&s));
+        let result_iter = terms.into_iter().map(|s| Self::parse_by_add(&s, config));
 
         if result_iter.clone().any(|r| r.is_err()) {
             return Err(ParseFormulaError);
@@ -144,127 +709,1876 @@ impl Formula {
         }
     }
 
-    fn parse_by_mul(s: &str) -> Result<Self, ParseFormulaError> {
+    fn parse_by_mul(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
         if Self::is_only_paren(s) {
             return Ok(Self::Empty);
         }
 
-        if Self::is_single_paren(s) {
-            return Self::parse(&s[1..s.len() - 1]);
+        if let Some((name, inner)) = delimiter::strip_environment(&s.trim().chars().collect_vec()) {
+            return Self::parse_environment(&name, &inner, config);
+        }
+
+        if let Some((lower_closed, inner, upper_closed)) = delimiter::strip_interval(&s.trim().chars().collect_vec()) {
+            return Self::parse_interval(lower_closed, &inner, upper_closed, config);
+        }
+
+        if let Some((delimiter, inner)) = Self::strip_single_paren(s) {
+            return Self::parse_group(delimiter, &inner, config);
+        }
+
+        // The body of a big operator or an integral is not split, e.g. `\sum_k 2 \cdot k`
+        let stops = [symbol::BIG_OPERATORS.as_slice(), symbol::INTEGRALS.as_slice()].concat();
+
+        // `\bmod` is looser than the other factors, e.g. `2 a \bmod n` => Mod(2 a, n)
+        let operands = Self::split_top_level(s, &symbol::MOD_OPERATORS, &stops, "Mod")?;
+
+        if operands.len() > 1 {
+            let operands = operands
+                .into_iter()
+                .map(|(_, operand)| Self::parse_by_mul(&operand, config))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if operands.iter().any(Self::is_empty) {
+                error!("Mod: missing operand => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+
+            return Ok(operands
+                .into_iter()
+                .reduce(|l, r| Self::Mod(Box::new(l), Box::new(r)))
+                .unwrap());
+        }
+
+        // `\cdot`, `\times`, `\div` and so on are the same as `*` and `/`
+        let separators = [Operator::Mul.symbols(), Operator::Div.symbols()].concat();
+        let mut terms = Self::split_top_level(s, &separators, &stops, "Mul")?
+            .into_iter()
+            .map(|(separator, term)| {
+                if Operator::Div.symbols().contains(&separator) {
+                    format!("/{}", term)
+                } else {
+                    term
+                }
+            })
+            .collect_vec();
+
+        terms = terms
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect_vec();
+
+        if terms.is_empty() {
+            return Ok(Self::Empty);
+        }
+
+        if terms.len() == 1 {
+            if terms[0].starts_with('/') {
+                return Ok(Self::Recip(Box::new(Self::parse_by_mul(terms[0][1..].trim(), config)?)));
+            }
+
+            // for abbreviate
+            let factors = Self::split_factors(&terms[0], config)?;
+
+            if factors.len() == 1 {
+                return Self::parse_symbol(&factors[0], config);
+            }
+
+            terms = factors;
+        }
+
+        let result_iter = terms.into_iter().map(|s| Self::parse_by_add(&s, config));
+
+        if result_iter.clone().any(|r| r.is_err()) {
+            return Err(ParseFormulaError);
+        }
+
+        let parsed_terms = result_iter
+            .map(|r| Self::ungroup_set(r.unwrap()))
+            .filter(|f| !f.is_empty())
+            .collect_vec();
+        let parsed_terms = Self::resolve_permutations(parsed_terms, config);
+
+        if parsed_terms.is_empty() {
+            return Ok(Formula::Empty);
+        } else {
+            return Ok(Formula::Mul(parsed_terms));
+        }
+    }
+
+    // Braces around a single operand group it, e.g. `2 \{x + 1\}` == `2 (x + 1)`, `\{x\}^2` == `x^2`
+    fn ungroup_set(formula: Self) -> Self {
+        return match formula {
+            Self::Set(mut elements) if elements.len() == 1 => elements.remove(0),
+            f => f,
+        };
+    }
+
+    // `P` followed by a pair counts permutations if declared combinatorial, e.g. `2 P(n, k)` => [2, Permutation(n, k)]
+    fn resolve_permutations(factors: Vec<Self>, config: &Config) -> Vec<Self> {
+        if !config.is_combinatorial("P") {
+            return factors;
+        }
+
+        let mut resolved = Vec::new();
+
+        for factor in factors {
+            match (resolved.last(), factor) {
+                (Some(Self::TS(s)), Self::Tuple(mut elements)) if s == "P" && elements.len() == 2 => {
+                    let k = elements.pop().unwrap();
+                    let n = elements.pop().unwrap();
+                    resolved.pop();
+                    resolved.push(Self::Permutation(Box::new(n), Box::new(k)));
+                }
+                (_, factor) => resolved.push(factor),
+            }
+        }
+
+        return resolved;
+    }
+
+    // `|x|` => Abs(x), `\|v\|` => Norm(v), `\lfloor x \rfloor` => Floor(x), `\lfloor x \rceil` => Round(x), `(x)` => x, `{n \choose k}` => Binomial(n, k)
+    fn parse_group(delimiter: Delimiter, inner: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        // e.g. `\begin{pmatrix} x \end{bmatrix}`
+        if delimiter == Delimiter::Env {
+            error!("Env: mismatched environments => {:?}", inner);
+            return Err(ParseFormulaError);
+        }
+
+        if delimiter == Delimiter::Brace {
+            return Self::parse_set(inner, config);
+        }
+
+        if delimiter == Delimiter::Group {
+            let parts = Self::split_top_level(inner, &["\\choose"], &[], "Choose")?;
+
+            if parts.len() == 2 {
+                return Ok(Self::Binomial(
+                    Box::new(Self::parse_by_add(&parts[0].1, config)?),
+                    Box::new(Self::parse_by_add(&parts[1].1, config)?),
+                ));
+            }
+        }
+
+        let elements = Self::split_top_level(inner, &[","], &[], "Tuple")?;
+
+        // `[0, 1]` is an interval, `(1, -2)` is a tuple until `resolve_pairs`
+        if delimiter == Delimiter::Bracket && elements.len() == 2 {
+            return Self::parse_interval(true, inner, true, config);
+        }
+
+        if delimiter == Delimiter::Paren && elements.len() > 1 {
+            let elements = elements
+                .into_iter()
+                .map(|(_, element)| Self::parse_by_set(&element, config))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if elements.iter().any(Self::is_empty) {
+                error!("Tuple: empty element => {:?}", inner);
+                return Err(ParseFormulaError);
+            }
+
+            return Ok(Self::Tuple(elements));
+        }
+
+        let formula = Self::parse_by_logic(inner, config)?;
+
+        return match delimiter {
+            Delimiter::Abs => Ok(Self::Abs(Box::new(formula))),
+            Delimiter::Norm => Ok(Self::Norm(Box::new(formula))),
+            Delimiter::Floor => Ok(Self::Floor(Box::new(formula))),
+            Delimiter::Ceil => Ok(Self::Ceil(Box::new(formula))),
+            Delimiter::Round => Ok(Self::Round(Box::new(formula))),
+            _ => Ok(formula),
+        };
+    }
+
+    // `1, 2` => Set([1, 2]), `x \mid x > 0` => SetBuilder([x], x, x > 0)
+    fn parse_set(inner: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let parts = Self::split_top_level(inner, &symbol::SET_BUILDER_SEPARATORS, &[], "Set")?;
+
+        if parts.len() > 2 {
+            error!("Set: more than one condition => {:?}", inner);
+            return Err(ParseFormulaError);
+        }
+
+        if parts.len() == 2 {
+            let element = Self::parse_by_set(&parts[0].1, config)?;
+            let condition = Self::parse_by_logic(&parts[1].1, config)?;
+
+            if element.is_empty() || condition.is_empty() {
+                error!("Set: missing element or condition => {:?}", inner);
+                return Err(ParseFormulaError);
+            }
+
+            // Variables of the element constrained by the condition are bound, e.g. `k` in `\{2k \mid k \in A\}`
+            let condition_vars = condition.free_vars();
+            let vars = element
+                .ordered_free_vars()
+                .into_iter()
+                .filter(|v| condition_vars.contains(v))
+                .collect_vec();
+
+            return Ok(Self::SetBuilder(vars, Box::new(element), Box::new(condition)));
+        }
+
+        if inner.trim().is_empty() {
+            return Ok(Self::Set(vec![]));
+        }
+
+        let elements = Self::split_top_level(inner, &[","], &[], "Set")?
+            .into_iter()
+            .map(|(_, element)| Self::parse_by_set(&element, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if elements.iter().any(Self::is_empty) {
+            error!("Set: empty element => {:?}", inner);
+            return Err(ParseFormulaError);
+        }
+
+        return Ok(Self::Set(elements));
+    }
+
+    // `0, \infty` => Interval(0, \infty) with the given closedness of the ends
+    fn parse_interval(
+        lower_closed: bool,
+        inner: &str,
+        upper_closed: bool,
+        config: &Config,
+    ) -> Result<Self, ParseFormulaError> {
+        let ends = Self::split_top_level(inner, &[","], &[], "Interval")?
+            .into_iter()
+            .map(|(_, end)| Self::parse_by_add(&end, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return match <[Self; 2]>::try_from(ends) {
+            Ok([lower, upper]) if !lower.is_empty() && !upper.is_empty() => Ok(Self::Interval(
+                lower_closed,
+                Box::new(lower),
+                Box::new(upper),
+                upper_closed,
+            )),
+            _ => {
+                error!("Interval: expected two ends => {:?}", inner);
+                Err(ParseFormulaError)
+            }
+        };
+    }
+
+    // Formulas with every `\pm` resolved to the same sign and every `\mp` to the opposite one
+    // e.g. `1 \pm 2` => [`1 + 2`, `1 - 2`], `a \pm b \mp c` => [`a + b - c`, `a - b + c`], `x` => [`x`]
+    pub(crate) fn expand_signs(&self) -> Vec<Self> {
+        if !self.has_plus_minus() {
+            return vec![self.clone()];
+        }
+
+        return [true, false]
+            .into_iter()
+            .map(|plus| self.clone().resolve_signs(plus))
+            .collect_vec();
+    }
+
+    pub(crate) fn has_plus_minus(&self) -> bool {
+        return matches!(self, Self::PlusMinus(_) | Self::MinusPlus(_))
+            || self.children().into_iter().any(Self::has_plus_minus);
+    }
+
+    // `\pm f` => `f` for plus and `-f` otherwise
+    fn resolve_signs(self, plus: bool) -> Self {
+        return match self.map_children(|f| f.resolve_signs(plus)) {
+            Self::PlusMinus(formula) if plus => *formula,
+            Self::MinusPlus(formula) if !plus => *formula,
+            Self::PlusMinus(formula) | Self::MinusPlus(formula) => Self::Neg(formula),
+            f => f,
+        };
+    }
+
+    // `pmatrix` and `1 & 2 \\ 3 & 4` => Matrix([[1, 2], [3, 4]]), `vmatrix` => its determinant or Abs(Matrix) if large
+    fn parse_environment(name: &str, inner: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let mut inner = inner.trim().to_string();
+
+        // Column specification: `\begin{array}{cc}`
+        if name == "array" {
+            let chars = inner.chars().collect_vec();
+            let len = delimiter::match_group(&chars)
+                .filter(|_| chars[0] == '{')
+                .ok_or_else(|| {
+                    error!("Env: missing column specification => {:?}", inner);
+                    return ParseFormulaError;
+                })?;
+            inner = chars[len..].iter().collect();
+        }
+
+        if !symbol::is_matrix_environment(name) {
+            error!("Env: unknown environment => {:?}", name);
+            return Err(ParseFormulaError);
+        }
+
+        let mut rows = Vec::new();
+
+        for (_, row) in Self::split_top_level(&inner, &["\\\\"], &[], "Env")? {
+            if row.trim().is_empty() {
+                continue;
+            }
+
+            let cells = Self::split_top_level(&row, &["&"], &[], "Env")?
+                .into_iter()
+                .map(|(_, cell)| Self::parse_by_add(&cell, config))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if cells.iter().any(Self::is_empty) {
+                error!("Env: empty cell => {:?}", row);
+                return Err(ParseFormulaError);
+            }
+
+            rows.push(cells);
+        }
+
+        if rows.is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+            error!("Env: rows of different lengths => {:?}", inner);
+            return Err(ParseFormulaError);
+        }
+
+        return match name {
+            "vmatrix" if rows.len() == rows[0].len() => match integer::exact_determinant(&rows) {
+                Some(determinant) => Ok(determinant),
+                None if rows.len() <= MAX_EXPANDED_DETERMINANT => Ok(Self::determinant(&rows)),
+                None => Ok(Self::Abs(Box::new(Self::Matrix(rows)))),
+            },
+            "vmatrix" => {
+                error!("Env: determinant of a non-square matrix => {:?}", inner);
+                Err(ParseFormulaError)
+            }
+            "Vmatrix" => Ok(Self::Norm(Box::new(Self::Matrix(rows)))),
+            _ => Ok(Self::Matrix(rows)),
+        };
+    }
+
+    // Laplace expansion along the first row, e.g. [[a, b], [c, d]] => `a d - b c`
+    fn determinant(rows: &[Vec<Self>]) -> Self {
+        if rows.len() == 1 {
+            return rows[0][0].clone();
+        }
+
+        let terms = (0..rows.len())
+            .map(|j| {
+                let minor = rows[1..]
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .enumerate()
+                            .filter(|(k, _)| *k != j)
+                            .map(|(_, f)| f.clone())
+                            .collect_vec()
+                    })
+                    .collect_vec();
+                let term = Self::Mul(vec![rows[0][j].clone(), Self::determinant(&minor)]);
+
+                if j % 2 == 0 {
+                    term
+                } else {
+                    Self::Neg(Box::new(term))
+                }
+            })
+            .collect_vec();
+
+        return Self::Add(terms);
+    }
+
+    // Split at `separators` outside of any delimiters, keeping the separator before each term
+    // e.g. `(x + y) - z` by [`+`, `-`] => [(``, `(x + y) `), (`-`, ` z`)]
+    // The rest from one of `stops` is left in the last term
+    fn split_top_level(
+        s: &str,
+        separators: &[&'static str],
+        stops: &[&str],
+        label: &str,
+    ) -> Result<Vec<(&'static str, String)>, ParseFormulaError> {
+        let chars = s.chars().collect_vec();
+        let mut opens = Vec::new();
+        let mut separator = "";
+        let mut term = String::new();
+        let mut terms = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let rest = &chars[i..];
+
+            if opens.is_empty() && stops.iter().any(|stop| Self::starts_with_symbol(rest, stop)) {
+                term.extend(rest);
+                break;
+            }
+
+            if opens.is_empty() {
+                if let Some(next) = separators.iter().find(|sep| Self::starts_with_symbol(rest, sep)) {
+                    terms.push((separator, term));
+                    term = String::new();
+                    separator = next;
+                    i += next.chars().count();
+                    continue;
+                }
+            }
+
+            let len = if let Some((close, len)) = delimiter::match_close(rest) {
+                match opens.pop() {
+                    // close paren before open
+                    None => {
+                        error!("{}: close paren before open => {:?}", label, s);
+                        return Err(ParseFormulaError);
+                    }
+
+                    // e.g. `(x]`
+                    Some(open) if open != close => {
+                        error!("{}: mismatched parens {:?} and {:?} => {:?}", label, open, close, s);
+                        return Err(ParseFormulaError);
+                    }
+
+                    _ => len,
+                }
+            } else if let Some((open, len)) = delimiter::match_open(rest) {
+                opens.push(open);
+                len
+            } else if rest[0] == '\\' {
+                // keep a control sequence as a whole
+                (1 + rest[1..].iter().take_while(|c| c.is_ascii_alphabetic()).count().max(1)).min(rest.len())
+            } else {
+                1
+            };
+
+            term.extend(&rest[..len]);
+            i += len;
+            debug!("{}: term => {:?}, paren_depth => {:?}", label, term, opens.len());
+        }
+
+        // push last term
+        terms.push((separator, term));
+
+        // some paren has not closed
+        if !opens.is_empty() {
+            error!("{}: some paren has not closed => {:?}", label, s);
+            return Err(ParseFormulaError);
+        }
+
+        return Ok(terms);
+    }
+
+    // `\cdot x` starts with `\cdot`, but `\cdots` does not
+    fn starts_with_symbol(chars: &[char], symbol: &str) -> bool {
+        let symbol_chars = symbol.chars().collect_vec();
+
+        if !chars.starts_with(&symbol_chars) {
+            return false;
+        }
+
+        let is_control_word = symbol.starts_with('\\') && symbol.len() > 1;
+        return !(is_control_word && chars.get(symbol_chars.len()).map_or(false, |c| c.is_ascii_alphabetic()));
+    }
+
+    // e.g. `2xy` => [`2`, `x`, `y`], `\alpha\beta` => [`\alpha`, `\beta`], `12(x)` => [`12`, `(x)`]
+    // Declared identifiers are matched first, e.g. `mKE` => [`m`, `KE`] for `KE`
+    // Scripts stay with their base, e.g. `2x^2` => [`2`, `x^2`]
+    // A big operator or an integral takes the rest as its body, e.g. `2 \sum_k k^2` => [`2`, `\sum_k k^2`]
+    // A function takes its argument, e.g. `2 \sin x \cos x` => [`2`, `\sin x`, `\cos x`]
+    // So does a derivative operator, e.g. `2 \frac{d}{dx} x^2` => [`2`, `\frac{d}{dx} x^2`]
+    fn split_factors(s: &str, config: &Config) -> Result<Vec<String>, ParseFormulaError> {
+        let chars = s.chars().collect_vec();
+        let mut factors = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            i += Self::match_base(&chars[start..], config)?;
+
+            let base = chars[start..i].iter().collect::<String>();
+
+            if symbol::is_big_operator(&base) || symbol::is_integral(&base) || Self::is_derivative_operator(&base) {
+                i = chars.len();
+            } else {
+                i += Self::match_scripts(&chars[i..])?.0;
+            }
+
+            if symbol::is_function(&base) {
+                i += Self::match_function_argument(&chars[i..], config)?;
+            }
+
+            factors.push(chars[start..i].iter().collect::<String>());
+        }
+
+        debug!("Mul: factors => {:?}", factors);
+        return Ok(factors);
+    }
+
+    // Argument of a function: a delimited group with its scripts, or the factors up to the next function
+    // e.g. `(x)^2 y` => `(x)^2`, `2x \cos x` => `2x `
+    fn match_function_argument(chars: &[char], config: &Config) -> Result<usize, ParseFormulaError> {
+        let spaces = chars.iter().take_while(|c| c.is_whitespace()).count();
+
+        if spaces == chars.len() {
+            error!("Mul: missing argument => {:?}", chars.iter().collect::<String>());
+            return Err(ParseFormulaError);
+        }
+
+        if let Some(len) = delimiter::match_group(&chars[spaces..]) {
+            return Ok(spaces + len + Self::match_scripts(&chars[spaces + len..])?.0);
+        }
+
+        let mut i = spaces;
+
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+
+            let len = Self::match_base(&chars[i..], config)?;
+            let base = chars[i..i + len].iter().collect::<String>();
+
+            if symbol::is_function(&base) || symbol::is_big_operator(&base) || symbol::is_integral(&base) {
+                break;
+            }
+
+            i += len;
+            i += Self::match_scripts(&chars[i..])?.0;
+        }
+
+        return Ok(i);
+    }
+
+    // Length of the factor at the head of `chars` without scripts
+    // Primes stay with their base, e.g. `y''` or `f'(x)` with its argument
+    fn match_base(chars: &[char], config: &Config) -> Result<usize, ParseFormulaError> {
+        let len = Self::match_unprimed_base(chars, config)?;
+        let primes = chars[len..].iter().take_while(|c| **c == '\'').count();
+
+        if primes == 0 {
+            return Ok(len + Self::match_factorial(&chars[len..])?);
+        }
+
+        let arg_len = match chars.get(len + primes) {
+            Some('(') => delimiter::match_group(&chars[len + primes..]).unwrap_or(0),
+            _ => 0,
+        };
+
+        return Ok(len + primes + arg_len);
+    }
+
+    // A factorial sign after a base, e.g. `! k` => 1
+    fn match_factorial(chars: &[char]) -> Result<usize, ParseFormulaError> {
+        return match chars {
+            // double factorial: `n!!`
+            ['!', '!', ..] => {
+                error!("Mul: double factorial => {:?}", chars.iter().collect::<String>());
+                Err(ParseFormulaError)
+            }
+            ['!', ..] => Ok(1),
+            _ => Ok(0),
+        };
+    }
+
+    fn match_unprimed_base(chars: &[char], config: &Config) -> Result<usize, ParseFormulaError> {
+        let c = chars[0];
+
+        // declared identifier: `KE`, `dt`
+        if c.is_alphabetic() {
+            if let Some(len) = config.match_identifier(chars) {
+                return Ok(len);
+            }
+        }
+
+        // delimited group: `(x + 1)`, `\left[ x \right]`, `{x}`
+        if let Some(len) = delimiter::match_group(chars) {
+            return Ok(len);
+        }
+
+        // control sequence: `\alpha` or `\,`
+        if c == '\\' {
+            let name_len = chars[1..].iter().take_while(|c| c.is_ascii_alphabetic()).count().max(1);
+            let i = (1 + name_len).min(chars.len());
+
+            // with an argument: `\mathrm{d}`, `\text{ m }`
+            let name = chars[1..i].iter().collect::<String>();
+            let spaces = chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+
+            // with an optional index and an argument: `\sqrt{x}`, `\sqrt[3]{x}`
+            if name == "sqrt" {
+                let index_len = Self::match_optional(&chars[i..]).map_or(0, |(len, _)| len);
+
+                return match Self::match_arguments(&chars[i + index_len..], 1) {
+                    Some((len, _)) => Ok(i + index_len + len),
+                    None => {
+                        error!(
+                            "Mul: missing argument of sqrt => {:?}",
+                            chars.iter().collect::<String>()
+                        );
+                        Err(ParseFormulaError)
+                    }
+                };
+            }
+
+            // with an argument: `\dot{x}`, `\ddot x`, `\overline{z}`, `\bar z`
+            if symbol::dot_order(&name).is_some() || symbol::is_conjugate_macro(&name) {
+                return match Self::match_arguments(&chars[i..], 1) {
+                    Some((len, _)) => Ok(i + len),
+                    None => {
+                        error!(
+                            "Mul: missing argument of {:?} => {:?}",
+                            name,
+                            chars.iter().collect::<String>()
+                        );
+                        Err(ParseFormulaError)
+                    }
+                };
+            }
+
+            // with two arguments: `\frac{x}{2}`, `\frac12`, `\binom{n}{k}`
+            if symbol::is_fraction_macro(&name) || symbol::is_binomial_macro(&name) {
+                return match Self::match_arguments(&chars[i..], 2) {
+                    Some((len, _)) => Ok(i + len),
+                    None => {
+                        error!(
+                            "Mul: missing arguments of {:?} => {:?}",
+                            name,
+                            chars.iter().collect::<String>()
+                        );
+                        Err(ParseFormulaError)
+                    }
+                };
+            }
+
+            if symbol::is_argument_macro(&name) {
+                return match delimiter::match_group(&chars[i + spaces..]) {
+                    Some(len) => Ok(i + spaces + len),
+                    None => {
+                        error!(
+                            "Mul: missing argument of {:?} => {:?}",
+                            name,
+                            chars.iter().collect::<String>()
+                        );
+                        Err(ParseFormulaError)
+                    }
+                };
+            }
+
+            return Ok(i);
+        }
+
+        // number: `12`, `3.14`
+        if c.is_ascii_digit() || c == '.' {
+            return Ok(chars.iter().take_while(|c| c.is_ascii_digit() || **c == '.').count());
+        }
+
+        return Ok(1);
+    }
+
+    // `count` arguments of a macro, each a group or a single token, e.g. `{x}2 y` => (4, [`x`, `2`])
+    fn match_arguments(chars: &[char], count: usize) -> Option<(usize, Vec<String>)> {
+        let mut args = Vec::new();
+        let mut i = 0;
+
+        for _ in 0..count {
+            i += chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+
+            let len = match chars.get(i)? {
+                '{' => delimiter::match_group(&chars[i..])?,
+                '\\' => (1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .count()
+                    .max(1))
+                .min(chars.len() - i),
+                _ => 1,
+            };
+
+            args.push(match delimiter::strip_group(&chars[i..i + len]) {
+                Some((Delimiter::Group, inner)) => inner,
+                _ => chars[i..i + len].iter().collect(),
+            });
+            i += len;
+        }
+
+        return Some((i, args));
+    }
+
+    // Optional argument in brackets, e.g. ` [3]{x}` => (4, `3`)
+    fn match_optional(chars: &[char]) -> Option<(usize, String)> {
+        let spaces = chars.iter().take_while(|c| c.is_whitespace()).count();
+
+        if chars.get(spaces) != Some(&'[') {
+            return None;
+        }
+
+        let len = chars[spaces..].iter().position(|&c| c == ']')?;
+        return Some((spaces + len + 1, chars[spaces + 1..spaces + len].iter().collect()));
+    }
+
+    // Subscript and superscript after a base, e.g. `_{k=1}^n k` => (9, Some(`k=1`), Some(`n`))
+    #[allow(clippy::type_complexity)]
+    fn match_scripts(chars: &[char]) -> Result<(usize, Option<String>, Option<String>), ParseFormulaError> {
+        let mut sub = None;
+        let mut sup = None;
+        let mut i = 0;
+
+        loop {
+            let spaces = chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+            let script = match chars.get(i + spaces) {
+                Some('_') => &mut sub,
+                Some('^') => &mut sup,
+                _ => break,
+            };
+
+            // double subscripts or superscripts: `x^2^3`
+            if script.is_some() {
+                error!("Mul: double scripts => {:?}", chars.iter().collect::<String>());
+                return Err(ParseFormulaError);
+            }
+
+            i += spaces + 1;
+            i += chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+
+            // `{k=1}`, `\infty` or a single character
+            let len = match chars.get(i) {
+                None => {
+                    error!("Mul: missing script => {:?}", chars.iter().collect::<String>());
+                    return Err(ParseFormulaError);
+                }
+                Some('{') => delimiter::match_group(&chars[i..]).ok_or(ParseFormulaError)?,
+                Some('\\') => {
+                    1 + chars[i + 1..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_alphabetic())
+                        .count()
+                        .max(1)
+                }
+                Some(_) => 1,
+            };
+            let len = len.min(chars.len() - i);
+            let arg = chars[i..i + len].iter().collect::<String>();
+
+            *script = Some(match delimiter::strip_group(&chars[i..i + len]) {
+                Some((Delimiter::Group, inner)) => inner,
+                _ => arg,
+            });
+            i += len;
+        }
+
+        return Ok((i, sub, sup));
+    }
+
+    // `x` => x, `x_1` => x_1, `x^2` => x^2, `\sum_{k=1}^n k` => Sum(k, 1, n, k)
+    fn parse_symbol(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        let chars = s.chars().collect_vec();
+        let base_len = Self::match_base(&chars, config)?;
+        let base = chars[..base_len].iter().collect::<String>();
+        let (scripts_len, sub, sup) = Self::match_scripts(&chars[base_len..])?;
+        let rest = chars[base_len + scripts_len..].iter().collect::<String>();
+
+        if base == "\\lim" {
+            return Self::parse_limit(sub, sup, &rest, config);
+        }
+
+        if symbol::is_big_operator(&base) {
+            return Self::parse_big_operator(&base, sub, sup, &rest, config);
+        }
+
+        if symbol::is_integral(&base) {
+            return Self::parse_integral(&base, sub, sup, &rest, config);
+        }
+
+        if symbol::is_function(&base) {
+            return Self::parse_function(&base, sub, sup, &rest, config);
+        }
+
+        if Self::is_derivative_operator(&base) && sub.is_none() && sup.is_none() {
+            let (numerator, denominator) = Self::split_fraction(&base).ok_or(ParseFormulaError)?;
+            return Self::parse_derivative(&numerator, &denominator, &rest, config)?.ok_or(ParseFormulaError);
+        }
+
+        if !rest.trim().is_empty() {
+            error!("Mul: unexpected {:?} => {:?}", rest, s);
+            return Err(ParseFormulaError);
+        }
+
+        // `C^n_k` => Binomial(n, k) if declared combinatorial
+        if let (true, Some(n), Some(k)) = (base == "C" && config.is_combinatorial("C"), &sup, &sub) {
+            return Ok(Self::Binomial(
+                Box::new(Self::parse_by_add(n, config)?),
+                Box::new(Self::parse_by_add(k, config)?),
+            ));
+        }
+
+        let mut formula = Self::parse_base(&base, config)?;
+
+        if let Some(sub) = sub {
+            formula = Self::Subscript(Box::new(formula), Box::new(Self::parse_by_add(&sub, config)?));
+        }
+
+        if let Some(sup) = sup {
+            formula = Self::Pow(
+                Box::new(Self::ungroup_set(formula)),
+                Box::new(Self::parse_by_add(&sup, config)?),
+            );
+        }
+
+        return Ok(formula);
+    }
+
+    fn parse_base(s: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        // `n!` => Factorial(n), `(n + 1)!` => Factorial(n + 1)
+        if let Some(operand) = s.strip_suffix('!') {
+            if operand.trim().is_empty() {
+                error!("Mul: missing operand of factorial => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+
+            return Ok(Self::Factorial(Box::new(Self::parse_base(operand, config)?)));
+        }
+
+        if delimiter::match_open(&s.chars().collect_vec()).is_some() {
+            return Self::parse_by_add(s, config);
+        }
+
+        if let Some((name, arg)) = Self::split_argument_macro(s) {
+            return Self::parse_argument_macro(&name, &arg, config);
+        }
+
+        // `\sqrt{x}` => x^(1 / 2), `\sqrt[3]{x}` => x^(1 / 3)
+        if Self::starts_with_symbol(&s.chars().collect_vec(), "\\sqrt") {
+            let chars = s["\\sqrt".len()..].chars().collect_vec();
+            let (index_len, index) = Self::match_optional(&chars).unwrap_or((0, "2".to_string()));
+            let (_, args) = Self::match_arguments(&chars[index_len..], 1).ok_or(ParseFormulaError)?;
+            let exponent = Self::Recip(Box::new(Self::parse_by_add(&index, config)?));
+            return Ok(Self::Pow(
+                Box::new(Self::parse_by_add(&args[0], config)?),
+                Box::new(exponent),
+            ));
+        }
+
+        // `y''` => Derivative(y, x, 2), `f'(t)` => Derivative(f, t, 1)
+        if let Some(formula) = Self::parse_primes(s, config)? {
+            return Ok(formula);
+        }
+
+        // `\dot{x}` => Derivative(x, t, 1)
+        if let Some((order, arg)) = Self::split_dot(s) {
+            let body = Self::parse_by_add(&arg, config)?;
+            return Ok(Self::Derivative(
+                Box::new(body),
+                symbol::DOT_VARIABLE.to_string(),
+                order,
+            ));
+        }
+
+        // `\overline{z}` => Conj(z), or Overline(z) if overlines are symbols
+        if let Some(arg) = Self::split_conjugate(s) {
+            let arg = Box::new(Self::parse_by_add(&arg, config)?);
+            return Ok(match config.overline {
+                Overline::Conjugate => Self::Conj(arg),
+                Overline::Symbol => Self::Overline(arg),
+            });
+        }
+
+        // `\frac{dy}{dx}` => Derivative(y, x, 1)
+        if let Some((numerator, denominator)) = Self::split_fraction(s) {
+            if let Some(formula) = Self::parse_derivative(&numerator, &denominator, "", config)? {
+                return Ok(formula);
+            }
+        }
+
+        // `\frac{x}{2}` => x * 1 / 2
+        if let Some((numerator, denominator)) = Self::split_fraction(s) {
+            return Ok(Self::Mul(vec![
+                Self::parse_by_add(&numerator, config)?,
+                Self::Recip(Box::new(Self::parse_by_add(&denominator, config)?)),
+            ]));
+        }
+
+        // `\binom{n}{k}` => Binomial(n, k)
+        if let Some((n, k)) = Self::split_binomial(s) {
+            return Ok(Self::Binomial(
+                Box::new(Self::parse_by_add(&n, config)?),
+                Box::new(Self::parse_by_add(&k, config)?),
+            ));
+        }
+
+        if config.is_constant(s) {
+            return Ok(Self::Const(s.to_string()));
+        }
+
+        if symbol::is_empty_set(s) {
+            return Ok(Self::Set(vec![]));
+        }
+
+        if config.is_noncommutative(s) {
+            return Ok(Self::Operator(s.to_string()));
+        }
+
+        if let Some(name) = s.strip_prefix('\\') {
+            if !symbol::is_symbol_macro(name) {
+                error!("Mul: unknown control sequence => {:?}", s);
+                return Err(ParseFormulaError);
+            }
+        }
+
+        return Ok(Self::TS(s.to_string()));
+    }
+
+    // `\sin^2 x` => Pow(Func(sin, x), 2), `\sin(x)^2` => Pow(Func(sin, x), 2), `\log_2 x` => log x / log 2
+    fn parse_function(
+        name: &str,
+        sub: Option<String>,
+        sup: Option<String>,
+        arg: &str,
+        config: &Config,
+    ) -> Result<Self, ParseFormulaError> {
+        let chars = arg.trim().chars().collect_vec();
+        let mut sup = sup;
+        let mut arg = arg.to_string();
+
+        // Scripts after a delimited argument apply to the function
+        if let Some(len) = delimiter::match_group(&chars) {
+            let (scripts_len, arg_sub, arg_sup) = Self::match_scripts(&chars[len..])?;
+
+            if len + scripts_len == chars.len() && arg_sub.is_none() && (sup.is_none() || arg_sup.is_none()) {
+                sup = sup.or(arg_sup);
+                arg = chars[..len].iter().collect();
+            }
+        }
+
+        let mut formula = Self::Func(name.to_string(), Box::new(Self::parse_by_mul(&arg, config)?));
+
+        if let Some(sub) = sub {
+            let base = Self::Func(name.to_string(), Box::new(Self::parse_by_add(&sub, config)?));
+            formula = Self::Mul(vec![formula, Self::Recip(Box::new(base))]);
+        }
+
+        if let Some(sup) = sup {
+            formula = Self::Pow(Box::new(formula), Box::new(Self::parse_by_add(&sup, config)?));
+        }
+
+        return Ok(formula);
+    }
+
+    // `\sum_{k=1}^{n} k^2` => Sum(Bound(k, 1, n), k^2)
+    fn parse_big_operator(
+        name: &str,
+        sub: Option<String>,
+        sup: Option<String>,
+        body: &str,
+        config: &Config,
+    ) -> Result<Self, ParseFormulaError> {
+        let bound = Self::parse_bound(&sub.unwrap_or_default(), sup.as_deref(), config)?;
+
+        // The index is not a constant in the body, e.g. `i` in `\sum_{i=1}^n i`
+        let mut body_config = config.clone();
+        body_config.constants.remove(&bound.var);
+        let body = Self::parse_by_mul(body, &body_config)?;
+
+        if body.is_empty() {
+            error!("Mul: {} without body", name);
+            return Err(ParseFormulaError);
+        }
+
+        return match name {
+            "\\sum" => Ok(Self::Sum(Box::new(bound), Box::new(body))),
+            _ => Ok(Self::Prod(Box::new(bound), Box::new(body))),
+        };
+    }
+
+    // `\lim_{x \to 0^+} x` => Limit(Bound(x, 0, _), Right, x)
+    fn parse_limit(
+        sub: Option<String>,
+        sup: Option<String>,
+        body: &str,
+        config: &Config,
+    ) -> Result<Self, ParseFormulaError> {
+        let sub = match (sub, sup) {
+            (Some(sub), None) => sub,
+            _ => {
+                error!("Mul: \\lim needs only a subscript => {:?}", body);
+                return Err(ParseFormulaError);
+            }
+        };
+
+        let parts = Self::split_top_level(&sub, &symbol::ARROWS, &[], "Limit")?;
+
+        if parts.len() != 2 {
+            error!("Limit: invalid approach => {:?}", sub);
+            return Err(ParseFormulaError);
+        }
+
+        let (target, side) = Self::split_side(parts[1].1.trim());
+        let mut bound = Self::parse_bound(&parts[0].1, None, config)?;
+        bound.lower = Some(Self::parse_by_add(target, config)?);
+
+        // The variable is not a constant in the body, e.g. `e` in `\lim_{e \to 0} e`
+        let mut body_config = config.clone();
+        body_config.constants.remove(&bound.var);
+        let body = Self::parse_by_mul(body, &body_config)?;
+
+        if body.is_empty() || bound.lower.as_ref().map_or(true, Self::is_empty) {
+            error!("Limit: missing body or target => {:?}", sub);
+            return Err(ParseFormulaError);
+        }
+
+        return Ok(Self::Limit(Box::new(bound), side, Box::new(body)));
+    }
+
+    // `0^+` => (`0`, Right), `1-` => (`1`, Left), `\infty` => (`\infty`, Both)
+    fn split_side(target: &str) -> (&str, Side) {
+        for (suffix, side) in [
+            ("^{+}", Side::Right),
+            ("^+", Side::Right),
+            ("+", Side::Right),
+            ("^{-}", Side::Left),
+            ("^-", Side::Left),
+            ("-", Side::Left),
+        ] {
+            if let Some(target) = target.strip_suffix(suffix) {
+                return (target.trim(), side);
+            }
+        }
+
+        return (target, Side::Both);
+    }
+
+    // `\int_0^1 x^2 dx` => Integral(\int, Bound(x, 0, 1), x^2)
+    // `\iint f dx dy` => Integral(\iint, Bound(y, _, _), Integral(\iint, Bound(x, _, _), f)) with limits on the outermost
+    // A subscript alone is a region or a contour, e.g. `\oint_C f dz` => Integral(\oint, Bound(z, region C), f)
+    fn parse_integral(
+        name: &str,
+        sub: Option<String>,
+        sup: Option<String>,
+        body: &str,
+        config: &Config,
+    ) -> Result<Self, ParseFormulaError> {
+        let (mut body, tail) = Self::split_integrand(body);
+        let mut vars = Vec::new();
+
+        for _ in 0..symbol::integral_dimension(name) {
+            let (rest, var) = Self::split_differential(&body).ok_or_else(|| {
+                error!("Mul: {} without differential => {:?}", name, body);
+                return ParseFormulaError;
+            })?;
+
+            body = rest;
+            vars.insert(0, var);
+        }
+
+        // The integration variables are not constants in the body, e.g. `e` in `\int e^2 de`
+        let mut body_config = config.clone();
+        vars.iter().for_each(|var| {
+            body_config.constants.remove(var);
+        });
+
+        // `\int dx` integrates 1
+        let mut formula = match Self::parse_by_mul(&body, &body_config)? {
+            Self::Empty => Self::TS("1".to_string()),
+            formula => formula,
+        };
+
+        let (lower, upper, region) = match (sub, sup) {
+            (Some(sub), Some(sup)) => (
+                Some(Self::parse_by_add(&sub, config)?),
+                Some(Self::parse_by_add(&sup, config)?),
+                None,
+            ),
+            (Some(sub), None) => (None, None, Some(Self::parse_by_logic(&sub, config)?)),
+            (None, Some(sup)) => (None, Some(Self::parse_by_add(&sup, config)?), None),
+            (None, None) => (None, None, None),
+        };
+
+        for (n, var) in vars.iter().enumerate() {
+            let bound = if n + 1 == vars.len() {
+                Bound {
+                    var: var.clone(),
+                    lower: lower.clone(),
+                    upper: upper.clone(),
+                    region: region.clone(),
+                }
+            } else {
+                Bound {
+                    var: var.clone(),
+                    lower: None,
+                    upper: None,
+                    region: None,
+                }
+            };
+
+            formula = Self::Integral(name.to_string(), Box::new(bound), Box::new(formula));
+        }
+
+        // Factors after the differentials, e.g. `\cdot 2` in `\int_0^1 x dx \cdot 2`
+        return match Self::parse_by_mul(&tail, config)? {
+            Self::Empty => Ok(formula),
+            Self::Mul(mut factors) => {
+                factors.insert(0, formula);
+                Ok(Self::Mul(factors))
+            }
+            factor => Ok(Self::Mul(vec![formula, factor])),
+        };
+    }
+
+    // Split after the differentials followed by a product or a quotient, e.g. `x dx \cdot 2` => (`x dx`, ` \cdot 2`)
+    fn split_integrand(s: &str) -> (String, String) {
+        let re = Regex::new(r"(\\mathrm\{d\}|\\operatorname\{d\}|d)\s*([A-Za-z]|\\[A-Za-z]+)").unwrap();
+        let separators = [Operator::Mul.symbols(), Operator::Div.symbols()].concat();
+
+        for m in re.find_iter(s) {
+            let (body, rest) = s.split_at(m.end());
+            let depth = body.chars().fold(0, |depth, c| match c {
+                '{' | '(' => depth + 1,
+                '}' | ')' => depth - 1,
+                _ => depth,
+            });
+
+            if depth == 0 && separators.iter().any(|sep| rest.trim_start().starts_with(sep)) {
+                return (body.to_string(), rest.to_string());
+            }
+        }
+
+        return (s.to_string(), String::new());
+    }
+
+    // `x^2 dx` => (`x^2 `, `x`), `f \mathrm{d}\theta` => (`f `, `\theta`)
+    fn split_differential(s: &str) -> Option<(String, String)> {
+        let re = Regex::new(r"^(.*?)(\\mathrm\{d\}|\\operatorname\{d\}|d)\s*([A-Za-z]|\\[A-Za-z]+)\s*$").unwrap();
+        let captures = re.captures(s)?;
+        let rest = captures.get(1)?.as_str();
+
+        // `d` at the end of a control word is not a differential, e.g. `\lambda`
+        if &captures[2] == "d" && Regex::new(r"\\[A-Za-z]*$").unwrap().is_match(rest) {
+            return None;
+        }
+
+        return Some((rest.to_string(), captures[3].to_string()));
+    }
+
+    // `k=1` and `n` => Bound(k, 1, n), `k` => Bound(k, _, _), `k \in S` => Bound(k, region S)
+    fn parse_bound(sub: &str, sup: Option<&str>, config: &Config) -> Result<Bound, ParseFormulaError> {
+        let parts = Self::split_top_level(sub, &["\\in"], &[], "Bound")?;
+
+        // A set instead of limits, e.g. `k \in S`
+        if let [(_, var), (_, region)] = parts.as_slice() {
+            return Ok(Bound {
+                region: Some(Self::parse_by_logic(region, config)?),
+                ..Self::parse_bound(var, sup, config)?
+            });
+        }
+
+        let (var, lower) = match sub.split_once('=') {
+            Some((var, lower)) => (var.trim(), Some(Self::parse_by_add(lower, config)?)),
+            None => (sub.trim(), None),
+        };
+
+        if var.is_empty() || var.contains(|c: char| c.is_whitespace() || "+-*/(){}".contains(c)) {
+            error!("Bound: invalid variable => {:?}", var);
+            return Err(ParseFormulaError);
+        }
+
+        let upper = match sup {
+            Some(sup) => Some(Self::parse_by_add(sup, config)?),
+            None => None,
+        };
+
+        return Ok(Bound {
+            var: var.to_string(),
+            lower,
+            upper,
+            region: None,
+        });
+    }
+
+    // `\mathrm{KE}` => (`mathrm`, `KE`)
+    fn split_argument_macro(s: &str) -> Option<(String, String)> {
+        let name = s.strip_prefix('\\')?.split(|c: char| !c.is_ascii_alphabetic()).next()?;
+
+        if !symbol::is_argument_macro(name) {
+            return None;
+        }
+
+        let (_, arg) = delimiter::strip_group(&s[1 + name.len()..].trim().chars().collect_vec())?;
+        return Some((name.to_string(), arg.trim().to_string()));
+    }
+
+    // `\ddot{x}` => (2, `x`)
+    fn split_dot(s: &str) -> Option<(usize, String)> {
+        let name = s.strip_prefix('\\')?.split(|c: char| !c.is_ascii_alphabetic()).next()?;
+        let order = symbol::dot_order(name)?;
+        let (_, args) = Self::match_arguments(&s[1 + name.len()..].chars().collect_vec(), 1)?;
+        return Some((order, args[0].clone()));
+    }
+
+    // `\bar z` => `z`
+    fn split_conjugate(s: &str) -> Option<String> {
+        let name = s.strip_prefix('\\')?.split(|c: char| !c.is_ascii_alphabetic()).next()?;
+
+        if !symbol::is_conjugate_macro(name) {
+            return None;
+        }
+
+        let (_, args) = Self::match_arguments(&s[1 + name.len()..].chars().collect_vec(), 1)?;
+        return Some(args[0].clone());
+    }
+
+    // `\frac{d}{dx}` and `\frac{\partial^2}{\partial x^2}` apply to the rest of the term
+    fn is_derivative_operator(s: &str) -> bool {
+        return match Self::split_fraction(s) {
+            Some((numerator, denominator)) => {
+                matches!(Self::split_differential_operator(&numerator), Some((_, body)) if body.trim().is_empty())
+                    && Self::split_differential_variables(&denominator).is_some()
+            }
+            None => false,
+        };
+    }
+
+    // `d^2 y` => (2, `y`), `\partial f` => (1, `f`), `\mathrm{d}` => (1, ``)
+    fn split_differential_operator(s: &str) -> Option<(usize, String)> {
+        let re = Regex::new(r"^\s*(?:d|\\mathrm\{d\}|\\partial\b)\s*(?:\^\s*(?:(\d)|\{\s*(\d+)\s*\}))?(.*)$").unwrap();
+        let captures = re.captures(s)?;
+        let order = match captures.get(1).or_else(|| captures.get(2)) {
+            Some(order) => order.as_str().parse::<usize>().ok()?,
+            None => 1,
+        };
+        let body = captures[3].to_string();
+
+        // `d_1` is not a differential operator
+        if body.starts_with(['^', '_']) {
+            return None;
+        }
+
+        return Some((order, body));
+    }
+
+    // `dx^2` => [(x, 2)], `\partial x \partial y` => [(x, 1), (y, 1)]
+    fn split_differential_variables(s: &str) -> Option<Vec<(String, usize)>> {
+        let re = Regex::new(
+            r"^\s*(?:d|\\mathrm\{d\}|\\partial\b)\s*([A-Za-z]|\\[A-Za-z]+)\s*(?:\^\s*(?:(\d)|\{\s*(\d+)\s*\}))?",
+        )
+        .unwrap();
+        let mut vars = Vec::new();
+        let mut rest = s.trim();
+
+        while !rest.is_empty() {
+            let captures = re.captures(rest)?;
+            let order = match captures.get(2).or_else(|| captures.get(3)) {
+                Some(order) => order.as_str().parse::<usize>().ok()?,
+                None => 1,
+            };
+
+            vars.push((captures[1].to_string(), order));
+            rest = rest[captures[0].len()..].trim_start();
+        }
+
+        return if vars.is_empty() { None } else { Some(vars) };
+    }
+
+    // `\frac{d^2 y}{dx^2}` => Derivative(y, x, 2), `\frac{d}{dx}` and `x^2` => Derivative(x^2, x, 1)
+    // None for an ordinary fraction like `\frac{d}{x}`
+    fn parse_derivative(
+        numerator: &str,
+        denominator: &str,
+        operand: &str,
+        config: &Config,
+    ) -> Result<Option<Self>, ParseFormulaError> {
+        let (order, body) = match Self::split_differential_operator(numerator) {
+            Some(operator) => operator,
+            None => return Ok(None),
+        };
+        let vars = match Self::split_differential_variables(denominator) {
+            Some(vars) => vars,
+            None => return Ok(None),
+        };
+
+        if vars.iter().map(|(_, order)| order).sum::<usize>() != order {
+            error!(
+                "Mul: mismatched orders of derivative => {:?} / {:?}",
+                numerator, denominator
+            );
+            return Err(ParseFormulaError);
+        }
+
+        let body = match (body.trim(), operand.trim()) {
+            ("", "") => {
+                error!("Mul: derivative without body => {:?} / {:?}", numerator, denominator);
+                return Err(ParseFormulaError);
+            }
+            ("", operand) => operand.to_string(),
+            (body, "") => body.to_string(),
+            (body, operand) => format!("{} {}", body, operand),
+        };
+
+        // `f(x)` differentiated by `x` is `f` itself, as in `f'(x)`
+        let body = match Regex::new(r"^([A-Za-z])\s*\(\s*([A-Za-z])\s*\)$")
+            .unwrap()
+            .captures(&body)
+        {
+            Some(captures) if vars.iter().any(|(var, _)| var == &captures[2]) => captures[1].to_string(),
+            _ => body,
+        };
+
+        let mut formula = Self::parse_by_add(&body, config)?;
+
+        for (var, order) in vars {
+            formula = Self::Derivative(Box::new(formula), var, order);
+        }
+
+        return Ok(Some(formula));
+    }
+
+    // `y''` => Derivative(y, x, 2), `f'(t)` => Derivative(f, t, 1), `f'(2)` => At(Bound(x, 2, _), Derivative(f, x, 1))
+    // The prime variable itself is another symbol, e.g. a coordinate `x'` => `x'`
+    fn parse_primes(s: &str, config: &Config) -> Result<Option<Self>, ParseFormulaError> {
+        let (name, primes) = match s.find('\'') {
+            Some(i) if i > 0 => (&s[..i], &s[i..]),
+            _ => return Ok(None),
+        };
+        let order = primes.chars().take_while(|c| *c == '\'').count();
+        let arg = primes[order..].trim();
+        let body = Self::parse_base(name, config)?;
+        let var = &config.prime_variable;
+
+        if arg.is_empty() && name.trim() == var {
+            return Ok(Some(Self::TS(s.trim().to_string())));
+        }
+
+        let derivative = Self::Derivative(Box::new(body.clone()), var.to_string(), order);
+
+        if arg.is_empty() {
+            return Ok(Some(derivative));
+        }
+
+        let point = match Self::strip_single_paren(arg) {
+            Some((Delimiter::Paren, inner)) => Self::parse_by_add(&inner, config)?,
+            _ => return Ok(Some(Self::Mul(vec![derivative, Self::parse_by_add(arg, config)?]))),
+        };
+
+        return match point {
+            Self::TS(point) if !Self::is_number(&point) => Ok(Some(Self::Derivative(Box::new(body), point, order))),
+            Self::Empty => {
+                error!("Prime: missing point => {:?}", s);
+                Err(ParseFormulaError)
+            }
+            point => Ok(Some(Self::At(
+                Box::new(Bound {
+                    var: var.to_string(),
+                    lower: Some(point),
+                    upper: None,
+                    region: None,
+                }),
+                Box::new(derivative),
+            ))),
+        };
+    }
+
+    // `\frac{x}{2}` => (`x`, `2`), `\dfrac12` => (`1`, `2`)
+    fn split_fraction(s: &str) -> Option<(String, String)> {
+        return Self::split_two_arguments(s, symbol::is_fraction_macro);
+    }
+
+    // `\binom{n}{k}` => (`n`, `k`)
+    fn split_binomial(s: &str) -> Option<(String, String)> {
+        return Self::split_two_arguments(s, symbol::is_binomial_macro);
+    }
+
+    fn split_two_arguments(s: &str, is_macro: fn(&str) -> bool) -> Option<(String, String)> {
+        let name = s.strip_prefix('\\')?.split(|c: char| !c.is_ascii_alphabetic()).next()?;
+
+        if !is_macro(name) {
+            return None;
+        }
+
+        let (_, args) = Self::match_arguments(&s[1 + name.len()..].chars().collect_vec(), 2)?;
+        return Some((args[0].clone(), args[1].clone()));
+    }
+
+    // `\text{m}` => Text(`m`), `\mathrm{d}` => `d`, `\mathrm{KE}` => `KE`, `\mathbb{R}` => `\mathbb{R}`
+    // Bold capitals stand for matrices, e.g. `\mathbf{A}` => Operator(`A`)
+    fn parse_argument_macro(name: &str, arg: &str, config: &Config) -> Result<Self, ParseFormulaError> {
+        if symbol::TEXT_MACROS.contains(&name) {
+            return Ok(Self::Text(arg.to_string()));
+        }
+
+        if symbol::is_bold_font(name) && arg.chars().count() == 1 && arg.chars().all(|c| c.is_ascii_uppercase()) {
+            return Ok(Self::Operator(arg.to_string()));
+        }
+
+        if !config.unwrapped_fonts.contains(name) {
+            return Ok(Self::TS(format!("\\{}{{{}}}", name, arg)));
+        }
+
+        if arg.chars().count() > 1 && arg.chars().all(|c| c.is_alphabetic()) {
+            return Ok(Self::TS(arg.to_string()));
+        }
+
+        return Self::parse_by_add(arg, config);
+    }
+
+    fn expand_paren(self) -> Self {
+        return match self {
+            // Neg(Neg(x)) => x
+            Self::Neg(boxed_formula) => {
+                return match *boxed_formula {
+                    Self::Neg(inner_boxed_formula) => *inner_boxed_formula,
+                    _ => Self::Neg(boxed_formula),
+                }
+            }
+
+            // Add([Add[x, y], z]) => Add([x, y, z])
+            Self::Add(formulas) => {
+                return Self::Add(Self::expand_add(
+                    formulas.into_iter().map(Self::expand_paren).collect_vec(),
+                ))
+            }
+
+            // Mul(Mul[x, y], z]) => Mul([x, y, z]), Mul([1, x]) => x
+            Self::Mul(formulas) => {
+                let mut factors = Self::expand_mul(formulas.into_iter().map(Self::expand_paren).collect_vec());
+
+                let is_one = |f: &Self| matches!(f, Self::TS(s) if s == "1");
+
+                if factors.iter().filter(|f| !is_one(f)).count() == 1 {
+                    factors.retain(|f| !is_one(f));
+                    return factors.remove(0);
+                }
+
+                return Self::Mul(factors);
+            }
+
+            // |-x| => |x|, ||x|| => |x|, |x - y| => |y - x|
+            Self::Abs(boxed_formula) => {
+                return match Self::expand_paren(*boxed_formula) {
+                    Self::Abs(inner_boxed_formula) => Self::Abs(inner_boxed_formula),
+                    formula => Self::Abs(Box::new(Self::unsigned(formula))),
+                }
+            }
+
+            // Power, subscript, function, conjugate, overline, combinatorial and modular operators
+            Self::Pow(_, _)
+            | Self::Subscript(_, _)
+            | Self::Func(_, _)
+            | Self::Conj(_)
+            | Self::Overline(_)
+            | Self::Factorial(_)
+            | Self::Binomial(_, _)
+            | Self::Permutation(_, _)
+            | Self::Floor(_)
+            | Self::Ceil(_)
+            | Self::Round(_)
+            | Self::Mod(_, _) => self.map_children(Self::expand_paren),
+
+            // \sum_{i=1}^{n} i => \sum_{#0=1}^{n} #0, \int t dt => \int #0 d#0, \{x \mid x > 0\} => \{#0.0 \mid #0.0 > 0\}
+            Self::Sum(_, _)
+            | Self::Prod(_, _)
+            | Self::Integral(_, _, _)
+            | Self::Limit(_, _, _)
+            | Self::At(_, _)
+            | Self::Forall(_, _)
+            | Self::Exists(_, _)
+            | Self::SetBuilder(_, _, _) => self.map_children(Self::expand_paren).canonicalize_bound(),
+
+            // \frac{d}{dx} x^2 => 2x, \frac{\partial^2 f}{\partial y \partial x} => \frac{\partial^2 f}{\partial x \partial y}
+            Self::Derivative(_, _, _) => self.map_children(Self::expand_paren).canonicalize_derivative(),
+
+            // Union([Union([A, B]), C]) => Union([A, B, C])
+            Self::Union(sets) => Self::Union(Self::expand_union(
+                sets.into_iter().map(Self::expand_paren).collect_vec(),
+            )),
+
+            // Intersection([Intersection([A, B]), C]) => Intersection([A, B, C])
+            Self::Intersection(sets) => Self::Intersection(Self::expand_intersection(
+                sets.into_iter().map(Self::expand_paren).collect_vec(),
+            )),
+
+            // And([And([p, q]), r]) => And([p, q, r])
+            Self::And(formulas) => Self::And(Self::expand_and(
+                formulas.into_iter().map(Self::expand_paren).collect_vec(),
+            )),
+
+            // Or([Or([p, q]), r]) => Or([p, q, r])
+            Self::Or(formulas) => Self::Or(Self::expand_or(
+                formulas.into_iter().map(Self::expand_paren).collect_vec(),
+            )),
+
+            // x > y => y < x
+            Self::Relation(relation, l, r) => match relation {
+                Relation::Gt | Relation::Ge => Self::Relation(relation.flipped(), r, l),
+                _ => Self::Relation(relation, l, r),
+            }
+            .map_children(Self::expand_paren),
+
+            // Elements of a matrix, a set, a tuple or a list, and operands of a connective
+            Self::Matrix(_)
+            | Self::Set(_)
+            | Self::Tuple(_)
+            | Self::List(_)
+            | Self::PlusMinus(_)
+            | Self::MinusPlus(_)
+            | Self::Difference(_, _)
+            | Self::In(_, _)
+            | Self::Interval(_, _, _, _)
+            | Self::Not(_)
+            | Self::Xor(_)
+            | Self::Implies(_, _)
+            | Self::Iff(_, _)
+            | Self::Congruence(_, _, _) => self.map_children(Self::expand_paren),
+
+            // ‖-v‖ => ‖v‖
+            Self::Norm(boxed_formula) => {
+                return Self::Norm(Box::new(Self::unsigned(Self::expand_paren(*boxed_formula))))
+            }
+
+            // o.w.
+            _ => self,
+        };
+    }
+
+    // Apply `f` to each direct sub-formula
+    pub(crate) fn map_children<F: Fn(Self) -> Self>(self, f: F) -> Self {
+        return match self {
+            Self::Neg(formula) => Self::Neg(Box::new(f(*formula))),
+            Self::PlusMinus(formula) => Self::PlusMinus(Box::new(f(*formula))),
+            Self::MinusPlus(formula) => Self::MinusPlus(Box::new(f(*formula))),
+            Self::Recip(formula) => Self::Recip(Box::new(f(*formula))),
+            Self::Abs(formula) => Self::Abs(Box::new(f(*formula))),
+            Self::Conj(formula) => Self::Conj(Box::new(f(*formula))),
+            Self::Overline(formula) => Self::Overline(Box::new(f(*formula))),
+            Self::Norm(formula) => Self::Norm(Box::new(f(*formula))),
+            Self::Factorial(formula) => Self::Factorial(Box::new(f(*formula))),
+            Self::Floor(formula) => Self::Floor(Box::new(f(*formula))),
+            Self::Ceil(formula) => Self::Ceil(Box::new(f(*formula))),
+            Self::Round(formula) => Self::Round(Box::new(f(*formula))),
+            Self::Mod(l, r) => Self::Mod(Box::new(f(*l)), Box::new(f(*r))),
+            Self::Func(name, formula) => Self::Func(name, Box::new(f(*formula))),
+            Self::Derivative(formula, var, order) => Self::Derivative(Box::new(f(*formula)), var, order),
+            Self::Add(formulas) => Self::Add(formulas.into_iter().map(&f).collect_vec()),
+            Self::Mul(formulas) => Self::Mul(formulas.into_iter().map(&f).collect_vec()),
+            Self::Pow(base, exponent) => Self::Pow(Box::new(f(*base)), Box::new(f(*exponent))),
+            Self::Binomial(n, k) => Self::Binomial(Box::new(f(*n)), Box::new(f(*k))),
+            Self::Permutation(n, k) => Self::Permutation(Box::new(f(*n)), Box::new(f(*k))),
+            Self::Subscript(base, sub) => Self::Subscript(Box::new(f(*base)), Box::new(f(*sub))),
+            Self::Sum(bound, body) => Self::Sum(Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Prod(bound, body) => Self::Prod(Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Integral(name, bound, body) => Self::Integral(name, Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Limit(bound, side, body) => Self::Limit(Box::new(bound.map(&f)), side, Box::new(f(*body))),
+            Self::At(bound, body) => Self::At(Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Matrix(rows) => Self::Matrix(
+                rows.into_iter()
+                    .map(|row| row.into_iter().map(&f).collect_vec())
+                    .collect_vec(),
+            ),
+            Self::Set(elements) => Self::Set(elements.into_iter().map(&f).collect_vec()),
+            Self::Tuple(elements) => Self::Tuple(elements.into_iter().map(&f).collect_vec()),
+            Self::List(answers) => Self::List(answers.into_iter().map(&f).collect_vec()),
+            Self::Union(sets) => Self::Union(sets.into_iter().map(&f).collect_vec()),
+            Self::Intersection(sets) => Self::Intersection(sets.into_iter().map(&f).collect_vec()),
+            Self::SetBuilder(vars, element, condition) => {
+                Self::SetBuilder(vars, Box::new(f(*element)), Box::new(f(*condition)))
+            }
+            Self::Forall(bound, body) => Self::Forall(Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Exists(bound, body) => Self::Exists(Box::new(bound.map(&f)), Box::new(f(*body))),
+            Self::Difference(l, r) => Self::Difference(Box::new(f(*l)), Box::new(f(*r))),
+            Self::In(element, set) => Self::In(Box::new(f(*element)), Box::new(f(*set))),
+            Self::Interval(lower_closed, lower, upper, upper_closed) => {
+                Self::Interval(lower_closed, Box::new(f(*lower)), Box::new(f(*upper)), upper_closed)
+            }
+            Self::Not(formula) => Self::Not(Box::new(f(*formula))),
+            Self::And(formulas) => Self::And(formulas.into_iter().map(&f).collect_vec()),
+            Self::Or(formulas) => Self::Or(formulas.into_iter().map(&f).collect_vec()),
+            Self::Xor(formulas) => Self::Xor(formulas.into_iter().map(&f).collect_vec()),
+            Self::Implies(l, r) => Self::Implies(Box::new(f(*l)), Box::new(f(*r))),
+            Self::Iff(l, r) => Self::Iff(Box::new(f(*l)), Box::new(f(*r))),
+            Self::Relation(relation, l, r) => Self::Relation(relation, Box::new(f(*l)), Box::new(f(*r))),
+            Self::Congruence(l, r, modulus) => {
+                Self::Congruence(Box::new(f(*l)), Box::new(f(*r)), Box::new(f(*modulus)))
+            }
+            _ => self,
+        };
+    }
+
+    // Direct sub-formulas
+    pub(crate) fn children(&self) -> Vec<&Self> {
+        return match self {
+            Self::Neg(formula)
+            | Self::PlusMinus(formula)
+            | Self::MinusPlus(formula)
+            | Self::Recip(formula)
+            | Self::Abs(formula)
+            | Self::Conj(formula)
+            | Self::Overline(formula)
+            | Self::Norm(formula)
+            | Self::Factorial(formula)
+            | Self::Floor(formula)
+            | Self::Ceil(formula)
+            | Self::Round(formula)
+            | Self::Func(_, formula)
+            | Self::Derivative(formula, _, _)
+            | Self::Not(formula) => vec![formula],
+            Self::Add(formulas)
+            | Self::Mul(formulas)
+            | Self::Set(formulas)
+            | Self::Tuple(formulas)
+            | Self::List(formulas)
+            | Self::Union(formulas)
+            | Self::Intersection(formulas)
+            | Self::And(formulas)
+            | Self::Or(formulas)
+            | Self::Xor(formulas) => formulas.iter().collect_vec(),
+            Self::Matrix(rows) => rows.iter().flatten().collect_vec(),
+            Self::Pow(l, r)
+            | Self::Binomial(l, r)
+            | Self::Permutation(l, r)
+            | Self::Mod(l, r)
+            | Self::Subscript(l, r)
+            | Self::SetBuilder(_, l, r)
+            | Self::Difference(l, r)
+            | Self::In(l, r)
+            | Self::Interval(_, l, r, _)
+            | Self::Implies(l, r)
+            | Self::Iff(l, r)
+            | Self::Relation(_, l, r) => vec![l, r],
+            Self::Congruence(l, r, modulus) => vec![l, r, modulus],
+            Self::Sum(bound, body)
+            | Self::Prod(bound, body)
+            | Self::Integral(_, bound, body)
+            | Self::Limit(bound, _, body)
+            | Self::At(bound, body)
+            | Self::Forall(bound, body)
+            | Self::Exists(bound, body) => bound
+                .lower
+                .iter()
+                .chain(bound.upper.iter())
+                .chain(bound.region.iter())
+                .chain([&**body])
+                .collect_vec(),
+            _ => vec![],
+        };
+    }
+
+    // Variable bound by this formula and its scope, e.g. `k` and `k^2` for `\sum_{k=1}^n k^2`
+    pub(crate) fn binder(&self) -> Option<(&Bound, &Self)> {
+        return match self {
+            Self::Sum(bound, body)
+            | Self::Prod(bound, body)
+            | Self::Integral(_, bound, body)
+            | Self::Limit(bound, _, body)
+            | Self::At(bound, body)
+            | Self::Forall(bound, body)
+            | Self::Exists(bound, body) => Some((bound, body)),
+            _ => None,
+        };
+    }
+
+    // Variables bound by this formula, e.g. [`k`] for `\sum_{k=1}^n k^2`, [`x`, `y`] for `\{(x, y) \mid x < y\}`
+    pub(crate) fn bound_vars(&self) -> Vec<&str> {
+        if let Self::SetBuilder(vars, _, _) = self {
+            return vars.iter().map(String::as_str).collect_vec();
+        }
+
+        return self
+            .binder()
+            .map(|(bound, _)| vec![bound.var.as_str()])
+            .unwrap_or_default();
+    }
+
+    // Sub-formulas in the scope of the bound variables, e.g. [`k^2`] for `\sum_{k=1}^n k^2`
+    fn scope(&self) -> Vec<&Self> {
+        if let Self::SetBuilder(_, element, condition) = self {
+            return vec![element, condition];
+        }
+
+        return self.binder().map(|(_, body)| vec![body]).unwrap_or_default();
+    }
+
+    // Sub-formulas of a binder outside of the scope, e.g. [`1`, `n`] for `\sum_{k=1}^n k^2`
+    fn limits(&self) -> Vec<&Self> {
+        return match self.binder() {
+            Some((bound, _)) => bound
+                .lower
+                .iter()
+                .chain(bound.upper.iter())
+                .chain(bound.region.iter())
+                .collect_vec(),
+            None => vec![],
+        };
+    }
+
+    // Apply `f` to the limits of a binder only
+    fn map_limits<F: Fn(Self) -> Self>(self, f: F) -> Self {
+        let var = match self.binder() {
+            Some((bound, _)) => bound.var.clone(),
+            None => return self,
+        };
+
+        return self.map_binder(&var, f, |body| body);
+    }
+
+    // Rebuild a binder with `var`, mapping its limits and its body separately
+    fn map_binder<F: Fn(Self) -> Self, G: Fn(Self) -> Self>(self, var: &str, limits: F, body: G) -> Self {
+        return match self {
+            Self::Sum(bound, b) => Self::Sum(Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b))),
+            Self::Prod(bound, b) => Self::Prod(Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b))),
+            Self::Integral(name, bound, b) => {
+                Self::Integral(name, Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b)))
+            }
+            Self::Limit(bound, side, b) => {
+                Self::Limit(Box::new(bound.map(&limits).with_var(var)), side, Box::new(body(*b)))
+            }
+            Self::At(bound, b) => Self::At(Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b))),
+            Self::Forall(bound, b) => Self::Forall(Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b))),
+            Self::Exists(bound, b) => Self::Exists(Box::new(bound.map(&limits).with_var(var)), Box::new(body(*b))),
+            _ => self,
+        };
+    }
+
+    // Rename free occurrences of `from` to `to`
+    pub(crate) fn rename(self, from: &str, to: &str) -> Self {
+        if let Self::TS(name) = &self {
+            return if name == from { Self::TS(to.to_string()) } else { self };
+        }
+
+        // `from` is shadowed in the scope of a binder of the same name
+        if self.bound_vars().contains(&from) {
+            return self.map_limits(|f| f.rename(from, to));
+        }
+
+        return self.map_children(|f| f.rename(from, to));
+    }
+
+    // Replace every occurrence of `from` by `to`, e.g. `x_1` by TS(x_1)
+    fn replace(self, from: &Self, to: &Self) -> Self {
+        if self == *from {
+            return to.clone();
+        }
+
+        return self.map_children(|f| f.replace(from, to));
+    }
+
+    // Replace free occurrences of `var` by `value`, e.g. `x^2` with x = `t + 1` => `(t + 1)^2`
+    pub(crate) fn substitute(self, var: &str, value: &Self) -> Self {
+        if let Self::TS(name) = &self {
+            return if name == var { value.clone() } else { self };
         }
 
-        let mut paren_depth = 0;
-        let mut term = String::new();
-        let mut terms = Vec::new();
+        if self.bound_vars().contains(&var) {
+            return self.map_limits(|f| f.substitute(var, value));
+        }
 
-        for c in s.chars() {
-            if vec!['*', '/'].contains(&c) && paren_depth == 0 {
-                // if c == '*' && paren_depth == 0 {
-                terms.push(term);
-                term = String::new();
+        return self.map_children(|f| f.substitute(var, value));
+    }
 
-                if c == '/' {
-                    term.push(c)
-                }
+    // Free variables, e.g. {`n`, `x`} for `\sum_{k=1}^{n} k x`
+    pub(crate) fn free_vars(&self) -> BTreeSet<String> {
+        if let Self::TS(name) = self {
+            return if Self::is_number(name) {
+                BTreeSet::new()
+            } else {
+                BTreeSet::from([name.clone()])
+            };
+        }
 
-                continue;
-            }
+        let bound = self.bound_vars();
+
+        if !bound.is_empty() {
+            let mut vars = self
+                .scope()
+                .into_iter()
+                .flat_map(Self::free_vars)
+                .filter(|v| !bound.contains(&v.as_str()))
+                .collect::<BTreeSet<_>>();
+            vars.extend(self.limits().into_iter().flat_map(Self::free_vars));
+            return vars;
+        }
 
-            if c == ')' {
-                // close paren before open
-                if paren_depth == 0 {
-                    error!("Mul: close paren before open => {:?}", s);
-                    return Err(ParseFormulaError);
-                }
+        return self.children().into_iter().flat_map(Self::free_vars).collect();
+    }
 
-                paren_depth -= 1;
-            }
+    // Free variables in order of first appearance, e.g. [`y`, `x`] for `y + x y`
+    fn ordered_free_vars(&self) -> Vec<String> {
+        let vars = self.free_vars();
+        let mut ordered = Vec::new();
+        let mut stack = vec![self];
 
-            if c == '(' {
-                paren_depth += 1;
+        while let Some(formula) = stack.pop() {
+            match formula {
+                Self::TS(name) if vars.contains(name) && !ordered.contains(name) => ordered.push(name.clone()),
+                _ => stack.extend(formula.children().into_iter().rev()),
             }
-
-            term.push(c);
-            debug!(
-                "Mul: c => {:?}, term => {:?}, paren_depth => {:?}",
-                c, term, paren_depth
-            );
         }
 
-        // push last term
-        if !term.is_empty() {
-            terms.push(term.clone());
-        }
-
-        // some paren has not closed
-        if paren_depth != 0 {
-            error!("Mul: some paren has not closed => {:?}", s);
-            return Err(ParseFormulaError);
-        }
+        return ordered;
+    }
 
-        terms = terms
-            .into_iter()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect_vec();
+    // Maximum nesting of binders, e.g. 2 for `\sum_i \sum_j i j`
+    fn binder_depth(&self) -> usize {
+        let depth = self.children().into_iter().map(Self::binder_depth).max().unwrap_or(0);
+        return if self.bound_vars().is_empty() { depth } else { depth + 1 };
+    }
 
-        if terms.is_empty() {
-            return Ok(Self::Empty);
+    // Rename a bound variable by the depth of nested binders, which identifies alpha-equivalent formulas
+    fn canonicalize_bound(self) -> Self {
+        let depth = self.scope().into_iter().map(Self::binder_depth).max().unwrap_or(0);
+
+        // Numbered in order of appearance in the element, e.g. `\{(x, y) \mid x < y\}` => `\{(#0.0, #0.1) \mid #0.0 < #0.1\}`
+        if let Self::SetBuilder(vars, element, condition) = self {
+            let canonical = (0..vars.len()).map(|i| format!("#{}.{}", depth, i)).collect_vec();
+            let rename = |f: Self| vars.iter().zip(&canonical).fold(f, |f, (var, c)| f.rename(var, c));
+            return Self::SetBuilder(
+                canonical.clone(),
+                Box::new(rename(*element)),
+                Box::new(rename(*condition)),
+            );
         }
 
-        if terms.len() == 1 {
-            if terms[0].starts_with('/') {
-                return Ok(Self::Recip(Box::new(Self::parse_by_mul(terms[0][1..].trim())?)));
-            }
+        let (var, canonical) = match self.binder() {
+            Some((bound, _)) => (bound.var.clone(), format!("#{}", depth)),
+            None => return self,
+        };
 
-            if terms[0].len() <= 1 {
-                return Ok(Formula::TS(terms[0].to_string()));
-            }
+        return self.map_binder(&canonical, |f| f, |body| body.rename(&var, &canonical));
+    }
 
-            // for abbreviate
-            terms = terms[0].split("").map(|s| s.trim().to_string()).collect_vec();
+    // Differentiate an explicit formula of the variable only, and sort the variables of nested derivatives
+    fn canonicalize_derivative(self) -> Self {
+        if let Self::Derivative(body, var, order) = &self {
+            if body.free_vars().iter().all(|v| v == var) {
+                return (0..*order).fold((**body).clone(), |f, _| f.diff(var)).expand_paren();
+            }
         }
 
-        let result_iter = terms.into_iter().map(|s| Self::parse(&s));
+        let mut vars = BTreeMap::new();
+        let mut body = self;
 
-        if result_iter.clone().any(|r| r.is_err()) {
-            return Err(ParseFormulaError);
+        while let Self::Derivative(inner, var, order) = body {
+            *vars.entry(var).or_insert(0) += order;
+            body = *inner;
         }
 
-        let parsed_terms = result_iter.map(|r| r.unwrap()).filter(|f| !f.is_empty()).collect_vec();
-
-        if parsed_terms.is_empty() {
-            return Ok(Formula::Empty);
-        } else {
-            return Ok(Formula::Mul(parsed_terms));
+        for (var, order) in vars {
+            body = Self::Derivative(Box::new(body), var, order);
         }
+
+        return body;
     }
 
-    fn expand_paren(self) -> Self {
+    // Choose one of `x` and `-x` regardless of the sign, e.g. `-x` => `x`, `y - x` => `x - y`
+    fn unsigned(self) -> Self {
         return match self {
-            // Neg(Neg(x)) => x
-            Self::Neg(boxed_formula) => {
-                return match *boxed_formula {
-                    Self::Neg(inner_boxed_formula) => *inner_boxed_formula,
-                    _ => Self::Neg(boxed_formula),
-                }
-            }
-
-            // Add([Add[x, y], z]) => Add([x, y, z])
+            Self::Neg(boxed_formula) => *boxed_formula,
             Self::Add(formulas) => {
-                return Self::Add(Self::expand_add(
-                    formulas.into_iter().map(Self::expand_paren).collect_vec(),
-                ))
-            }
+                let negated = formulas
+                    .iter()
+                    .map(|f| Self::expand_paren(Self::Neg(Box::new(f.clone()))))
+                    .sorted()
+                    .collect_vec();
+                let sorted = formulas.into_iter().sorted().collect_vec();
 
-            // Mul(Mul[x, y], z]) => Mul([x, y, z])
-            Self::Mul(formulas) => {
-                return Self::Mul(Self::expand_mul(
-                    formulas.into_iter().map(Self::expand_paren).collect_vec(),
-                ))
+                Self::Add(sorted.min(negated))
             }
-
-            // o.w.
             _ => self,
         };
     }
@@ -279,6 +2593,46 @@ impl Formula {
             .collect_vec();
     }
 
+    fn expand_union(selfs: Vec<Self>) -> Vec<Self> {
+        return selfs
+            .into_iter()
+            .flat_map(|f| match f {
+                Self::Union(sets) => sets,
+                _ => vec![f],
+            })
+            .collect_vec();
+    }
+
+    fn expand_intersection(selfs: Vec<Self>) -> Vec<Self> {
+        return selfs
+            .into_iter()
+            .flat_map(|f| match f {
+                Self::Intersection(sets) => sets,
+                _ => vec![f],
+            })
+            .collect_vec();
+    }
+
+    fn expand_and(selfs: Vec<Self>) -> Vec<Self> {
+        return selfs
+            .into_iter()
+            .flat_map(|f| match f {
+                Self::And(formulas) => formulas,
+                _ => vec![f],
+            })
+            .collect_vec();
+    }
+
+    fn expand_or(selfs: Vec<Self>) -> Vec<Self> {
+        return selfs
+            .into_iter()
+            .flat_map(|f| match f {
+                Self::Or(formulas) => formulas,
+                _ => vec![f],
+            })
+            .collect_vec();
+    }
+
     fn expand_mul(selfs: Vec<Self>) -> Vec<Self> {
         return selfs
             .into_iter()
@@ -289,34 +2643,197 @@ impl Formula {
             .collect_vec();
     }
 
-    fn eq_without_expand(&self, other: &Self) -> bool {
+    // Sorted commutative factors and the rest in order, excluding multiplication by 1
+    fn split_commutative(formulas: &[Self]) -> (Vec<&Self>, Vec<&Self>) {
+        let (scalars, operators): (Vec<_>, Vec<_>) = formulas
+            .iter()
+            .filter(|&f| f != &Self::TS("1".to_string()))
+            .partition(|f| f.is_commutative());
+
+        return (scalars.into_iter().sorted().collect_vec(), operators);
+    }
+
+    // Contains no operators or matrices outside of a scalar like `|A|`, e.g. `2 x` => true, `A^2` => false
+    fn is_commutative(&self) -> bool {
+        return match self {
+            Self::Operator(_) | Self::Matrix(_) => false,
+            Self::Abs(_) | Self::Norm(_) => true,
+            _ => self.children().into_iter().all(Self::is_commutative),
+        };
+    }
+
+    pub(crate) fn eq_without_expand(&self, other: &Self) -> bool {
         return match (self, other) {
             // l == r
             (Self::TS(l), Self::TS(r)) => l == r,
 
+            // \pi == \pi
+            (Self::Const(l), Self::Const(r)) => l == r,
+
+            // \text{l} == \text{r}
+            (Self::Text(l), Self::Text(r)) => l == r,
+
+            // A == A
+            (Self::Operator(l), Self::Operator(r)) => l == r,
+
+            // |l| == |r|
+            (Self::Abs(l_formula), Self::Abs(r_formula)) => l_formula == r_formula,
+
+            // \overline{l} == \overline{r}
+            (Self::Conj(l_formula), Self::Conj(r_formula)) => l_formula == r_formula,
+            (Self::Overline(l_formula), Self::Overline(r_formula)) => l_formula == r_formula,
+
+            // ‖l‖ == ‖r‖
+            (Self::Norm(l_formula), Self::Norm(r_formula)) => l_formula == r_formula,
+
+            // l_base ^ l_exponent == r_base ^ r_exponent
+            (Self::Pow(l_base, l_exponent), Self::Pow(r_base, r_exponent)) => {
+                l_base == r_base && l_exponent == r_exponent
+            }
+
+            // l! == r!
+            (Self::Factorial(l_formula), Self::Factorial(r_formula)) => l_formula == r_formula,
+
+            // \binom{l_n}{l_k} == \binom{r_n}{r_k}, P(l_n, l_k) == P(r_n, r_k)
+            (Self::Binomial(l_n, l_k), Self::Binomial(r_n, r_k))
+            | (Self::Permutation(l_n, l_k), Self::Permutation(r_n, r_k)) => l_n == r_n && l_k == r_k,
+
+            // \lfloor l \rfloor == \lfloor r \rfloor, \lceil l \rceil == \lceil r \rceil, \lfloor l \rceil == \lfloor r \rceil
+            (Self::Floor(l_formula), Self::Floor(r_formula))
+            | (Self::Ceil(l_formula), Self::Ceil(r_formula))
+            | (Self::Round(l_formula), Self::Round(r_formula)) => l_formula == r_formula,
+
+            // l_a \bmod l_n == r_a \bmod r_n
+            (Self::Mod(l_l, l_r), Self::Mod(r_l, r_r)) => l_l == r_l && l_r == r_r,
+
+            // a \equiv b \pmod{n} == b \equiv a \pmod{n}
+            (Self::Congruence(l_l, l_r, l_modulus), Self::Congruence(r_l, r_r, r_modulus)) => {
+                l_modulus == r_modulus && ((l_l == r_l && l_r == r_r) || (l_l == r_r && l_r == r_l))
+            }
+
+            // \sin l == \sin r
+            (Self::Func(l_name, l_formula), Self::Func(r_name, r_formula)) => {
+                l_name == r_name && l_formula == r_formula
+            }
+
+            // \frac{d^n l}{dx^n} == \frac{d^n r}{dx^n}
+            (Self::Derivative(l_formula, l_var, l_order), Self::Derivative(r_formula, r_var, r_order)) => {
+                l_formula == r_formula && l_var == r_var && l_order == r_order
+            }
+
+            // l_base _ l_sub == r_base _ r_sub
+            (Self::Subscript(l_base, l_sub), Self::Subscript(r_base, r_sub)) => l_base == r_base && l_sub == r_sub,
+
+            // \sum_{l} l_body == \sum_{r} r_body
+            (Self::Sum(l_bound, l_body), Self::Sum(r_bound, r_body)) => l_bound == r_bound && l_body == r_body,
+
+            // \prod_{l} l_body == \prod_{r} r_body
+            (Self::Prod(l_bound, l_body), Self::Prod(r_bound, r_body)) => l_bound == r_bound && l_body == r_body,
+
+            // \int_{l} l_body == \int_{r} r_body with the same macro
+            (Self::Integral(l_name, l_bound, l_body), Self::Integral(r_name, r_bound, r_body)) => {
+                l_name == r_name && l_bound == r_bound && l_body == r_body
+            }
+
+            // \lim_{l} l_body == \lim_{r} r_body
+            (Self::Limit(l_bound, l_side, l_body), Self::Limit(r_bound, r_side, r_body)) => {
+                l_bound == r_bound && l_side == r_side && l_body == r_body
+            }
+
+            // l_body at l_point == r_body at r_point
+            (Self::At(l_bound, l_body), Self::At(r_bound, r_body)) => l_bound == r_bound && l_body == r_body,
+
+            // element-wise with the same dimensions
+            (Self::Matrix(l_rows), Self::Matrix(r_rows)) => l_rows == r_rows,
+
+            // {l_elements} == {r_elements} ignoring order and duplicates
+            (Self::Set(l_elements), Self::Set(r_elements)) => {
+                l_elements.iter().all(|f| r_elements.contains(f)) && r_elements.iter().all(|f| l_elements.contains(f))
+            }
+
+            // (l_1, l_2) == (r_1, r_2) in order
+            (Self::Tuple(l_elements), Self::Tuple(r_elements)) => l_elements == r_elements,
+
+            // l_1, l_2 == r_2, r_1 as multisets
+            (Self::List(l_answers), Self::List(r_answers)) => {
+                l_answers.iter().sorted().collect_vec() == r_answers.iter().sorted().collect_vec()
+            }
+
+            // {l_element | l_condition} == {r_element | r_condition}
+            (Self::SetBuilder(l_vars, l_element, l_condition), Self::SetBuilder(r_vars, r_element, r_condition)) => {
+                l_vars == r_vars && l_element == r_element && l_condition == r_condition
+            }
+
+            // l < r == l < r, l = r == r = l
+            (Self::Relation(l_relation, l_l, l_r), Self::Relation(r_relation, r_l, r_r)) => {
+                l_relation == r_relation
+                    && ((l_l == r_l && l_r == r_r) || (l_relation.flipped() == *l_relation && l_l == r_r && l_r == r_l))
+            }
+
+            // ∀l. l_body == ∀r. r_body
+            (Self::Forall(l_bound, l_body), Self::Forall(r_bound, r_body)) => l_bound == r_bound && l_body == r_body,
+
+            // ∃l. l_body == ∃r. r_body
+            (Self::Exists(l_bound, l_body), Self::Exists(r_bound, r_body)) => l_bound == r_bound && l_body == r_body,
+
+            // A ∪ B == B ∪ A, A ∩ B == B ∩ A
+            (Self::Union(l_sets), Self::Union(r_sets)) | (Self::Intersection(l_sets), Self::Intersection(r_sets)) => {
+                l_sets.iter().sorted().collect_vec() == r_sets.iter().sorted().collect_vec()
+            }
+
+            // A \ B == A \ B
+            (Self::Difference(l_l, l_r), Self::Difference(r_l, r_r)) => l_l == r_l && l_r == r_r,
+
+            // [l_lower, l_upper) == [r_lower, r_upper)
+            (
+                Self::Interval(l_lower_closed, l_lower, l_upper, l_upper_closed),
+                Self::Interval(r_lower_closed, r_lower, r_upper, r_upper_closed),
+            ) => {
+                l_lower_closed == r_lower_closed
+                    && l_lower == r_lower
+                    && l_upper == r_upper
+                    && l_upper_closed == r_upper_closed
+            }
+
+            // x ∈ A == x ∈ A
+            (Self::In(l_element, l_set), Self::In(r_element, r_set)) => l_element == r_element && l_set == r_set,
+
+            // ¬l == ¬r
+            (Self::Not(l_formula), Self::Not(r_formula)) => l_formula == r_formula,
+
+            // p ∧ q == q ∧ p, p ∨ q == q ∨ p, p ⊕ q == q ⊕ p
+            (Self::And(l_formulas), Self::And(r_formulas))
+            | (Self::Or(l_formulas), Self::Or(r_formulas))
+            | (Self::Xor(l_formulas), Self::Xor(r_formulas)) => {
+                l_formulas.iter().sorted().collect_vec() == r_formulas.iter().sorted().collect_vec()
+            }
+
+            // p → q == p → q
+            (Self::Implies(l_premise, l_conclusion), Self::Implies(r_premise, r_conclusion)) => {
+                l_premise == r_premise && l_conclusion == r_conclusion
+            }
+
+            // p ↔ q == q ↔ p
+            (Self::Iff(l_l, l_r), Self::Iff(r_l, r_r)) => (l_l == r_l && l_r == r_r) || (l_l == r_r && l_r == r_l),
+
             // sum(l_formulas) == sum(r_formulas)
             (Self::Add(l_formulas), Self::Add(r_formulas)) => {
                 l_formulas.iter().sorted().collect_vec() == r_formulas.iter().sorted().collect_vec()
             }
 
             // prod(l_formulas) == prod(r_formulas)
+            // Scalars in any order, non-commutative factors in the same order, e.g. 2 A B == A 2 B != B A 2
             (Self::Mul(l_formulas), Self::Mul(r_formulas)) => {
-                l_formulas
-                    .iter()
-                    // Exclude multiplication by 1
-                    .filter(|&f| f != &Self::TS("1".to_string()))
-                    .sorted()
-                    .collect_vec()
-                    == r_formulas
-                        .iter()
-                        .filter(|&f| f != &Self::TS("1".to_string()))
-                        .sorted()
-                        .collect_vec()
+                Self::split_commutative(l_formulas) == Self::split_commutative(r_formulas)
             }
 
             // - l == - r
             (Self::Neg(l_formula), Self::Neg(r_formula)) => l_formula == r_formula,
 
+            // ± l == ± r, ∓ l == ∓ r
+            (Self::PlusMinus(l_formula), Self::PlusMinus(r_formula))
+            | (Self::MinusPlus(l_formula), Self::MinusPlus(r_formula)) => l_formula == r_formula,
+
             // 1 / l == 1 / r
             (Self::Recip(l_formula), Self::Recip(r_formula)) => l_formula == r_formula,
 
@@ -339,13 +2856,18 @@ impl FromStr for Formula {
 
 impl PartialEq for Formula {
     fn eq(&self, other: &Self) -> bool {
-        return Self::eq_without_expand(&Self::expand_paren(self.clone()), &Self::expand_paren(other.clone()));
+        return Self::eq_without_expand(
+            &Self::expand_paren(self.clone()).fold_numbers(),
+            &Self::expand_paren(other.clone()).fold_numbers(),
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::config::Config;
     use crate::formula::Formula::{self, *};
+    use crate::formula::{Bound, Side};
 
     // helper
     fn ts(s: &str) -> Formula {
@@ -360,6 +2882,10 @@ mod tests {
         return Recip(Box::new(formula));
     }
 
+    fn pow(base: Formula, exponent: Formula) -> Formula {
+        return Pow(Box::new(base), Box::new(exponent));
+    }
+
     #[cfg(test)]
     mod parse_tests {
         use super::*;
@@ -542,6 +3068,231 @@ mod tests {
             assert!(Formula::parse("((x + 1)").is_err());
         }
 
+        #[test]
+        fn delimiter_fail_test() {
+            assert!(Formula::parse("(x]").is_err());
+            assert!(Formula::parse("\\left[ x \\right)").is_err());
+            assert!(Formula::parse("([x)]").is_err());
+        }
+
+        #[test]
+        fn delimiter_mul_test() {
+            let input = Formula::parse("\\left( x + y \\right) [z]").unwrap();
+            let expect = Mul(vec![Add(vec![ts("x"), ts("y")]), ts("z")]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn abs_test() {
+            let input = Formula::parse("2|x - 1|").unwrap();
+            let expect = Mul(vec![ts("2"), Abs(Box::new(Add(vec![ts("x"), neg(ts("1"))])))]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn norm_test() {
+            let input = Formula::parse("\\|v\\|").unwrap();
+            let expect = Norm(Box::new(ts("v")));
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn pow_test() {
+            let input = Formula::parse("2x^{n+1}").unwrap();
+            let expect = Mul(vec![ts("2"), pow(ts("x"), Add(vec![ts("n"), ts("1")]))]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn sum_test() {
+            let input = Formula::parse("\\sum_{k=1}^{n} 2 k").unwrap();
+            let expect = Sum(
+                Box::new(Bound {
+                    var: "k".to_string(),
+                    lower: Some(ts("1")),
+                    upper: Some(ts("n")),
+                    region: None,
+                }),
+                Box::new(Mul(vec![ts("2"), ts("k")])),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn sum_index_not_constant_test() {
+            let input = Formula::parse("\\sum_{i} i").unwrap();
+            let expect = Sum(
+                Box::new(Bound {
+                    var: "i".to_string(),
+                    lower: None,
+                    upper: None,
+                    region: None,
+                }),
+                Box::new(ts("i")),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn integral_test() {
+            let input = Formula::parse("\\int_0^1 x^2 \\, \\mathrm{d}x").unwrap();
+            let expect = Integral(
+                "\\int".to_string(),
+                Box::new(Bound {
+                    var: "x".to_string(),
+                    lower: Some(ts("0")),
+                    upper: Some(ts("1")),
+                    region: None,
+                }),
+                Box::new(pow(ts("x"), ts("2"))),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn double_integral_test() {
+            let input = Formula::parse("\\iint_D x y dx dy").unwrap();
+            let inner = Integral(
+                "\\iint".to_string(),
+                Box::new(Bound {
+                    var: "x".to_string(),
+                    lower: None,
+                    upper: None,
+                    region: None,
+                }),
+                Box::new(Mul(vec![ts("x"), ts("y")])),
+            );
+            let expect = Integral(
+                "\\iint".to_string(),
+                Box::new(Bound {
+                    var: "y".to_string(),
+                    lower: None,
+                    upper: None,
+                    region: Some(ts("D")),
+                }),
+                Box::new(inner),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn limit_test() {
+            let input = Formula::parse("\\lim_{x \\to 0^+} \\frac{1}{x}").unwrap();
+            let expect = Limit(
+                Box::new(Bound {
+                    var: "x".to_string(),
+                    lower: Some(ts("0")),
+                    upper: None,
+                    region: None,
+                }),
+                Side::Right,
+                Box::new(Mul(vec![ts("1"), recip(ts("x"))])),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn set_test() {
+            let input = Formula::parse("x \\in \\{1, 2\\} \\cup A \\setminus B \\cap C").unwrap();
+            let expect = In(
+                Box::new(ts("x")),
+                Box::new(Difference(
+                    Box::new(Union(vec![Set(vec![ts("1"), ts("2")]), ts("A")])),
+                    Box::new(Intersection(vec![ts("B"), ts("C")])),
+                )),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
+        #[test]
+        fn combinatorial_test() {
+            let binomial = Binomial(Box::new(ts("n")), Box::new(ts("k")));
+
+            let config = "combinatorial = C, P".parse::<Config>().unwrap();
+
+            for input in ["\\binom{n}{k}", "{n \\choose k}", "C^n_k", "C_k^n"] {
+                let input = Formula::parse_with_config(input, &config).unwrap();
+                assert!(Formula::eq_without_expand(&input, &binomial));
+            }
+
+            let input = Formula::parse_with_config("2 P(n, k)", &config).unwrap();
+            let expect = Mul(vec![ts("2"), Permutation(Box::new(ts("n")), Box::new(ts("k")))]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            // Symbols otherwise, e.g. an arbitrary constant `C_1^2`
+            assert!(!Formula::parse("C^n_k").unwrap().eq_without_expand(&binomial));
+            assert!(!Formula::parse("2 P(n, k)").unwrap().eq_without_expand(&expect));
+
+            let input = Formula::parse("(n + 1)! n!").unwrap();
+            let expect = Mul(vec![
+                Factorial(Box::new(Add(vec![ts("n"), ts("1")]))),
+                Factorial(Box::new(ts("n"))),
+            ]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            assert!(Formula::parse("!n").is_err());
+            assert!(Formula::parse("n!!").is_err());
+        }
+
+        #[test]
+        fn modular_test() {
+            let input = Formula::parse("2 \\lfloor x \\rfloor + \\left\\lceil y \\right\\rceil").unwrap();
+            let expect = Add(vec![
+                Mul(vec![ts("2"), Floor(Box::new(ts("x")))]),
+                Ceil(Box::new(ts("y"))),
+            ]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            let input = Formula::parse("2 a \\bmod n").unwrap();
+            let expect = Mod(Box::new(Mul(vec![ts("2"), ts("a")])), Box::new(ts("n")));
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            let expect = Congruence(Box::new(ts("a")), Box::new(ts("b")), Box::new(ts("n")));
+
+            for input in ["a \\equiv b \\pmod{n}", "a \\equiv b \\pmod n", "a \\equiv b \\mod{n}"] {
+                assert!(Formula::eq_without_expand(&Formula::parse(input).unwrap(), &expect));
+            }
+
+            assert!(Formula::parse("a \\equiv b \\equiv c \\pmod{n}").is_err());
+            assert!(Formula::parse("a \\bmod").is_err());
+        }
+
+        #[test]
+        fn conjugate_test() {
+            let expect = Mul(vec![ts("z"), Conj(Box::new(ts("z")))]);
+
+            for input in ["z \\overline{z}", "z \\bar z", "z \\bar{z}"] {
+                assert!(Formula::eq_without_expand(&Formula::parse(input).unwrap(), &expect));
+            }
+
+            let input = Formula::parse("\\overline{z + w}").unwrap();
+            let expect = Conj(Box::new(Add(vec![ts("z"), ts("w")])));
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            assert!(Formula::parse("\\bar").is_err());
+        }
+
+        #[test]
+        fn pairs_test() {
+            let interval = |lower_closed, lower, upper, upper_closed| {
+                Interval(lower_closed, Box::new(ts(lower)), Box::new(ts(upper)), upper_closed)
+            };
+
+            let input = Formula::parse("(1, 2)").unwrap();
+            let expect = Tuple(vec![ts("1"), ts("2")]);
+            assert!(Formula::eq_without_expand(&input, &expect));
+
+            let input = Formula::parse("x \\in (1, 2) \\cup [3, 4[").unwrap();
+            let expect = In(
+                Box::new(ts("x")),
+                Box::new(Union(vec![
+                    interval(false, "1", "2", false),
+                    interval(true, "3", "4", false),
+                ])),
+            );
+            assert!(Formula::eq_without_expand(&input, &expect));
+        }
+
         #[test]
         fn paren_add_test() {
             let input = Formula::parse("(x + y) + 1").unwrap();
@@ -675,6 +3426,18 @@ mod tests {
             assert!(Formula::eq_without_expand(&Formula::expand_paren(input), &expect));
         }
 
+        #[test]
+        // \sum_{i} \sum_{j} i j => \sum_{#1} \sum_{#0} #1 #0
+        fn canonicalize_bound_test() {
+            let l = Formula::parse("\\sum_{i} \\sum_{j} i j").unwrap();
+            let r = Formula::parse("\\sum_{j} \\sum_{i} j i").unwrap();
+            assert!(!Formula::eq_without_expand(&l, &r));
+            assert!(Formula::eq_without_expand(
+                &Formula::expand_paren(l),
+                &Formula::expand_paren(r)
+            ));
+        }
+
         #[test]
         // ((x y) z) 1 => x y z 1
         fn expand_recursive_mul_paren_test() {
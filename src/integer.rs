@@ -0,0 +1,581 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::debug;
+
+use crate::bigint::BigInt;
+use crate::formula::Formula;
+use crate::numeric::{Sampler, MIN_VALID_SAMPLES};
+use crate::operator::Relation;
+
+// Factorials and the `k` of binomials and permutations larger than this are not evaluated
+const MAX_ARGUMENT: u32 = 1000;
+
+// Exponents larger than this in absolute value are not evaluated
+pub(crate) const MAX_EXPONENT: u32 = 1000;
+
+// Variables are sampled at random integers from 0 up to this, or up to the period of the moduli if larger
+const INTEGER_SAMPLE_MAX: usize = 30;
+
+// Number of integer points tried for each comparison
+const INTEGER_SAMPLES: usize = 200;
+
+// Every combination of the integers from 0 up to this is tried first, if there are not too many
+const INTEGER_GRID_MAX: usize = 3;
+
+// Moduli whose least common multiple exceeds this are sampled at random residues
+const MAX_PERIOD: usize = 1000;
+
+// Points at most checked to compare congruences exactly at every residue
+const MAX_RESIDUES: usize = 1 << 20;
+
+// Exact rational value, e.g. `\frac{3!}{4}` => 6 / 4
+#[derive(Debug, Clone)]
+struct Ratio {
+    num: BigInt,
+    den: BigInt, // Always positive
+}
+
+impl Ratio {
+    fn integer(value: BigInt) -> Self {
+        return Self {
+            num: value,
+            den: BigInt::one(),
+        };
+    }
+
+    // `3.14` => 314 / 100
+    fn parse(s: &str) -> Option<Self> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let digits = format!("{}{}", int, frac);
+        let num = if digits.is_empty() { None } else { digits.parse().ok() }?;
+        return Some(Self {
+            num,
+            den: BigInt::from(10).pow(frac.len() as u32),
+        });
+    }
+
+    // 6 / 3 => 2, 6 / 4 => None
+    fn to_integer(&self) -> Option<BigInt> {
+        let (quotient, remainder) = self.num.div_rem(&self.den)?;
+        return if remainder.is_zero() { Some(quotient) } else { None };
+    }
+
+    // 7 / 2 => 3, -7 / 2 => -4
+    fn floor(&self) -> BigInt {
+        return self.num.div_floor(&self.den).unwrap_or_else(BigInt::zero);
+    }
+
+    // 7 / 2 => 4, -7 / 2 => -3
+    fn ceil(&self) -> BigInt {
+        return -(-self.num.clone()).div_floor(&self.den).unwrap_or_else(BigInt::zero);
+    }
+
+    // Halves round up, e.g. 5 / 2 => 3, -5 / 2 => -2
+    fn round(&self) -> BigInt {
+        let two = BigInt::from(2);
+        return (self.num.clone() * two.clone() + self.den.clone())
+            .div_floor(&(self.den.clone() * two))
+            .unwrap_or_else(BigInt::zero);
+    }
+
+    fn add(self, other: Self) -> Self {
+        return Self {
+            num: self.num * other.den.clone() + other.num * self.den.clone(),
+            den: self.den * other.den,
+        };
+    }
+
+    fn mul(self, other: Self) -> Self {
+        return Self {
+            num: self.num * other.num,
+            den: self.den * other.den,
+        };
+    }
+
+    fn recip(self) -> Option<Self> {
+        if self.num.is_zero() {
+            return None;
+        }
+
+        return Some(Self {
+            num: if self.num.is_negative() { -self.den } else { self.den },
+            den: self.num.abs(),
+        });
+    }
+
+    fn pow(self, exponent: &BigInt) -> Option<Self> {
+        let n = exponent.abs().to_u32().filter(|n| *n <= MAX_EXPONENT)?;
+        let power = Self {
+            num: self.num.pow(n),
+            den: self.den.pow(n),
+        };
+        return if exponent.is_negative() {
+            power.recip()
+        } else {
+            Some(power)
+        };
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        return (self.num.clone() * other.den.clone()).cmp(&(other.num.clone() * self.den.clone()));
+    }
+}
+
+// Value of a formula at an integer point: a number, or the truth of a relation or a congruence
+#[derive(Debug)]
+enum Value {
+    Number(Ratio),
+    Truth(bool),
+}
+
+impl Value {
+    fn same(&self, other: &Self) -> bool {
+        return match (self, other) {
+            (Self::Number(l), Self::Number(r)) => l.cmp(r) == Ordering::Equal,
+            (Self::Truth(l), Self::Truth(r)) => l == r,
+            _ => false,
+        };
+    }
+}
+
+// `n!` for 0 <= n <= MAX_ARGUMENT, e.g. 5 => 120
+pub fn factorial(n: &BigInt) -> Option<BigInt> {
+    let n = n.to_u32().filter(|n| *n <= MAX_ARGUMENT)?;
+    return Some(falling(&BigInt::from(n as i64), n));
+}
+
+// `\binom{n}{k}` by n (n - 1) ... (n - k + 1) / k!, so that it is 0 for k > n >= 0 or k < 0
+// e.g. (5, 2) => 10, (2, 5) => 0
+pub fn binomial(n: &BigInt, k: &BigInt) -> Option<BigInt> {
+    if k.is_negative() {
+        return Some(BigInt::zero());
+    }
+
+    let (quotient, _) = permutation(n, k)?.div_rem(&factorial(k)?)?;
+    return Some(quotient);
+}
+
+// `P(n, k)` = n (n - 1) ... (n - k + 1), e.g. (5, 2) => 20
+pub fn permutation(n: &BigInt, k: &BigInt) -> Option<BigInt> {
+    let k = k.to_u32().filter(|k| *k <= MAX_ARGUMENT)?;
+    return Some(falling(n, k));
+}
+
+// Product of the `k` integers from `n` downward
+fn falling(n: &BigInt, k: u32) -> BigInt {
+    return (0..k).fold(BigInt::one(), |acc, i| acc * (n.clone() - BigInt::from(i as i64)));
+}
+
+// An integral f64 in the exactly representable range, e.g. 5.0 => 5, 0.5 => None
+pub(crate) fn from_f64(value: f64) -> Option<BigInt> {
+    if value.fract() != 0.0 || value.abs() > 9007199254740992.0 {
+        return None;
+    }
+
+    return Some(BigInt::from(value as i64));
+}
+
+// Exact value of a constant formula if it is an integer, e.g. `2^{60} + 1` => 1152921504606846977
+pub(crate) fn exact_integer(formula: &Formula) -> Option<BigInt> {
+    return exact_value(formula, &BTreeMap::new())?.to_integer();
+}
+
+// Determinant of a matrix of rational constants by fraction-free elimination (Bareiss), or None for other entries
+// e.g. [[1, 2], [3, 4]] => `-2`, [[\frac{1}{2}, 0], [0, 1]] => `1 / 2`
+pub(crate) fn exact_determinant(rows: &[Vec<Formula>]) -> Option<Formula> {
+    let mut denominator = BigInt::one();
+    let mut matrix = Vec::new();
+
+    // Each row is scaled to integers by the product of its denominators
+    for row in rows {
+        let values = row
+            .iter()
+            .map(|f| exact_value(f, &BTreeMap::new()))
+            .collect::<Option<Vec<_>>>()?;
+        let scale = values.iter().fold(BigInt::one(), |acc, v| acc * v.den.clone());
+        let scaled = values
+            .into_iter()
+            .map(|v| Some(v.num * scale.div_rem(&v.den)?.0))
+            .collect::<Option<Vec<_>>>()?;
+        denominator = denominator * scale;
+        matrix.push(scaled);
+    }
+
+    let n = matrix.len();
+    let mut negative = false;
+    let mut previous = BigInt::one();
+
+    for k in 0..n.saturating_sub(1) {
+        if matrix[k][k].is_zero() {
+            match (k + 1..n).find(|i| !matrix[*i][k].is_zero()) {
+                Some(i) => {
+                    matrix.swap(k, i);
+                    negative = !negative;
+                }
+                None => return Some(Formula::TS("0".to_string())),
+            }
+        }
+
+        for i in k + 1..n {
+            for j in k + 1..n {
+                let product = matrix[i][j].clone() * matrix[k][k].clone() - matrix[i][k].clone() * matrix[k][j].clone();
+                matrix[i][j] = product.div_rem(&previous)?.0;
+            }
+        }
+
+        previous = matrix[k][k].clone();
+    }
+
+    let num = matrix.last()?.last()?.clone();
+    let ratio = Ratio {
+        num: if negative { -num } else { num },
+        den: denominator,
+    };
+    let abs = match ratio.to_integer() {
+        Some(n) => Formula::TS(n.abs().to_string()),
+        None => Formula::Mul(vec![
+            Formula::TS(ratio.num.abs().to_string()),
+            Formula::Recip(Box::new(Formula::TS(ratio.den.to_string()))),
+        ]),
+    };
+
+    return Some(if ratio.num.is_negative() {
+        Formula::Neg(Box::new(abs))
+    } else {
+        abs
+    });
+}
+
+// Whether the formula is compared on integers: combinatorics and congruences take integer variables,
+// and roundings of constants are evaluated exactly, e.g. `\binom{n}{2}`, `\lfloor \frac{7}{2} \rfloor`
+pub fn is_discrete(formula: &Formula) -> bool {
+    return takes_integers(formula) || (is_rounding(formula) && formula.free_vars().is_empty());
+}
+
+// e.g. `n!`, `\binom{n}{k}`, `a \equiv b \pmod{n}`
+fn takes_integers(formula: &Formula) -> bool {
+    return matches!(
+        formula,
+        Formula::Factorial(_) | Formula::Binomial(_, _) | Formula::Permutation(_, _) | Formula::Congruence(_, _, _)
+    ) || formula.children().into_iter().any(takes_integers);
+}
+
+// Whether the formula rounds or takes remainders, which are also defined on real numbers, e.g. `\lfloor x \rfloor`, `x \bmod 1`
+pub fn is_rounding(formula: &Formula) -> bool {
+    return matches!(
+        formula,
+        Formula::Floor(_) | Formula::Ceil(_) | Formula::Round(_) | Formula::Mod(_, _)
+    ) || formula.children().into_iter().any(is_rounding);
+}
+
+// Exact value with `vars` bound to integers, or None if it is not rational or too large
+// e.g. `\frac{n!}{2}` with n = 4 => 12
+fn exact_value(formula: &Formula, vars: &BTreeMap<String, BigInt>) -> Option<Ratio> {
+    let integer = |f: &Formula| exact_value(f, vars)?.to_integer();
+
+    return match formula {
+        Formula::TS(s) if Formula::is_number(s) => Ratio::parse(s),
+        Formula::TS(s) => vars.get(s).cloned().map(Ratio::integer),
+        Formula::Neg(f) => exact_value(f, vars).map(|v| Ratio::integer(BigInt::from(-1)).mul(v)),
+        Formula::Recip(f) => exact_value(f, vars)?.recip(),
+        Formula::Abs(f) => exact_value(f, vars).map(|v| Ratio {
+            num: v.num.abs(),
+            den: v.den,
+        }),
+        Formula::Add(terms) => terms.iter().try_fold(Ratio::integer(BigInt::zero()), |acc, f| {
+            Some(acc.add(exact_value(f, vars)?))
+        }),
+        Formula::Mul(factors) => factors.iter().try_fold(Ratio::integer(BigInt::one()), |acc, f| {
+            Some(acc.mul(exact_value(f, vars)?))
+        }),
+        Formula::Pow(base, exponent) => exact_value(base, vars)?.pow(&integer(exponent)?),
+        Formula::Factorial(n) => factorial(&integer(n)?).map(Ratio::integer),
+        Formula::Binomial(n, k) => binomial(&integer(n)?, &integer(k)?).map(Ratio::integer),
+        Formula::Permutation(n, k) => permutation(&integer(n)?, &integer(k)?).map(Ratio::integer),
+        Formula::Floor(f) => Some(Ratio::integer(exact_value(f, vars)?.floor())),
+        Formula::Ceil(f) => Some(Ratio::integer(exact_value(f, vars)?.ceil())),
+        Formula::Round(f) => Some(Ratio::integer(exact_value(f, vars)?.round())),
+        Formula::Mod(a, n) => integer(a)?.modulo(&integer(n)?).map(Ratio::integer),
+        _ => None,
+    };
+}
+
+// A relation or a congruence is true or false, anything else is a number
+// e.g. `a \equiv b \pmod{n}` holds when n divides a - b
+fn value(formula: &Formula, vars: &BTreeMap<String, BigInt>) -> Option<Value> {
+    let integer = |f: &Formula| exact_value(f, vars)?.to_integer();
+
+    return match formula {
+        Formula::Congruence(l, r, modulus) => {
+            let remainder = (integer(l)? - integer(r)?).modulo(&integer(modulus)?)?;
+            Some(Value::Truth(remainder.is_zero()))
+        }
+        Formula::Relation(relation, l, r) => {
+            let ordering = exact_value(l, vars)?.cmp(&exact_value(r, vars)?);
+            Some(Value::Truth(match relation {
+                Relation::Eq => ordering == Ordering::Equal,
+                Relation::Ne => ordering != Ordering::Equal,
+                Relation::Lt => ordering == Ordering::Less,
+                Relation::Le => ordering != Ordering::Greater,
+                Relation::Gt => ordering == Ordering::Greater,
+                Relation::Ge => ordering != Ordering::Less,
+            }))
+        }
+        _ => exact_value(formula, vars).map(Value::Number),
+    };
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    return a;
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    return (a / gcd(a, b)).saturating_mul(b);
+}
+
+// A positive constant modulus, e.g. `12` => 12, `n` => None
+fn constant_modulus(formula: &Formula) -> Option<usize> {
+    let n = exact_value(formula, &BTreeMap::new())?.to_integer()?.abs().to_u32()?;
+    return Some(n as usize).filter(|n| *n > 0);
+}
+
+// Least common multiple of the constant moduli, so that sampling up to it covers every residue
+// e.g. `x \equiv 1 \pmod{4}` and `x \bmod 6 = 1` => 12
+fn period(formulas: &[&Formula]) -> usize {
+    return formulas.iter().fold(1, |acc, formula| {
+        let n = match formula {
+            Formula::Mod(_, n) | Formula::Congruence(_, _, n) => constant_modulus(n).unwrap_or(1),
+            _ => 1,
+        };
+        let acc = lcm(acc, n).min(MAX_PERIOD);
+        return lcm(acc, period(&formula.children())).min(MAX_PERIOD);
+    });
+}
+
+// Period in every variable of a polynomial with integer coefficients and remainders by constants, which is
+// the least common multiple of the moduli, or None for other formulas, e.g. `x^2 \bmod 4 + 1` => 4
+fn polynomial_period(formula: &Formula) -> Option<usize> {
+    let own = match formula {
+        Formula::TS(s) if Formula::is_number(s) => {
+            exact_value(formula, &BTreeMap::new())?.to_integer()?;
+            1
+        }
+        Formula::TS(_) | Formula::Neg(_) | Formula::Add(_) | Formula::Mul(_) => 1,
+        Formula::Pow(_, exponent) if !exact_value(exponent, &BTreeMap::new())?.to_integer()?.is_negative() => 1,
+        Formula::Mod(_, n) | Formula::Congruence(_, _, n) => constant_modulus(n)?,
+        _ => return None,
+    };
+
+    return formula
+        .children()
+        .into_iter()
+        .try_fold(own, |acc, f| Some(lcm(acc, polynomial_period(f)?)));
+}
+
+// Values of `vars` for the `index`-th point of the grid of the integers below `radix`
+fn grid_point(vars: &[String], index: usize, radix: usize) -> BTreeMap<String, BigInt> {
+    return vars
+        .iter()
+        .enumerate()
+        .map(|(i, var)| {
+            let digit = index / radix.pow(i as u32) % radix;
+            return (var.clone(), BigInt::from(digit as i64));
+        })
+        .collect();
+}
+
+// Whether two polynomial congruences with constant moduli hold at the same integer points, or None if they are not such
+// Both repeat with the least common multiple of the moduli in every variable, so every residue is checked,
+// e.g. `2x \equiv 4 \pmod{6}` and `x \equiv 2 \pmod{3}` hold at x = 2, 5 mod 6 alike
+fn congruent(lhs: &Formula, rhs: &Formula, vars: &[String]) -> Option<bool> {
+    let period = lcm(polynomial_period(lhs)?, polynomial_period(rhs)?);
+    let points = (0..vars.len())
+        .try_fold(1usize, |acc, _| acc.checked_mul(period))
+        .filter(|points| *points <= MAX_RESIDUES)?;
+
+    for index in 0..points {
+        let values = grid_point(vars, index, period);
+
+        if !value(lhs, &values)?.same(&value(rhs, &values)?) {
+            debug!("Integer: congruences differ at {:?}", values);
+            return Some(false);
+        }
+    }
+
+    return Some(true);
+}
+
+// Whether `lhs` and `rhs` agree exactly at non-negative integer points where both are defined,
+// or None if they are defined at too few points, e.g. `\binom{n}{k}` and `\frac{n!}{k! (n - k)!}`
+// Congruences agree when they hold at the same points, e.g. `x \equiv 3 \pmod{5}` and `x \equiv -2 \pmod{5}`
+pub fn equivalent(lhs: &Formula, rhs: &Formula) -> Option<bool> {
+    let vars = lhs
+        .free_vars()
+        .into_iter()
+        .chain(rhs.free_vars())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    // Congruences of polynomials by constant moduli are compared exactly, others are sampled
+    if let (Formula::Congruence(_, _, _), Formula::Congruence(_, _, _)) = (lhs, rhs) {
+        if let Some(result) = congruent(lhs, rhs, &vars) {
+            return Some(result);
+        }
+    }
+
+    let max = INTEGER_SAMPLE_MAX.max(period(&[lhs, rhs]));
+    let radix = INTEGER_GRID_MAX + 1;
+    let grid = (0..vars.len())
+        .try_fold(1usize, |acc, _| acc.checked_mul(radix))
+        .filter(|grid| *grid <= INTEGER_SAMPLES / 2)
+        .unwrap_or(0);
+
+    // A constant formula has a single value
+    let samples = if vars.is_empty() { 1 } else { INTEGER_SAMPLES };
+    let mut sampler = Sampler::new();
+    let mut valid = 0;
+
+    for index in 0..samples {
+        // The grid first, then independent random values
+        let values = if index < grid {
+            grid_point(&vars, index, radix)
+        } else {
+            vars.iter()
+                .map(|var| (var.clone(), BigInt::from(sampler.next_integer(max) as i64)))
+                .collect()
+        };
+
+        if let (Some(l), Some(r)) = (value(lhs, &values), value(rhs, &values)) {
+            if !l.same(&r) {
+                debug!("Integer: counterexample => {:?}", values);
+                return Some(false);
+            }
+
+            valid += 1;
+        }
+    }
+
+    debug!("Integer: {} of {} samples agree", valid, samples);
+    return if valid >= MIN_VALID_SAMPLES.min(samples) {
+        Some(true)
+    } else {
+        None
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bigint::BigInt;
+    use crate::config::Config;
+    use crate::formula::Formula;
+    use crate::integer::*;
+
+    fn big(n: i64) -> BigInt {
+        return BigInt::from(n);
+    }
+
+    fn same(lhs: &str, rhs: &str) -> Option<bool> {
+        let config = "combinatorial = C, P".parse::<Config>().unwrap();
+        let parse = |s: &str| Formula::parse_with_config(s, &config).unwrap();
+        return equivalent(&parse(lhs), &parse(rhs));
+    }
+
+    #[test]
+    fn combinatorial_test() {
+        assert_eq!(factorial(&big(5)), Some(big(120)));
+        assert_eq!(factorial(&big(0)), Some(big(1)));
+        assert_eq!(factorial(&big(-1)), None);
+        assert_eq!(
+            factorial(&big(30)).unwrap().to_string(),
+            "265252859812191058636308480000000"
+        );
+        assert_eq!(binomial(&big(5), &big(2)), Some(big(10)));
+        assert_eq!(binomial(&big(2), &big(5)), Some(big(0)));
+        assert_eq!(binomial(&big(-2), &big(2)), Some(big(3)));
+        assert_eq!(permutation(&big(5), &big(2)), Some(big(20)));
+    }
+
+    #[test]
+    fn exact_determinant_test() {
+        let matrix = |rows: &[&[&str]]| {
+            rows.iter()
+                .map(|row| row.iter().map(|s| Formula::parse(s).unwrap()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        let determinant = |rows: &[&[&str]]| exact_determinant(&matrix(rows)).map(|f| format!("{:?}", f));
+
+        assert_eq!(
+            determinant(&[&["1", "2"], &["3", "4"]]),
+            Some("Neg(TS(\"2\"))".to_string())
+        );
+        assert_eq!(
+            determinant(&[&["0", "1"], &["1", "0"]]),
+            Some("Neg(TS(\"1\"))".to_string())
+        );
+        assert_eq!(determinant(&[&["1", "2"], &["2", "4"]]), Some("TS(\"0\")".to_string()));
+        assert_eq!(
+            determinant(&[&["\\frac{1}{2}", "0"], &["0", "3"]]),
+            Some("Mul([TS(\"3\"), Recip(TS(\"2\"))])".to_string())
+        );
+        assert_eq!(determinant(&[&["x", "0"], &["0", "1"]]), None);
+    }
+
+    #[test]
+    fn equivalent_test() {
+        assert_eq!(same("\\binom{n}{k}", "\\frac{n!}{k! (n - k)!}"), Some(true));
+        assert_eq!(same("P(n, k)", "\\frac{n!}{(n - k)!}"), Some(true));
+        assert_eq!(same("(n + 1)!", "(n + 1) n!"), Some(true));
+        assert_eq!(same("\\binom{n}{k}", "\\binom{n}{n - k}"), Some(true));
+        assert_eq!(same("\\binom{n}{k}", "\\frac{n!}{k!}"), Some(false));
+        assert_eq!(same("25!", "15511210043330985984000000"), Some(true));
+        assert_eq!(same("25!", "15511210043330985984000001"), Some(false));
+        assert_eq!(same("x!", "\\pi"), None);
+
+        // Defined at too few points, or differing only at larger values
+        assert_eq!(same("(n - 40)!", "1"), None);
+        assert_eq!(same("(n - 7)!", "1"), Some(false));
+        assert_eq!(same("n!", "n! + \\binom{n}{9}"), Some(false));
+        assert_eq!(same("a! + b + c + d", "a! + b + c + d + \\binom{d}{2}"), Some(false));
+    }
+
+    #[test]
+    fn modular_test() {
+        assert_eq!(
+            same("\\lfloor \\frac{n}{2} \\rfloor", "\\lceil \\frac{n - 1}{2} \\rceil"),
+            Some(true)
+        );
+        assert_eq!(
+            same("\\lfloor \\frac{n}{2} \\rfloor", "\\lceil \\frac{n}{2} \\rceil"),
+            Some(false)
+        );
+        assert_eq!(
+            same("(a + b) \\bmod 3", "(a \\bmod 3 + b \\bmod 3) \\bmod 3"),
+            Some(true)
+        );
+        assert_eq!(same("x \\equiv 3 \\pmod{5}", "x \\equiv -2 \\pmod 5"), Some(true));
+        assert_eq!(same("x \\equiv 3 \\pmod{5}", "x \\bmod 5 = 3"), Some(true));
+        assert_eq!(same("x \\equiv 3 \\pmod{12}", "x \\equiv 3 \\pmod{24}"), Some(false));
+        assert_eq!(same("x \\equiv 3 \\pmod{5}", "x = 3"), Some(false));
+        assert_eq!(
+            same("x \\equiv 1 \\pmod{1000}", "x \\equiv 1 \\pmod{2000}"),
+            Some(false)
+        );
+        assert_eq!(
+            same("x^2 \\equiv 1 \\pmod{8}", "x \\bmod 2 \\equiv 1 \\pmod{2}"),
+            Some(true)
+        );
+        assert_eq!(same("x \\equiv 1 \\pmod{n}", "x \\equiv n + 1 \\pmod{n}"), Some(true));
+        assert_eq!(same("x \\equiv 1 \\pmod{n}", "x \\equiv 2 \\pmod{n}"), Some(false));
+        assert_eq!(
+            same(
+                "\\lfloor \\frac{x}{2} \\rfloor \\equiv 0 \\pmod{2}",
+                "x \\equiv 0 \\pmod{2}"
+            ),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use log::debug;
+
+use crate::config::{Config, Mode, Pairs};
+use crate::formula::Formula;
+use crate::numeric;
+use crate::operator::Relation;
+
+// Connected part of a subset of the real line, e.g. `[0, 1)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    lower: f64,
+    lower_closed: bool,
+    upper: f64,
+    upper_closed: bool,
+}
+
+impl Span {
+    fn new(lower: f64, lower_closed: bool, upper: f64, upper_closed: bool) -> Self {
+        // Infinite ends are never included, e.g. `[0, \infty]` == `[0, \infty)`
+        return Self {
+            lower,
+            lower_closed: lower_closed && lower.is_finite(),
+            upper,
+            upper_closed: upper_closed && upper.is_finite(),
+        };
+    }
+
+    fn point(value: f64) -> Self {
+        return Self::new(value, true, value, true);
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.lower > self.upper || (self.lower == self.upper && !(self.lower_closed && self.upper_closed));
+    }
+}
+
+// Disjoint spans in increasing order, e.g. `x \ne 0` => [(-\infty, 0), (0, \infty)]
+type Spans = Vec<Span>;
+
+// The subset of the real line and the variable constrained to it, if any
+// e.g. `x > 2` => (Some(x), [(2, \infty)]), `[0, 1] \cup \{2\}` => (None, [[0, 1], [2, 2]])
+fn real_subset(formula: &Formula, config: &Config) -> Option<(Option<String>, Spans)> {
+    let all = |formulas: &[Formula], combine: fn(Spans, Spans) -> Spans| {
+        let mut subsets = formulas.iter().map(|f| real_subset(f, config));
+        let first = subsets.next()??;
+
+        return subsets.try_fold(first, |(var, spans), subset| {
+            let (other_var, other_spans) = subset?;
+            return Some((same_var(var, other_var)?, combine(spans, other_spans)));
+        });
+    };
+
+    return match formula {
+        Formula::TS(s) if s == "\\mathbb{R}" => {
+            Some((None, vec![Span::new(f64::NEG_INFINITY, false, f64::INFINITY, false)]))
+        }
+        Formula::Interval(lower_closed, lower, upper, upper_closed) => {
+            let (lower, upper) = (value(lower, config)?, value(upper, config)?);
+
+            // Reversed ends are likely a mistake rather than the empty set, e.g. `[3, 1]` != `\emptyset`
+            if lower > upper {
+                return None;
+            }
+
+            Some((None, vec![Span::new(lower, *lower_closed, upper, *upper_closed)]))
+        }
+        Formula::Set(elements) => Some((
+            None,
+            normalize(
+                elements
+                    .iter()
+                    .map(|f| value(f, config).filter(|v| v.is_finite()).map(Span::point))
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+        )),
+        Formula::Union(sets) | Formula::Or(sets) => all(sets, union),
+        Formula::Intersection(sets) | Formula::And(sets) => all(sets, intersection),
+        Formula::Difference(l, r) => all(&[(**l).clone(), Formula::Not(r.clone())], intersection),
+        Formula::Not(formula) => real_subset(formula, config).map(|(var, spans)| (var, complement(&spans))),
+        Formula::In(element, set) => {
+            let (var, spans) = real_subset(set, config)?;
+            Some((same_var(var, Some(variable(element)?))?, spans))
+        }
+        Formula::Relation(relation, l, r) => match (variable(l), variable(r)) {
+            (Some(var), None) => Some((Some(var), relation_spans(*relation, value(r, config)?))),
+            (None, Some(var)) => Some((Some(var), relation_spans(relation.flipped(), value(l, config)?))),
+            _ => None,
+        },
+        _ => None,
+    };
+}
+
+// Whether `lhs` and `rhs` are the same subset of the real line for the same variable, or None if either is not one
+// e.g. `x > 2`, `(2, \infty)`, `]2, +\infty[` and `x \in (2, \infty)` are the same
+pub fn same_subset(lhs: &Formula, rhs: &Formula, config: &Config) -> Option<bool> {
+    // A pair compared with something else than a tuple is an interval, e.g. `(0, 1)` with `0 < x < 1`
+    let as_interval = |f: &Formula, other: &Formula| {
+        if config.pairs == Pairs::Auto && !matches!(other, Formula::Tuple(_)) {
+            return f.clone().into_interval();
+        }
+
+        return f.clone();
+    };
+
+    let (l_var, l_spans) = real_subset(&as_interval(lhs, rhs), config)?;
+    let (r_var, r_spans) = real_subset(&as_interval(rhs, lhs), config)?;
+    debug!("Interval: {:?} {:?} and {:?} {:?}", l_var, l_spans, r_var, r_spans);
+
+    // Ends are folded to the same digits as structural comparison, so only numeric mode allows for a tolerance
+    // e.g. `x = 0.3333333333` != `x = \frac{1}{3}` unless numeric
+    let is_same_end = |l: f64, r: f64| {
+        l == r || (config.mode == Mode::Numeric && l.is_finite() && r.is_finite() && numeric::is_close(l, r))
+    };
+    let is_same_span = |l: &Span, r: &Span| {
+        is_same_end(l.lower, r.lower)
+            && is_same_end(l.upper, r.upper)
+            && l.lower_closed == r.lower_closed
+            && l.upper_closed == r.upper_closed
+    };
+
+    return Some(
+        same_var(l_var, r_var).is_some()
+            && l_spans.len() == r_spans.len()
+            && l_spans.iter().zip(&r_spans).all(|(l, r)| is_same_span(l, r)),
+    );
+}
+
+// A variable like `x`, but not a number or `\infty`
+pub(crate) fn variable(formula: &Formula) -> Option<String> {
+    return match formula {
+        Formula::TS(s) if !Formula::is_number(s) && s != "\\infty" => Some(s.clone()),
+        _ => None,
+    };
+}
+
+// `x` and `x` => x, `x` and none => x, `x` and `y` => None
+fn same_var(l: Option<String>, r: Option<String>) -> Option<Option<String>> {
+    return match (l, r) {
+        (Some(l), Some(r)) if l != r => None,
+        (l, r) => Some(l.or(r)),
+    };
+}
+
+// `\infty` => inf, `-\infty` => -inf, `\frac{1}{2}` => 0.5
+fn value(formula: &Formula, config: &Config) -> Option<f64> {
+    return match formula {
+        Formula::TS(s) if s == "\\infty" => Some(f64::INFINITY),
+        Formula::Neg(formula) => value(formula, config).map(|v| -v),
+        Formula::Add(terms) if terms.len() == 1 => value(&terms[0], config),
+        _ => formula.eval(config, &BTreeMap::new()),
+    };
+}
+
+// `< c` => [(-\infty, c)], `\ne c` => [(-\infty, c), (c, \infty)]
+fn relation_spans(relation: Relation, c: f64) -> Spans {
+    let (inf, neg_inf) = (f64::INFINITY, f64::NEG_INFINITY);
+
+    return match relation {
+        Relation::Eq => vec![Span::point(c)],
+        Relation::Ne => complement(&[Span::point(c)]),
+        Relation::Lt => vec![Span::new(neg_inf, false, c, false)],
+        Relation::Le => vec![Span::new(neg_inf, false, c, true)],
+        Relation::Gt => vec![Span::new(c, false, inf, false)],
+        Relation::Ge => vec![Span::new(c, true, inf, false)],
+    };
+}
+
+// Sort and merge overlapping or touching spans, e.g. [[1, 2], (0, 1]] => [(0, 2]]
+fn normalize(spans: Spans) -> Spans {
+    let mut sorted = spans.into_iter().filter(|span| !span.is_empty()).collect::<Vec<_>>();
+    sorted.sort_by(|l, r| {
+        l.lower
+            .partial_cmp(&r.lower)
+            .unwrap_or(Ordering::Equal)
+            .then(r.lower_closed.cmp(&l.lower_closed))
+    });
+
+    let mut merged: Spans = Vec::new();
+
+    for span in sorted {
+        match merged.last_mut() {
+            Some(last)
+                if span.lower < last.upper
+                    || (span.lower == last.upper && (span.lower_closed || last.upper_closed)) =>
+            {
+                if span.upper > last.upper || (span.upper == last.upper && span.upper_closed) {
+                    last.upper = span.upper;
+                    last.upper_closed = span.upper_closed;
+                }
+            }
+            _ => merged.push(span),
+        }
+    }
+
+    return merged;
+}
+
+fn union(l: Spans, r: Spans) -> Spans {
+    return normalize([l, r].concat());
+}
+
+// The gaps between normalized spans, e.g. [[0, 1]] => [(-\infty, 0), (1, \infty)]
+fn complement(spans: &[Span]) -> Spans {
+    let mut gaps = Vec::new();
+    let (mut lower, mut lower_closed) = (f64::NEG_INFINITY, false);
+
+    for span in spans {
+        gaps.push(Span::new(lower, lower_closed, span.lower, !span.lower_closed));
+        lower = span.upper;
+        lower_closed = !span.upper_closed;
+    }
+
+    gaps.push(Span::new(lower, lower_closed, f64::INFINITY, false));
+    return normalize(gaps);
+}
+
+fn intersection(l: Spans, r: Spans) -> Spans {
+    return complement(&union(complement(&l), complement(&r)));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::formula::Formula;
+    use crate::interval::*;
+
+    fn subset(s: &str) -> Option<(Option<String>, Spans)> {
+        return real_subset(&Formula::parse(s).unwrap(), &Config::default());
+    }
+
+    #[test]
+    fn real_subset_test() {
+        let inf = f64::INFINITY;
+        assert_eq!(
+            subset("x > 2"),
+            Some((Some("x".to_string()), vec![Span::new(2.0, false, inf, false)]))
+        );
+        assert_eq!(
+            subset("[0, 1) \\cup [1, 2]"),
+            Some((None, vec![Span::new(0.0, true, 2.0, true)]))
+        );
+        assert_eq!(
+            subset("0 < x \\le 1"),
+            Some((Some("x".to_string()), vec![Span::new(0.0, false, 1.0, true)]))
+        );
+        assert_eq!(
+            subset("x \\ne 0"),
+            Some((
+                Some("x".to_string()),
+                vec![Span::new(-inf, false, 0.0, false), Span::new(0.0, false, inf, false)]
+            ))
+        );
+        assert_eq!(subset("[0, 1] \\setminus \\{1\\}"), subset("[0, 1)"));
+        assert_eq!(subset("x < y"), None);
+        assert_eq!(subset("x > 0 \\land y > 0"), None);
+        assert_eq!(subset("[3, 1]"), None);
+    }
+}
@@ -1,28 +1,92 @@
+pub mod answers;
+pub mod bigint;
+pub mod complex;
+pub mod config;
+pub mod delimiter;
+pub mod diff;
 pub mod errors;
+pub mod eval;
 pub mod formula;
+pub mod integer;
+pub mod interval;
 pub mod logger;
+pub mod logic;
+pub mod macros;
+pub mod numeric;
 pub mod operator;
+pub mod preprocess;
+pub mod symbol;
 
 use log::debug;
 
+use config::{Config, Mode};
 use errors::ParseFormulaError;
 use formula::Formula;
 
 pub fn exec(lhs: &str, rhs: &str) -> Result<bool, ParseFormulaError> {
-    let lhs_formula = Formula::parse(lhs)?;
-    let rhs_formula = Formula::parse(rhs)?;
+    return exec_with_config(lhs, rhs, &Config::default());
+}
+
+pub fn exec_with_config(lhs: &str, rhs: &str, config: &Config) -> Result<bool, ParseFormulaError> {
+    let lhs_formula = Formula::parse_with_config(lhs, config)?.complex_normal_form(config);
+    let rhs_formula = Formula::parse_with_config(rhs, config)?.complex_normal_form(config);
     debug!(
         "Parse results => {{ lhs => {:?}, rhs => {:?} }}",
         lhs_formula, rhs_formula
     );
 
-    let result = lhs_formula == rhs_formula;
+    let lhs_folded = lhs_formula.clone().fold_constants(config);
+    let rhs_folded = rhs_formula.clone().fold_constants(config);
+
+    // Answer lists are compared as multisets of answers in any mode
+    if let Some(diff) = answers::compare(&lhs_folded, &rhs_folded) {
+        return Ok(diff.is_empty());
+    }
+
+    // Inequalities and intervals are compared as subsets of the real line in any mode
+    if let Some(result) = interval::same_subset(&lhs_folded, &rhs_folded, config) {
+        return Ok(result);
+    }
+
+    // Propositions are compared by their truth values in any mode
+    if logic::is_propositional(&lhs_folded) || logic::is_propositional(&rhs_folded) {
+        return Ok(logic::counterexample(&lhs_folded, &rhs_folded).is_none());
+    }
+
+    // Combinatorics and congruences are compared exactly at integer points in any mode, before folding rounds them
+    if integer::is_discrete(&lhs_formula) || integer::is_discrete(&rhs_formula) {
+        if let Some(result) = integer::equivalent(&lhs_formula, &rhs_formula) {
+            return Ok(result);
+        }
+    }
+
+    // Complex-valued formulas are compared at complex sample points in any mode, before folding rounds them
+    if complex::is_complex(&lhs_formula, config) || complex::is_complex(&rhs_formula, config) {
+        if let Some(result) = complex::equivalent(&lhs_formula, &rhs_formula, config) {
+            return Ok(result);
+        }
+    }
+
+    let result = match &config.mode {
+        // Roundings of real variables are compared at sample points, e.g. `\lfloor x \rfloor + 1` == `\lfloor x + 1 \rfloor`
+        Mode::Structural if integer::is_rounding(&lhs_folded) || integer::is_rounding(&rhs_folded) => {
+            numeric::equivalent(&lhs_folded, &rhs_folded, config)
+        }
+        Mode::Structural => lhs_folded == rhs_folded,
+        Mode::Numeric => numeric::equivalent(&lhs_folded, &rhs_folded, config),
+        Mode::UpToConstant(var) => numeric::equivalent_up_to_constant(&lhs_folded, &rhs_folded, var, config),
+    };
     return Ok(result);
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::exec;
+    use crate::config::Config;
+    use crate::formula::Formula;
+    use crate::macros::Macro;
+    use crate::numeric;
+    use crate::symbol::Constant;
+    use crate::{exec, exec_with_config};
 
     #[test]
     fn true_test() {
@@ -237,4 +301,957 @@ mod tests {
         assert!(exec("1 / x", " 1  /  x ").unwrap());
         assert!(exec("1 / x", "1/x").unwrap());
     }
+
+    #[test]
+    fn symbol_macro_test() {
+        assert!(exec("\\alpha", "\\alpha").unwrap());
+        assert!(exec("2 \\theta", "\\theta * 2").unwrap());
+        assert!(exec("\\alpha\\beta", "\\beta \\alpha").unwrap());
+        assert!(!exec("\\alpha", "a l p h a").unwrap());
+        assert!(exec("x", "\\frac").is_err());
+    }
+
+    #[test]
+    fn number_test() {
+        assert!(exec("12 x", "x * 12").unwrap());
+        assert!(!exec("12", "21").unwrap());
+        assert!(exec("2 * 3", "6").unwrap());
+        assert!(exec("x + 1 + 2", "3 + x").unwrap());
+        assert!(exec("2^{60}", "1152921504606846976").unwrap());
+        assert!(!exec("1000000000000 + 1", "1000000000000").unwrap());
+        assert!(!exec("2^{60}", "2^{60} + 1").unwrap());
+    }
+
+    #[test]
+    fn constant_test() {
+        assert!(exec("2 \\pi", "\\pi * 2").unwrap());
+        assert!(exec("\\pi + \\pi", "2 \\pi").unwrap());
+        assert!(exec("e x", "x e").unwrap());
+        assert!(!exec("\\pi", "3.14").unwrap());
+        assert!(exec("4 \\arctan 1", "\\pi").unwrap());
+    }
+
+    #[test]
+    fn constant_config_test() {
+        let mut config = Config::default();
+        config.constants.insert("\\pi".to_string(), Constant::Real(3.0));
+        assert!(exec_with_config("\\pi * 2", "6", &config).unwrap());
+
+        config.constants.remove("e");
+        assert!(exec_with_config("e + 1", "1 + e", &config).unwrap());
+        assert!(!exec_with_config("e + 1", "3.718281828459045", &config).unwrap());
+    }
+
+    #[test]
+    fn identifier_test() {
+        let mut config = Config::default();
+        config.add_identifier("KE");
+        config.add_identifier("dt");
+        assert!(exec_with_config("KE", "KE", &config).unwrap());
+        assert!(exec_with_config("2 KE", "KE * 2", &config).unwrap());
+        assert!(exec_with_config("v dt", "dt * v", &config).unwrap());
+        assert!(!exec_with_config("KE", "E K", &config).unwrap());
+        assert!(exec("KE", "E K").unwrap());
+    }
+
+    #[test]
+    fn identifier_longest_match_test() {
+        let config = "identifiers = R, Re".parse::<Config>().unwrap();
+        assert!(exec_with_config("Re x", "x * Re", &config).unwrap());
+        assert!(!exec_with_config("Re", "R * e", &config).unwrap());
+    }
+
+    #[test]
+    fn delimiter_test() {
+        assert!(exec("x + 1", "\\left( x + 1 \\right)").unwrap());
+        assert!(exec("2 (x + 1)", "2 [x + 1]").unwrap());
+        assert!(exec("2 (x + 1)", "2 \\{x + 1\\}").unwrap());
+        assert!(exec("2 (x + 1)", "2 \\bigl( x + 1 \\bigr)").unwrap());
+        assert!(exec("2 (x + 1)", "2 \\Big[ x + 1 \\Big]").unwrap());
+        assert!(exec("(x + 1) y", "\\left[ (x + 1) y \\right]").unwrap());
+        assert!(exec("x + 1", "{x + 1}").unwrap());
+    }
+
+    #[test]
+    fn delimiter_fail_test() {
+        assert!(exec("x", "(x]").is_err());
+        assert!(exec("x", "[x)").is_err());
+        assert!(exec("x", "\\left( x \\right]").is_err());
+        assert!(exec("x", "\\{x)").is_err());
+        assert!(exec("x", "2 [x + 1").is_err());
+        assert!(exec("x", "2 x + 1]").is_err());
+    }
+
+    #[test]
+    fn mul_operator_test() {
+        assert!(exec("2 \\cdot x", "2x").unwrap());
+        assert!(exec("2 \\times x", "2 * x").unwrap());
+        assert!(exec("2 \\ast x", "x \\cdot 2").unwrap());
+        assert!(exec("2 × x", "2x").unwrap());
+        assert!(exec("2 · x", "2x").unwrap());
+        assert!(exec("(x + 1)\\cdot(y + 1)", "(x + 1)(y + 1)").unwrap());
+        assert!(!exec("2 \\cdot x", "2 + x").unwrap());
+    }
+
+    #[test]
+    fn div_operator_test() {
+        assert!(exec("x \\div 2", "x / 2").unwrap());
+        assert!(exec("x ÷ y", "x / y").unwrap());
+        assert!(exec("1 \\div x \\div y", "1 / y / x").unwrap());
+        assert!(!exec("x \\div 2", "x \\cdot 2").unwrap());
+    }
+
+    #[test]
+    fn frac_test() {
+        assert!(exec("\\frac{x}{2}", "x / 2").unwrap());
+        assert!(exec("\\dfrac{x + 1}{y}", "(x + 1) / y").unwrap());
+        assert!(exec("\\frac12 x", "\\tfrac{1}{2} x").unwrap());
+        assert!(exec("\\frac{1}{2}", "0.5").unwrap());
+        assert!(!exec("\\frac{x}{2}", "\\frac{2}{x}").unwrap());
+        assert!(exec("x", "\\frac{x}").is_err());
+    }
+
+    #[test]
+    fn function_test() {
+        assert!(exec("\\sin x", "\\sin(x)").unwrap());
+        assert!(exec("2 \\sin x \\cos x", "\\cos(x) \\sin(x) \\cdot 2").unwrap());
+        assert!(exec("\\sin^2 x", "(\\sin x)^2").unwrap());
+        assert!(exec("\\sin(x)^2", "\\sin^2 x").unwrap());
+        assert!(exec("\\sin 2x", "\\sin(2x)").unwrap());
+        assert!(exec("\\ln(x + 1) y", "y \\ln(1 + x)").unwrap());
+        assert!(exec("\\log_2 x", "\\frac{\\log x}{\\log 2}").unwrap());
+        assert!(exec("\\sin 0 + \\exp 0", "1").unwrap());
+        assert!(!exec("\\sin x", "\\cos x").unwrap());
+        assert!(!exec("\\sin x y", "y \\sin x").unwrap());
+        assert!(exec("x", "\\sin").is_err());
+    }
+
+    #[test]
+    fn sqrt_test() {
+        assert!(exec("\\sqrt{x}", "x^{1/2}").unwrap());
+        assert!(exec("\\sqrt x", "\\sqrt{x}").unwrap());
+        assert!(exec("\\sqrt[3]{x + 1}", "(x + 1)^{1/3}").unwrap());
+        assert!(exec("\\sqrt{4}", "2").unwrap());
+        assert!(!exec("\\sqrt{x}", "\\sqrt[3]{x}").unwrap());
+        assert!(exec("x", "\\sqrt").is_err());
+    }
+
+    #[test]
+    fn diff_test() {
+        let derivative = |s: &str| Formula::parse(s).unwrap().diff("x");
+        assert_eq!(
+            derivative("x^2 \\sin x"),
+            Formula::parse("2x \\sin x + x^2 \\cos x").unwrap()
+        );
+        assert_eq!(derivative("\\frac{1}{x}"), Formula::parse("-\\frac{1}{x^2}").unwrap());
+
+        // A student's antiderivative is verified by differentiating it
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        let antiderivative = Formula::parse("x \\ln x - x").unwrap();
+        assert!(numeric::equivalent(
+            &antiderivative.diff("x"),
+            &Formula::parse("\\ln x").unwrap(),
+            &config
+        ));
+    }
+
+    #[test]
+    fn derivative_test() {
+        assert!(exec("\\frac{dy}{dx}", "\\frac{d y}{d x}").unwrap());
+        assert!(exec("\\frac{dy}{dx}", "\\frac{\\mathrm{d}y}{\\mathrm{d}x}").unwrap());
+        assert!(exec("\\frac{dy}{dx}", "\\frac{d}{dx} y").unwrap());
+        assert!(exec("\\frac{dy}{dx}", "y'").unwrap());
+        assert!(exec("\\frac{d^2 y}{dx^2}", "y''").unwrap());
+        assert!(exec("\\frac{d^2 f}{dt^2}", "f''(t)").unwrap());
+        assert!(exec("\\frac{d}{dt} f(t)", "f'(t)").unwrap());
+        assert!(exec("\\dot{x}", "\\frac{dx}{dt}").unwrap());
+        assert!(exec("\\ddot x", "\\frac{d^{2} x}{dt^{2}}").unwrap());
+        assert!(exec("2 y' + y", "y + 2 \\frac{dy}{dx}").unwrap());
+        assert!(!exec("\\frac{dy}{dx}", "\\frac{dy}{dt}").unwrap());
+        assert!(!exec("y'", "y''").unwrap());
+        assert!(!exec("\\frac{dy}{dx}", "\\frac{y}{x}").unwrap());
+    }
+
+    #[test]
+    fn prime_test() {
+        assert!(exec("f'(2)", "f'(1 + 1)").unwrap());
+        assert!(!exec("f'(2)", "2 f'").unwrap());
+        assert!(!exec("f'(2)", "f'(3)").unwrap());
+        assert!(exec("x'", "x'").unwrap());
+        assert!(!exec("x'", "1").unwrap());
+        assert!(!exec("x'", "\\frac{dx}{dt}").unwrap());
+
+        let config = "prime_variable = t".parse::<Config>().unwrap();
+        assert!(exec_with_config("y'", "\\frac{dy}{dt}", &config).unwrap());
+        assert!(exec_with_config("x'", "\\dot{x}", &config).unwrap());
+        assert!(!exec_with_config("y'", "\\frac{dy}{dx}", &config).unwrap());
+    }
+
+    #[test]
+    fn partial_derivative_test() {
+        assert!(exec("\\frac{\\partial f}{\\partial x}", "\\frac{\\partial}{\\partial x} f").unwrap());
+        assert!(exec(
+            "\\frac{\\partial^2 f}{\\partial x \\partial y}",
+            "\\frac{\\partial^2 f}{\\partial y \\partial x}"
+        )
+        .unwrap());
+        assert!(exec(
+            "\\frac{\\partial^2 f}{\\partial x^2}",
+            "\\frac{\\partial}{\\partial x} \\frac{\\partial f}{\\partial x}"
+        )
+        .unwrap());
+        assert!(!exec("\\frac{\\partial f}{\\partial x}", "\\frac{\\partial f}{\\partial y}").unwrap());
+    }
+
+    #[test]
+    fn derivative_explicit_test() {
+        assert!(exec("\\frac{d}{dx} x^3", "3 x^2").unwrap());
+        assert!(exec("\\frac{d}{dx} (x^2 + \\sin x)", "2x + \\cos x").unwrap());
+        assert!(exec("\\frac{d^2}{dx^2} x^3", "6x").unwrap());
+        assert!(exec("\\frac{d}{dx} 5", "0").unwrap());
+        assert!(!exec("\\frac{d}{dx} x^3", "x^2").unwrap());
+    }
+
+    #[test]
+    fn derivative_fail_test() {
+        assert!(exec("x", "\\frac{d^2 y}{dx}").is_err());
+        assert!(exec("x", "\\frac{d}{dx}").is_err());
+        assert!(exec("x", "\\dot").is_err());
+    }
+
+    #[test]
+    fn limit_test() {
+        assert!(exec(
+            "\\lim_{x \\to 0} \\frac{\\sin x}{x}",
+            "\\lim_{x \\rightarrow 0} \\frac{\\sin(x)}{x}"
+        )
+        .unwrap());
+        assert!(exec("\\lim_{x \\to 0} \\frac{f}{x}", "\\lim_{t \\to 0} \\frac{f}{t}").unwrap());
+        assert!(exec("\\lim_{x \\to a^+} f x", "\\lim_{x \\to a+} x f").unwrap());
+        assert!(!exec("\\lim_{x \\to a^+} f x", "\\lim_{x \\to a^-} f x").unwrap());
+        assert!(!exec("\\lim_{x \\to a} f x", "\\lim_{x \\to b} f x").unwrap());
+    }
+
+    #[test]
+    fn limit_eval_test() {
+        assert!(exec("\\lim_{x \\to 0} \\frac{\\sin x}{x}", "1").unwrap());
+        assert!(exec("\\lim_{x \\to 1} \\frac{x^2 - 1}{x - 1}", "2").unwrap());
+        assert!(exec("\\lim_{n \\to \\infty} \\frac{2n + 1}{n}", "2").unwrap());
+        assert!(exec("\\lim_{x \\to 0^+} \\frac{|x|}{x}", "1").unwrap());
+        assert!(exec("\\lim_{x \\to 0^-} \\frac{|x|}{x}", "-1").unwrap());
+        assert!(!exec("\\lim_{x \\to 0} \\frac{|x|}{x}", "1").unwrap());
+        assert!(exec("\\lim_{n \\to \\infty} (1 + \\frac{1}{n})^n", "e").unwrap());
+        assert!(exec("\\lim_{x \\to \\infty} (1 + \\frac{1}{x})^x", "e").unwrap());
+        assert!(!exec("\\lim_{n \\to \\infty} (1 + \\frac{1}{n})^n", "2.718").unwrap());
+        assert!(!exec("\\lim_{x \\to \\infty} x", "0").unwrap());
+
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        assert!(exec_with_config("\\lim_{n \\to \\infty} (1 + \\frac{1}{n})^n", "e", &config).unwrap());
+        assert!(!exec_with_config("\\lim_{x \\to 0^+} \\frac{1}{x}", "0", &config).unwrap());
+    }
+
+    #[test]
+    fn limit_fail_test() {
+        assert!(exec("x", "\\lim_{x} x").is_err());
+        assert!(exec("x", "\\lim_{x \\to 0}").is_err());
+        assert!(exec("x", "\\lim_{x \\to 0}^{1} x").is_err());
+    }
+
+    #[test]
+    fn matrix_test() {
+        assert!(exec(
+            "\\begin{pmatrix} 1 & 2 \\\\ 3 & 4 \\end{pmatrix}",
+            "\\begin{bmatrix} 1 & 2 \\\\ 3 & 2 + 2 \\end{bmatrix}"
+        )
+        .unwrap());
+        assert!(exec(
+            "\\begin{pmatrix} x + 1 & -y \\end{pmatrix}",
+            "\\begin{pmatrix} 1 + x & -y \\\\ \\end{pmatrix}"
+        )
+        .unwrap());
+        assert!(exec(
+            "\\begin{array}{cc} a & b \\end{array}",
+            "\\begin{matrix} a & b \\end{matrix}"
+        )
+        .unwrap());
+        assert!(!exec(
+            "\\begin{pmatrix} 1 & 2 \\\\ 3 & 4 \\end{pmatrix}",
+            "\\begin{pmatrix} 1 & 3 \\\\ 2 & 4 \\end{pmatrix}"
+        )
+        .unwrap());
+
+        // Dimensions
+        assert!(!exec(
+            "\\begin{pmatrix} 1 & 2 \\end{pmatrix}",
+            "\\begin{pmatrix} 1 \\\\ 2 \\end{pmatrix}"
+        )
+        .unwrap());
+        assert!(!exec(
+            "\\begin{pmatrix} 1 & 2 \\end{pmatrix}",
+            "\\begin{pmatrix} 1 & 2 & 0 \\end{pmatrix}"
+        )
+        .unwrap());
+
+        // Scalar multiple of a matrix
+        assert!(exec(
+            "2 \\begin{pmatrix} 1 \\\\ x \\end{pmatrix}",
+            "\\begin{pmatrix} 1 \\\\ x \\end{pmatrix} \\cdot 2"
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn determinant_test() {
+        assert!(exec("\\begin{vmatrix} a & b \\\\ c & d \\end{vmatrix}", "a d - b c").unwrap());
+        assert!(exec("\\begin{vmatrix} 1 & 2 \\\\ 3 & 4 \\end{vmatrix}", "-2").unwrap());
+        assert!(exec(
+            "\\begin{vmatrix} 2 & 0 & 1 \\\\ 1 & 3 & 2 \\\\ 1 & 1 & 2 \\end{vmatrix}",
+            "6"
+        )
+        .unwrap());
+        assert!(!exec("\\begin{vmatrix} a & b \\\\ c & d \\end{vmatrix}", "a d + b c").unwrap());
+
+        // The determinant of I + J is n + 1
+        let matrix = |n: usize, x: &str| {
+            let rows = (0..n)
+                .map(|i| {
+                    (0..n)
+                        .map(|j| if i == j { x } else { "1" })
+                        .collect::<Vec<_>>()
+                        .join(" & ")
+                })
+                .collect::<Vec<_>>();
+            format!("\\begin{{vmatrix}} {} \\end{{vmatrix}}", rows.join(" \\\\ "))
+        };
+        assert!(exec(&matrix(10, "2"), "11").unwrap());
+        assert!(!exec(&matrix(10, "2"), "1").unwrap());
+        assert!(exec(&matrix(10, "x"), &matrix(10, "x")).unwrap());
+        assert!(!exec(&matrix(10, "x"), &matrix(10, "y")).unwrap());
+    }
+
+    #[test]
+    fn matrix_fail_test() {
+        assert!(exec("x", "\\begin{pmatrix} 1 & 2 \\\\ 3 \\end{pmatrix}").is_err());
+        assert!(exec("x", "\\begin{pmatrix} 1 & 2 \\end{bmatrix}").is_err());
+        assert!(exec("x", "\\begin{pmatrix} 1 & 2").is_err());
+        assert!(exec("x", "\\begin{vmatrix} 1 & 2 \\end{vmatrix}").is_err());
+        assert!(exec("x", "\\begin{align} x \\end{align}").is_err());
+        assert!(exec("x", "\\begin{pmatrix} 1 & \\\\ 3 & 4 \\end{pmatrix}").is_err());
+    }
+
+    #[test]
+    fn noncommutative_test() {
+        let config = "noncommutative = A, B, C".parse::<Config>().unwrap();
+        assert!(!exec_with_config("A B", "B A", &config).unwrap());
+        assert!(exec_with_config("2 A x B", "x A 2 B", &config).unwrap());
+        assert!(exec_with_config("A (B C)", "A B C", &config).unwrap());
+        assert!(!exec_with_config("A B C", "A C B", &config).unwrap());
+        assert!(exec_with_config("A B + B A", "B A + A B", &config).unwrap());
+
+        // Commutative by default
+        assert!(exec("A B", "B A").unwrap());
+
+        // Bold capitals and matrices
+        assert!(!exec("\\mathbf{A} \\mathbf{B}", "\\mathbf{B} \\mathbf{A}").unwrap());
+        assert!(exec("\\mathbf{A} \\boldsymbol{B}", "\\boldsymbol{A} \\mathbf{B}").unwrap());
+        assert!(exec("k \\mathbf{A} \\mathbf{B}", "\\mathbf{A} k \\mathbf{B}").unwrap());
+        assert!(!exec(
+            "\\begin{pmatrix} 1 & 2 \\end{pmatrix} \\begin{pmatrix} 3 \\\\ 4 \\end{pmatrix}",
+            "\\begin{pmatrix} 3 \\\\ 4 \\end{pmatrix} \\begin{pmatrix} 1 & 2 \\end{pmatrix}"
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn set_test() {
+        assert!(exec("\\{1, 2, 3\\}", "\\{3, 2, 1\\}").unwrap());
+        assert!(exec("\\{1, 2\\}", "\\{1, 1, 2\\}").unwrap());
+        assert!(exec("\\{x + 1, 2\\}", "\\left\\{ 2, 1 + x \\right\\}").unwrap());
+        assert!(exec("\\{\\}", "\\emptyset").unwrap());
+        assert!(exec("\\varnothing", "\\emptyset").unwrap());
+        assert!(!exec("\\{1, 2\\}", "\\{1, 2, 3\\}").unwrap());
+        assert!(!exec("\\{1\\}", "1").unwrap());
+        assert!(!exec("\\{\\}", "").unwrap());
+        assert!(exec("\\{x + 1\\} \\cdot 2", "2 (x + 1)").unwrap());
+        assert!(exec("\\{x\\}^2", "x^2").unwrap());
+    }
+
+    #[test]
+    fn set_builder_test() {
+        assert!(exec("\\{x \\mid x > 0\\}", "\\{x : x > 0\\}").unwrap());
+        assert!(exec("\\{2k \\mid k \\in \\mathbb{Z}\\}", "\\{k 2 : k \\in \\mathbb{Z}\\}").unwrap());
+        assert!(!exec("\\{x \\mid x > 0\\}", "\\{x \\mid x > 1\\}").unwrap());
+        assert!(exec("\\{x | x > 0\\}", "\\{x \\mid x > 0\\}").unwrap());
+        assert!(exec("\\{x | |x| < 1\\}", "\\{y : |y| < 1\\}").unwrap());
+        assert!(exec("\\left\\{ |x| \\right\\}", "\\{|x|\\}").unwrap());
+    }
+
+    #[test]
+    fn set_operator_test() {
+        assert!(exec("A \\cup B", "B \\cup A").unwrap());
+        assert!(exec("A \\cap B \\cap C", "C \\cap (B \\cap A)").unwrap());
+        assert!(exec("(A \\cup B) \\cup C", "A \\cup (C \\cup B)").unwrap());
+        assert!(exec("A \\cup B \\cap C", "A \\cup (B \\cap C)").unwrap());
+        assert!(!exec("A \\setminus B", "B \\setminus A").unwrap());
+        assert!(!exec("A \\cup B", "A \\cap B").unwrap());
+        assert!(exec("\\{1, 2\\} \\cup \\{3\\}", "\\{3\\} \\cup \\{2, 1\\}").unwrap());
+    }
+
+    #[test]
+    fn membership_test() {
+        assert!(exec("x \\in A \\cup B", "x \\in B \\cup A").unwrap());
+        assert!(exec("2 \\in \\{1, 2\\}", "2 \\in \\{2, 1\\}").unwrap());
+        assert!(!exec("x \\in A", "A \\in x").unwrap());
+    }
+
+    #[test]
+    fn set_fail_test() {
+        assert!(exec("x", "A \\cup").is_err());
+        assert!(exec("x", "\\cap B").is_err());
+        assert!(exec("x", "x \\in").is_err());
+        assert!(exec("x", "x \\in A \\in B").is_err());
+        assert!(exec("x", "\\{1, , 2\\}").is_err());
+        assert!(exec("x", "\\{x \\mid \\}").is_err());
+        assert!(exec("x", "\\{x \\mid x > 0 \\mid x < 1\\}").is_err());
+    }
+
+    #[test]
+    fn logic_test() {
+        assert!(exec("\\neg (p \\land q)", "\\neg p \\lor \\neg q").unwrap());
+        assert!(exec("\\lnot (p \\lor q)", "\\neg p \\wedge \\neg q").unwrap());
+        assert!(exec("p \\to q", "\\neg p \\lor q").unwrap());
+        assert!(exec("p \\Rightarrow q", "\\neg q \\implies \\neg p").unwrap());
+        assert!(exec("p \\leftrightarrow q", "(p \\to q) \\land (q \\to p)").unwrap());
+        assert!(exec("p \\oplus q", "\\neg (p \\iff q)").unwrap());
+        assert!(exec("p \\lor q \\land r", "p \\lor (q \\land r)").unwrap());
+        assert!(exec("p \\to q \\to r", "p \\land q \\to r").unwrap());
+        assert!(exec("\\neg \\neg p", "p").unwrap());
+        assert!(exec("x > 0 \\land p", "p \\land x > 0").unwrap());
+        assert!(!exec("p \\to q", "q \\to p").unwrap());
+        assert!(!exec("p \\lor q", "p \\oplus q").unwrap());
+        assert!(!exec("p \\land q", "p").unwrap());
+    }
+
+    #[test]
+    fn logic_fail_test() {
+        assert!(exec("p", "p \\land").is_err());
+        assert!(exec("p", "\\lor q").is_err());
+        assert!(exec("p", "\\neg").is_err());
+        assert!(exec("p", "p \\iff q \\iff r").is_err());
+    }
+
+    #[test]
+    fn relation_test() {
+        assert!(exec("x > 1", "1 < x").unwrap());
+        assert!(exec("x \\geq 1", "1 \\le x").unwrap());
+        assert!(exec("a = b + 1", "1 + b = a").unwrap());
+        assert!(exec("x \\neq 0", "0 \\ne x").unwrap());
+        assert!(exec("0 < x < 1", "x > 0 \\land x < 1").unwrap());
+        assert!(!exec("x < 1", "x \\le 1").unwrap());
+        assert!(!exec("x < 1", "1 < x").unwrap());
+        assert!(exec("x", "x <").is_err());
+    }
+
+    #[test]
+    fn interval_test() {
+        assert!(exec("x > 2", "(2, \\infty)").unwrap());
+        assert!(exec("x > 2", "]2, +\\infty[").unwrap());
+        assert!(exec("x > 2", "x \\in (2,\\infty)").unwrap());
+        assert!(exec("(2, \\infty)", "\\left( 2, \\infty \\right)").unwrap());
+        assert!(exec("0 < x \\le 1", "x \\in (0, 1]").unwrap());
+        assert!(exec("[0, 1[", "0 \\le x < 1").unwrap());
+        assert!(exec("x < -1 \\lor x \\ge 1", "(-\\infty, -1) \\cup [1, \\infty)").unwrap());
+        assert!(exec("(0, 1] \\cup [1, 2)", "(0, 2)").unwrap());
+        assert!(exec("x \\ne 0", "(-\\infty, 0) \\cup (0, +\\infty)").unwrap());
+        assert!(exec("\\neg (x > 2)", "x \\le 2").unwrap());
+        assert!(exec("[\\frac{1}{2}, 1]", "\\frac{1}{2} \\le x \\le 1").unwrap());
+        assert!(exec("x \\in \\mathbb{R}", "(-\\infty, \\infty)").unwrap());
+        assert!(!exec("x > 2", "[2, \\infty)").unwrap());
+        assert!(!exec("x > 2", "y > 2").unwrap());
+        assert!(!exec("(0, 1]", "[0, 1)").unwrap());
+
+        // Reversed ends are kept apart from the empty set
+        assert!(!exec("[3, 1]", "[5, 4]").unwrap());
+        assert!(!exec("[3, 1]", "\\emptyset").unwrap());
+        assert!(!exec("x \\in [3, 1]", "x \\in \\emptyset").unwrap());
+        assert!(exec("[3, 1]", "[3, 1]").unwrap());
+
+        // Ends are compared exactly unless numeric
+        assert!(!exec("x = 0.3333333333", "x = \\frac{1}{3}").unwrap());
+        assert!(!exec("x = \\pi", "x = 3.14159265358").unwrap());
+        assert!(!exec("x > 0.3333333333", "(\\frac{1}{3}, \\infty)").unwrap());
+        assert!(exec("x = \\frac{1}{2}", "x = 0.5").unwrap());
+        assert!(exec("x > \\sqrt{2}", "x > 2^{\\frac{1}{2}}").unwrap());
+
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        assert!(exec_with_config("x = 0.3333333333", "x = \\frac{1}{3}", &config).unwrap());
+    }
+
+    #[test]
+    fn interval_fail_test() {
+        assert!(exec("x", "(0, 1, 2]").is_err());
+        assert!(exec("x", "(, 1]").is_err());
+        assert!(exec("x", "(0]").is_err());
+    }
+
+    #[test]
+    fn tuple_test() {
+        assert!(exec("(1, -2)", "\\left( 1, -2 \\right)").unwrap());
+        assert!(exec("(x, y) = (3, 4)", "(3, 4) = (x, y)").unwrap());
+        assert!(exec("(1, 2, 3)", "(1, 1 + 1, 3)").unwrap());
+        assert!(!exec("(1, -2)", "(-2, 1)").unwrap());
+        assert!(!exec("(1, 2)", "(1, 2, 3)").unwrap());
+        assert!(!exec("(x, y) = (3, 4)", "(x, y) = (4, 3)").unwrap());
+        assert!(exec("x", "(1, , 2)").is_err());
+    }
+
+    #[test]
+    fn pairs_test() {
+        // In a set context or with an infinite end
+        assert!(exec("x \\in (0, 1)", "0 < x < 1").unwrap());
+        assert!(exec("(0, 1) \\cup (1, 2)", "x \\in (0, 2) \\land x \\ne 1").unwrap());
+        assert!(exec("(-\\infty, 0)", "x < 0").unwrap());
+
+        // Compared with an inequality
+        assert!(exec("(0, 1)", "0 < x < 1").unwrap());
+
+        let config = "pairs = tuples".parse::<Config>().unwrap();
+        assert!(!exec_with_config("(0, 1)", "0 < x < 1", &config).unwrap());
+        assert!(exec_with_config("x \\in (0, 1)", "0 < x < 1", &config).unwrap());
+
+        let config = "pairs = intervals".parse::<Config>().unwrap();
+        assert!(exec_with_config("(0, 1)", "]0, 1[", &config).unwrap());
+        assert!(!exec_with_config("(0, 1)", "(1, 0)", &config).unwrap());
+    }
+
+    #[test]
+    fn answer_list_test() {
+        assert!(exec("x = 2, x = -3", "x = -3, x = 2").unwrap());
+        assert!(exec("x = 2, x = -3", "x = -3 \\text{ or } x = 2").unwrap());
+        assert!(exec("x = 2, x = -3", "x \\in \\{2, -3\\}").unwrap());
+        assert!(exec("x = 2, x = -3", "2 = x \\lor x = -3").unwrap());
+        assert!(exec("x = \\frac{1}{2}; x = 1", "x = 1 \\textrm{or} x = 0.5").unwrap());
+        assert!(exec("\\{1, 2\\}", "1, 2").unwrap());
+        assert!(!exec("x = 2, x = -3", "x = 2").unwrap());
+        assert!(!exec("x = 2, x = -3", "x = 2, x = -3, x = 0").unwrap());
+        assert!(!exec("x = 2, x = -3", "y = 2, y = -3").unwrap());
+        assert!(!exec("x = 2, x = 2", "x = 2").unwrap());
+        assert!(exec("x", "x = 2, ").is_err());
+
+        // Commas of a quantifier
+        assert!(exec("\\forall x, P x", "\\forall y, P y").unwrap());
+    }
+
+    #[test]
+    fn plus_minus_test() {
+        let quadratic = "x = \\frac{-b \\pm \\sqrt{b^2-4ac}}{2a}";
+        assert!(exec(
+            quadratic,
+            "x = \\frac{-b + \\sqrt{b^2-4ac}}{2a}, x = \\frac{-b - \\sqrt{b^2-4ac}}{2a}"
+        )
+        .unwrap());
+        assert!(exec(quadratic, "x = \\frac{-b \\mp \\sqrt{b^2-4ac}}{2a}").unwrap());
+        assert!(exec("x = 1 \\pm 2", "x = -1 \\text{ or } x = 3").unwrap());
+        assert!(exec("x = \\pm 2", "x \\in \\{2, -2\\}").unwrap());
+        assert!(exec("a \\pm b \\mp c", "a + b - c, a - b + c").unwrap());
+        assert!(!exec("a \\pm b \\mp c", "a + b + c, a - b - c").unwrap());
+        assert!(!exec("x = 1 \\pm 2", "x = 3").unwrap());
+        assert!(exec("x", "x \\pm").is_err());
+    }
+
+    #[test]
+    fn combinatorial_test() {
+        assert!(exec("\\binom{n}{k}", "\\frac{n!}{k!(n-k)!}").unwrap());
+        assert!(!exec("\\binom{5}{2}", "\\frac{5!}{3!}").unwrap());
+
+        // `C` and `P` are symbols unless declared combinatorial
+        let config = "combinatorial = C, P".parse::<Config>().unwrap();
+        assert!(exec_with_config("{n \\choose k}", "C^n_k", &config).unwrap());
+        assert!(exec_with_config("P(n,k)", "\\frac{n!}{(n-k)!}", &config).unwrap());
+        assert!(!exec("C_1^2", "2").unwrap());
+        assert!(!exec("C_0^2", "1").unwrap());
+        assert!(!exec("P(1, 2)", "P(3, 4)").unwrap());
+        assert!(!exec("{n \\choose k}", "C^n_k").unwrap());
+        assert!(exec("\\binom{n+1}{k+1}", "\\binom{n}{k} + \\binom{n}{k+1}").unwrap());
+        assert!(exec("30!", "265252859812191058636308480000000").unwrap());
+        assert!(!exec("30!", "265252859812191058636308480000001").unwrap());
+        assert!(exec("\\binom{5}{2}", "10").unwrap());
+        assert!(!exec("\\binom{n}{k}", "\\frac{n!}{k!}").unwrap());
+    }
+
+    #[test]
+    fn modular_test() {
+        assert!(exec("x \\equiv 3 \\pmod{5}", "x \\equiv 8 \\pmod{5}").unwrap());
+        assert!(exec("x \\equiv 3 \\pmod{5}", "x \\equiv -2 \\pmod 5").unwrap());
+        assert!(exec("2x \\equiv 4 \\pmod{6}", "x \\equiv 2 \\pmod{3}").unwrap());
+        assert!(!exec("2x \\equiv 4 \\pmod{6}", "x \\equiv 2 \\pmod{6}").unwrap());
+        assert!(exec("17 \\bmod 5", "2").unwrap());
+        assert!(exec("\\lfloor \\frac{7}{2} \\rfloor", "\\lceil \\frac{5}{2} \\rceil").unwrap());
+        assert!(!exec("\\lfloor \\frac{n}{2} \\rfloor", "\\frac{n}{2}").unwrap());
+
+        // Variables in roundings are real, unless combinatorics make them integers
+        assert!(!exec("\\lfloor \\frac{n}{2} \\rfloor + \\lceil \\frac{n}{2} \\rceil", "n").unwrap());
+        assert!(exec(
+            "\\lfloor \\frac{n}{2} \\rfloor + \\lceil \\frac{n}{2} \\rceil + n!",
+            "n + n!"
+        )
+        .unwrap());
+        assert!(exec("\\lfloor x \\rfloor + 1", "\\lfloor x + 1 \\rfloor").unwrap());
+        assert!(!exec("\\lfloor x \\rfloor", "x").unwrap());
+        assert!(!exec("\\lfloor x \\rfloor", "\\lceil x \\rceil").unwrap());
+        assert!(!exec("\\lfloor x + 0.5 \\rfloor", "\\lceil x \\rceil").unwrap());
+        assert!(!exec("x \\bmod 1", "0").unwrap());
+
+        // Congruences with constant moduli are compared at every residue
+        assert!(!exec("x \\equiv 1 \\pmod{1000}", "x \\equiv 1 \\pmod{2000}").unwrap());
+        assert!(exec("x \\equiv 1 \\pmod{2000}", "x \\equiv 2001 \\pmod{2000}").unwrap());
+
+        // Other congruences are sampled
+        assert!(exec("x \\equiv 1 \\pmod{n}", "x \\equiv n + 1 \\pmod{n}").unwrap());
+    }
+
+    #[test]
+    fn rounding_test() {
+        assert!(exec("\\lfloor x \\rceil", "\\operatorname{round}(x)").unwrap());
+        assert!(exec("\\lfloor x + 0.5 \\rfloor", "\\left\\lfloor x \\right\\rceil").unwrap());
+        assert!(exec("\\lfloor \\frac{5}{2} \\rceil", "3").unwrap());
+        assert!(exec("\\operatorname{round}(-2.5)", "-2").unwrap());
+        assert!(!exec("\\lfloor x \\rceil", "\\lfloor x \\rfloor").unwrap());
+        assert!(exec("\\operatorname{round}(x) + 1", "\\operatorname{round}(x + 1)").unwrap());
+        assert!(exec("\\operatorname{round}(x) \\cdot 2", "2 \\lfloor x \\rceil").unwrap());
+        assert!(exec("x", "\\lfloor x \\rceil \\rceil").is_err());
+    }
+
+    #[test]
+    fn complex_test() {
+        assert!(exec("i^2", "-1").unwrap());
+        assert!(exec("i^2 x", "-x").unwrap());
+        assert!(exec("x i^2", "-x").unwrap());
+        assert!(exec("i^2 R", "-R").unwrap());
+        assert!(exec("i^3 x", "-i x").unwrap());
+        assert!(exec("(1 + i)^2", "2i").unwrap());
+        assert!(exec("\\frac{1}{i}", "-i").unwrap());
+        assert!(exec("e^{i \\pi} + 1", "0").unwrap());
+        assert!(exec("\\overline{i z}", "-i \\bar z").unwrap());
+        assert!(exec("\\overline{z + i w}", "\\bar z - i \\bar w").unwrap());
+        assert!(exec("\\Im z", "\\frac{z - \\bar z}{2i}").unwrap());
+        assert!(exec("\\Re z", "\\frac{z + \\overline{z}}{2}").unwrap());
+        assert!(exec("\\arg(-1)", "\\pi").unwrap());
+        assert!(exec("(x + i y)(x - i y)", "x^2 + y^2").unwrap());
+        assert!(!exec("\\bar z", "z").unwrap());
+        assert!(!exec("i^3", "i").unwrap());
+        assert!(!exec("2^{70} i", "2^{70} i + i").unwrap());
+        assert!(exec("(1 + i)^{80}", "2^{40}").unwrap());
+
+        let config = "imaginary = j".parse().unwrap();
+        assert!(exec_with_config("j^2", "-1", &config).unwrap());
+        assert!(exec_with_config("(1 + j)(1 - j)", "2", &config).unwrap());
+    }
+
+    #[test]
+    fn overline_test() {
+        // Overlines are conjugates by default
+        assert!(exec("\\bar x", "\\overline{x}").unwrap());
+        assert!(!exec("\\bar x", "x").unwrap());
+        assert!(exec("z \\bar z", "|z|^2").unwrap());
+        assert!(exec("\\overline{z w}", "\\bar z \\bar w").unwrap());
+        assert!(exec("\\overline{z + w}", "\\bar z + \\bar w").unwrap());
+
+        // Repeating decimals are numbers in any case
+        assert!(exec("0.\\overline{3}", "\\frac{1}{3}").unwrap());
+        assert!(exec("0.1\\overline{6}", "\\frac{1}{6}").unwrap());
+        assert!(exec("1.\\overline{142857}", "\\frac{8}{7}").unwrap());
+        assert!(exec("0.\\bar{6}", "\\frac{2}{3}").unwrap());
+
+        // Or symbols such as means or segments
+        let config = "overline = symbol".parse::<Config>().unwrap();
+        assert!(exec_with_config("\\bar x", "\\overline{x}", &config).unwrap());
+        assert!(!exec_with_config("\\bar x", "x", &config).unwrap());
+        assert!(!exec_with_config("z \\bar z", "|z|^2", &config).unwrap());
+        assert!(exec_with_config("\\overline{x + 1}", "\\overline{1 + x}", &config).unwrap());
+        assert!(exec_with_config("\\overline{AB}", "\\overline{AB}", &config).unwrap());
+        assert!(!exec_with_config("\\overline{AB}", "\\overline{AC}", &config).unwrap());
+        assert!(exec_with_config("0.\\overline{3}", "\\frac{1}{3}", &config).unwrap());
+
+        let config = "overline = symbol\nmode = numeric".parse::<Config>().unwrap();
+        assert!(exec_with_config("\\bar x + 1", "1 + \\bar x", &config).unwrap());
+        assert!(!exec_with_config("\\bar x", "x", &config).unwrap());
+    }
+
+    #[test]
+    fn quantifier_test() {
+        assert!(exec("\\forall x \\exists y (x < y)", "\\forall a \\exists b (a < b)").unwrap());
+        assert!(exec("\\forall x, P x", "\\forall t: P t").unwrap());
+        assert!(exec("\\exists x \\in A, x > 0", "\\exists y \\in A: y > 0").unwrap());
+        assert!(exec(
+            "\\forall \\epsilon > 0, \\epsilon^2 > 0",
+            "\\forall \\delta > 0: \\delta^2 > 0"
+        )
+        .unwrap());
+        assert!(exec("\\forall x (P x \\land Q x)", "\\forall y (Q y \\land P y)").unwrap());
+        assert!(exec("p \\land \\forall x P x", "(\\forall y P y) \\land p").unwrap());
+        assert!(!exec("p \\land \\forall x P x", "\\forall x P x \\land p").unwrap());
+        assert!(!exec("\\forall x \\exists y (x < y)", "\\exists y \\forall x (x < y)").unwrap());
+        assert!(!exec("\\forall x \\exists y (x < y)", "\\forall x \\exists y (y < x)").unwrap());
+        assert!(!exec("\\forall x, P x", "\\exists x, P x").unwrap());
+
+        // A free variable is not renamed
+        assert!(!exec("\\forall x (x < z)", "\\forall z (z < z)").unwrap());
+
+        assert!(exec("\\forall x_1 (x_1 > 0)", "\\forall y (y > 0)").unwrap());
+        assert!(exec("\\forall x_{12} (x_{12} > 0)", "\\forall y (y > 0)").unwrap());
+        assert!(!exec("\\forall x_1 (x_2 > 0)", "\\forall y (y > 0)").unwrap());
+        assert!(exec("\\forall x > 0 (x^2 > 0)", "\\forall y > 0, y^2 > 0").unwrap());
+        assert!(exec(
+            "\\forall x > 0 \\exists y (y < x)",
+            "\\forall a > 0, \\exists b (b < a)"
+        )
+        .unwrap());
+        assert!(exec("\\exists! x (P x)", "\\exists! y, P y").unwrap());
+        assert!(!exec("\\exists! x (P x)", "\\exists x (P x)").unwrap());
+    }
+
+    #[test]
+    fn bound_variable_test() {
+        assert!(exec("\\{x \\mid x > 0\\}", "\\{t : t > 0\\}").unwrap());
+        assert!(exec("\\{2k \\mid k \\in \\mathbb{Z}\\}", "\\{2n \\mid n \\in \\mathbb{Z}\\}").unwrap());
+        assert!(!exec("\\{x + a \\mid x > 0\\}", "\\{x + b \\mid x > 0\\}").unwrap());
+        assert!(exec("\\sum_{i=1}^{n} \\int_0^1 i t dt", "\\sum_{k=1}^{n} \\int_0^1 k s ds").unwrap());
+    }
+
+    #[test]
+    fn quantifier_fail_test() {
+        assert!(exec("x", "\\forall").is_err());
+        assert!(exec("x", "\\forall x").is_err());
+        assert!(exec("x", "\\exists 2, x").is_err());
+        assert!(exec("x", "\\forall x \\in A").is_err());
+    }
+
+    #[test]
+    fn formatting_macro_test() {
+        assert!(exec("2 x + 1", "2\\,x + 1").unwrap());
+        assert!(exec("2 x + 1", "\\displaystyle 2 x \\; + \\quad 1").unwrap());
+        assert!(exec("x y", "x\\!y").unwrap());
+        assert!(exec("x + 1", "$x + 1$").unwrap());
+    }
+
+    #[test]
+    fn font_macro_test() {
+        assert!(exec("2 x", "2 \\mathrm{x}").unwrap());
+        assert!(exec("v", "\\mathbf{v}").unwrap());
+        assert!(exec("\\mathrm{KE}", "\\mathit{KE}").unwrap());
+        assert!(!exec("\\mathrm{KE}", "K E").unwrap());
+        assert!(!exec("R", "\\mathbb{R}").unwrap());
+        assert!(exec("x", "\\mathrm x").is_err());
+    }
+
+    #[test]
+    fn font_macro_config_test() {
+        let mut config = Config::default();
+        config.unwrapped_fonts.remove("mathbf");
+        assert!(!exec_with_config("v", "\\mathbf{v}", &config).unwrap());
+        assert!(exec_with_config("2 \\mathbf{v}", "\\mathbf{ v } \\cdot 2", &config).unwrap());
+    }
+
+    #[test]
+    fn text_macro_test() {
+        assert!(exec("5 \\text{m}", "5\\,\\text{ m }").unwrap());
+        assert!(!exec("5 \\text{m}", "5 m").unwrap());
+        assert!(!exec("5 \\text{m}", "5 \\text{s}").unwrap());
+    }
+
+    #[test]
+    fn macro_test() {
+        let mut config = Config::default();
+        config
+            .load_macros("\\newcommand{\\R}{\\mathbb{R}} \\newcommand{\\twice}[1]{2 (#1)}")
+            .unwrap();
+        assert!(exec_with_config("\\R", "\\mathbb{R}", &config).unwrap());
+        assert!(exec_with_config("\\twice{x + 1}", "2 (x + 1)", &config).unwrap());
+        assert!(exec_with_config("\\twice x", "x \\cdot 2", &config).unwrap());
+        assert!(exec_with_config("\\twice{\\twice{x}}", "4 x", &config).unwrap());
+        assert!(!exec_with_config("\\twice{x}", "x", &config).unwrap());
+        assert!(exec_with_config("\\twice", "x", &config).is_err());
+    }
+
+    #[test]
+    fn macro_default_test() {
+        let mut config = Config::default();
+        config.load_macros("\\def\\half#1{#1 / 2}").unwrap();
+        config
+            .define_macro("\\scale", Macro::new(2, Some("3"), "#1 #2"))
+            .unwrap();
+        assert!(config.define_macro("\\bad", Macro::new(0, Some("3"), "x")).is_err());
+        assert!(exec_with_config("\\half{x}", "x \\div 2", &config).unwrap());
+        assert!(exec_with_config("\\scale{x}", "3 x", &config).unwrap());
+        assert!(exec_with_config("\\scale[4]{x}", "4 x", &config).unwrap());
+    }
+
+    #[test]
+    fn abs_test() {
+        assert!(exec("|x|", "|x|").unwrap());
+        assert!(exec("|x|", "\\lvert x \\rvert").unwrap());
+        assert!(exec("|x|", "\\left| x \\right|").unwrap());
+        assert!(exec("|x|", "\\bigl| x \\bigr|").unwrap());
+        assert!(exec("2 |x + 1|", "|1 + x| \\cdot 2").unwrap());
+        assert!(exec("|x| |y|", "|y||x|").unwrap());
+        assert!(!exec("|x|", "x").unwrap());
+        assert!(!exec("|x|", "\\|x\\|").unwrap());
+    }
+
+    #[test]
+    fn abs_nested_test() {
+        assert!(exec("||x| - |y||", "\\left| |x| - |y| \\right|").unwrap());
+        assert!(exec("|x|y|z|", "|x| \\cdot y \\cdot |z|").unwrap());
+        assert!(exec("||x||", "|x|").unwrap());
+    }
+
+    #[test]
+    fn abs_fact_test() {
+        assert!(exec("|-x|", "|x|").unwrap());
+        assert!(exec("|x - y|", "|y - x|").unwrap());
+        assert!(exec("|-3|", "3").unwrap());
+        assert!(exec("\\|-v\\|", "\\|v\\|").unwrap());
+        assert!(!exec("|x + y|", "|x - y|").unwrap());
+    }
+
+    #[test]
+    fn norm_test() {
+        assert!(exec("\\|v\\|", "\\lVert v \\rVert").unwrap());
+        assert!(exec("2 \\|v\\|", "\\left\\| v \\right\\| 2").unwrap());
+    }
+
+    #[test]
+    fn abs_fail_test() {
+        assert!(exec("x", "|x").is_err());
+        assert!(exec("x", "\\left| x )").is_err());
+        assert!(exec("x", "(x|)").is_err());
+    }
+
+    #[test]
+    fn pow_test() {
+        assert!(exec("x^2", "x^{2}").unwrap());
+        assert!(exec("2 x^2", "x^2 \\cdot 2").unwrap());
+        assert!(exec("(x + 1)^2", "(1 + x)^{2}").unwrap());
+        assert!(exec("2^3", "8").unwrap());
+        assert!(!exec("x^2", "x 2").unwrap());
+        assert!(!exec("x^2", "2^x").unwrap());
+        assert!(exec("x", "x^").is_err());
+        assert!(exec("x", "x^2^3").is_err());
+    }
+
+    #[test]
+    fn subscript_test() {
+        assert!(exec("x_1 + x_2", "x_{2} + x_{1}").unwrap());
+        assert!(exec("a_n^2", "a_{n}^{2}").unwrap());
+        assert!(exec("a_n^2", "a^2_n").unwrap());
+        assert!(!exec("x_1", "x_2").unwrap());
+        assert!(!exec("x_1", "x 1").unwrap());
+    }
+
+    #[test]
+    fn sum_test() {
+        assert!(exec("\\sum_{k=1}^{n} k^2", "\\sum_{k=1}^n k^2").unwrap());
+        assert!(exec("\\sum_{k=1}^{n} 2 k", "\\sum_{k=1}^{n} k \\cdot 2").unwrap());
+        assert!(exec("\\sum\\limits_{k=1}^{n} 2 k", "\\sum_{k=1}^{n} k \\cdot 2").unwrap());
+        assert!(exec("\\sum_{k=1}^{n} k + 1", "1 + \\sum_{k=1}^{n} k").unwrap());
+        assert!(!exec("\\sum_{k=1}^{n} k", "\\sum_{k=0}^{n} k").unwrap());
+        assert!(!exec("\\sum_{k=1}^{n} k", "\\prod_{k=1}^{n} k").unwrap());
+    }
+
+    #[test]
+    fn sum_alpha_equivalence_test() {
+        assert!(exec("\\sum_{i=1}^{n} i^2", "\\sum_{j=1}^{n} j^2").unwrap());
+        assert!(exec("\\sum_{i} a_i", "\\sum_{k} a_k").unwrap());
+        assert!(exec(
+            "\\sum_{i=1}^{n} \\sum_{j=1}^{i} i j",
+            "\\sum_{a=1}^{n} \\sum_{b=1}^{a} b a"
+        )
+        .unwrap());
+        assert!(!exec("\\sum_{i=1}^{n} \\sum_{j=1}^{n} i", "\\sum_{i=1}^{n} \\sum_{j=1}^{n} j").unwrap());
+        assert!(!exec("\\sum_{i=1}^{n} i x", "\\sum_{x=1}^{n} x x").unwrap());
+    }
+
+    #[test]
+    fn sum_over_set_test() {
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        assert!(exec_with_config("\\sum_{k \\in \\{1, 2, 4\\}} k^2", "21", &config).unwrap());
+        assert!(exec_with_config("\\prod_{k \\in \\{1, 2, 4\\}} k", "8", &config).unwrap());
+        assert!(exec("\\sum_{k \\in S} a_k", "\\sum_{j \\in S} a_j").unwrap());
+        assert!(!exec("\\sum_{k \\in S} a_k", "\\sum_{k \\in T} a_k").unwrap());
+    }
+
+    #[test]
+    fn sum_eval_test() {
+        assert!(exec("\\sum_{k=1}^{3} k", "6").unwrap());
+        assert!(exec("\\sum_{k=1}^{3} k^2", "14").unwrap());
+        assert!(exec("\\prod_{k=1}^{4} k", "24").unwrap());
+        assert!(exec("\\sum_{i=0}^{2} 2^i", "7").unwrap());
+    }
+
+    #[test]
+    fn sum_fail_test() {
+        assert!(exec("x", "\\sum_{k=1}^{n}").is_err());
+        assert!(exec("x", "\\sum_{=1}^{n} k").is_err());
+    }
+
+    #[test]
+    fn integral_test() {
+        assert!(exec("\\int x^2 dx", "\\int x^2\\,dx").unwrap());
+        assert!(exec("\\int x^2 dx", "\\int x^2 \\mathrm{d}x").unwrap());
+        assert!(exec("\\int_0^1 f dx", "\\int_{0}^{1} f \\, dx").unwrap());
+        assert!(exec("\\int dx", "\\int 1 dx").unwrap());
+        assert!(exec("2 \\int_a^b x dx + 1", "1 + 2 \\int_a^b x\\,dx").unwrap());
+        assert!(!exec("\\int_0^1 f dx", "\\int_0^2 f dx").unwrap());
+        assert!(!exec("\\int x y dx", "\\int x y dy").unwrap());
+        assert!(!exec("\\oint z dz", "\\int z dz").unwrap());
+        assert!(!exec("\\iint_D f dx dy", "\\int_D \\int f dx dy").unwrap());
+        assert!(exec("\\int_0^1 x dx \\cdot 2", "2 \\int_0^1 x dx").unwrap());
+        assert!(exec("\\int_0^1 x dx / 2", "\\frac{1}{4}").unwrap());
+    }
+
+    #[test]
+    fn integral_alpha_equivalence_test() {
+        assert!(exec("\\int_0^1 x^2 dx", "\\int_0^1 t^2 dt").unwrap());
+        assert!(exec("\\int e^{\\theta} d\\theta", "\\int e^{u} du").unwrap());
+        assert!(exec("\\iint x y dx dy", "\\iint u v du dv").unwrap());
+        assert!(exec("\\int_0^1 \\int_0^x x y dy dx", "\\int_0^1 \\int_0^s s t dt ds").unwrap());
+        assert!(!exec("\\iint x^2 y dx dy", "\\iint x y^2 dx dy").unwrap());
+    }
+
+    #[test]
+    fn integral_eval_test() {
+        assert!(exec("\\int_0^1 x dx", "1 / 2").unwrap());
+        assert!(exec("\\int_0^2 3 t^2 dt", "8").unwrap());
+        assert!(exec("\\int_0^1 \\int_0^1 x y dx dy", "0.25").unwrap());
+        assert!(exec("\\int_0^1 |x - 0.3| dx", "0.29").unwrap());
+        assert!(exec("\\int_{-1}^{1} \\sqrt{1 - x^2} dx", "\\frac{\\pi}{2}").unwrap());
+    }
+
+    #[test]
+    fn integral_fail_test() {
+        assert!(exec("x", "\\int x^2").is_err());
+        assert!(exec("x", "\\iint x y dx").is_err());
+        assert!(exec("x", "\\int \\lambda").is_err());
+    }
+
+    #[test]
+    fn numeric_mode_test() {
+        let config = "mode = numeric".parse::<Config>().unwrap();
+        assert!(exec_with_config("(x + 1)^2", "x^2 + 2x + 1", &config).unwrap());
+        assert!(exec_with_config("\\int_0^1 x^4 dx", "0.2", &config).unwrap());
+        assert!(exec_with_config("\\int_0^a x dx", "a^2 / 2", &config).unwrap());
+        assert!(!exec_with_config("(x + 1)^2", "x^2 + 1", &config).unwrap());
+        assert!(!exec("(x + 1)^2", "x^2 + 2x + 1").unwrap());
+    }
+
+    #[test]
+    fn up_to_constant_mode_test() {
+        let config = "mode = up_to_constant: x".parse::<Config>().unwrap();
+        assert!(exec_with_config("\\frac{x^2}{2} + C", "\\frac{x^2+1}{2}", &config).unwrap());
+        assert!(exec_with_config("\\frac{x^2}{2} + C", "\\frac{x^2}{2} + K", &config).unwrap());
+        assert!(exec_with_config("\\frac{x^3}{3} + c", "\\frac{x^3}{3}", &config).unwrap());
+        assert!(!exec_with_config("\\frac{x^2}{2} + C", "x^2 + C", &config).unwrap());
+        assert!(!exec("\\frac{x^2}{2} + C", "\\frac{x^2+1}{2}").unwrap());
+    }
 }
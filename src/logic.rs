@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::formula::Formula;
+
+// Formulas with at most this many atoms are compared by truth tables, the others by BDDs
+const TRUTH_TABLE_MAX_ATOMS: usize = 10;
+
+// Truth values of atoms falsifying an equivalence, e.g. [(p, true), (q, false)]
+pub type Assignment = Vec<(Formula, bool)>;
+
+// Terminal nodes of a BDD
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+// Reduced ordered binary decision diagram, whose nodes are shared by all built formulas
+struct Bdd {
+    nodes: Vec<(usize, usize, usize)>, // (atom, low, high)
+    unique: HashMap<(usize, usize, usize), usize>,
+    cache: HashMap<(char, usize, usize), usize>,
+}
+
+impl Bdd {
+    fn new() -> Self {
+        return Self {
+            nodes: vec![(usize::MAX, FALSE, FALSE), (usize::MAX, TRUE, TRUE)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        };
+    }
+
+    fn make(&mut self, atom: usize, low: usize, high: usize) -> usize {
+        if low == high {
+            return low;
+        }
+
+        if let Some(&node) = self.unique.get(&(atom, low, high)) {
+            return node;
+        }
+
+        self.nodes.push((atom, low, high));
+        self.unique.insert((atom, low, high), self.nodes.len() - 1);
+        return self.nodes.len() - 1;
+    }
+
+    // Combine two diagrams by a boolean operator tagged by `op` for the cache
+    fn apply(&mut self, op: char, f: fn(bool, bool) -> bool, a: usize, b: usize) -> usize {
+        if a <= TRUE && b <= TRUE {
+            return if f(a == TRUE, b == TRUE) { TRUE } else { FALSE };
+        }
+
+        if let Some(&node) = self.cache.get(&(op, a, b)) {
+            return node;
+        }
+
+        let atom = self.nodes[a].0.min(self.nodes[b].0);
+        let cofactors = |node: usize, nodes: &[(usize, usize, usize)]| match nodes[node] {
+            (v, low, high) if v == atom => (low, high),
+            _ => (node, node),
+        };
+        let (a_low, a_high) = cofactors(a, &self.nodes);
+        let (b_low, b_high) = cofactors(b, &self.nodes);
+
+        let low = self.apply(op, f, a_low, b_low);
+        let high = self.apply(op, f, a_high, b_high);
+        let node = self.make(atom, low, high);
+
+        self.cache.insert((op, a, b), node);
+        return node;
+    }
+
+    fn build(&mut self, formula: &Formula, atoms: &[Formula]) -> usize {
+        return match formula {
+            Formula::Not(p) => {
+                let p = self.build(p, atoms);
+                self.apply('^', |a, b| a ^ b, p, TRUE)
+            }
+            Formula::And(formulas) => self.build_all(formulas, atoms, '&', |a, b| a && b, TRUE),
+            Formula::Or(formulas) => self.build_all(formulas, atoms, '|', |a, b| a || b, FALSE),
+            Formula::Xor(formulas) => self.build_all(formulas, atoms, '^', |a, b| a ^ b, FALSE),
+            Formula::Implies(p, q) => {
+                let (p, q) = (self.build(p, atoms), self.build(q, atoms));
+                self.apply('>', |a, b| !a || b, p, q)
+            }
+            Formula::Iff(p, q) => {
+                let (p, q) = (self.build(p, atoms), self.build(q, atoms));
+                self.apply('=', |a, b| a == b, p, q)
+            }
+            atom => {
+                let index = atoms.iter().position(|a| a == atom).unwrap_or(usize::MAX);
+                self.make(index, FALSE, TRUE)
+            }
+        };
+    }
+
+    fn build_all(
+        &mut self,
+        formulas: &[Formula],
+        atoms: &[Formula],
+        op: char,
+        f: fn(bool, bool) -> bool,
+        init: usize,
+    ) -> usize {
+        return formulas.iter().fold(init, |acc, formula| {
+            let node = self.build(formula, atoms);
+            return self.apply(op, f, acc, node);
+        });
+    }
+
+    // Truth values reaching TRUE from `node`, with unvisited atoms false
+    fn satisfy(&self, node: usize, count: usize) -> Option<Vec<bool>> {
+        if node == FALSE {
+            return None;
+        }
+
+        let mut values = vec![false; count];
+        let mut node = node;
+
+        // Every non-terminal node of a reduced diagram reaches TRUE
+        while node > TRUE {
+            let (atom, low, high) = self.nodes[node];
+            values[atom] = low == FALSE;
+            node = if low == FALSE { high } else { low };
+        }
+
+        return Some(values);
+    }
+}
+
+// Connectives are interpreted, anything else is an atom, e.g. `x > 0` in `x > 0 \land p`
+pub fn is_propositional(formula: &Formula) -> bool {
+    return matches!(
+        formula,
+        Formula::Not(_)
+            | Formula::And(_)
+            | Formula::Or(_)
+            | Formula::Xor(_)
+            | Formula::Implies(_, _)
+            | Formula::Iff(_, _)
+    );
+}
+
+// Distinct atoms in order of appearance, e.g. `p \land (q \lor p)` => [p, q]
+fn collect_atoms(formula: &Formula, atoms: &mut Vec<Formula>) {
+    if is_propositional(formula) {
+        formula.children().into_iter().for_each(|f| collect_atoms(f, atoms));
+    } else if !atoms.contains(formula) {
+        atoms.push(formula.clone());
+    }
+}
+
+fn eval_bool(formula: &Formula, atoms: &[Formula], values: &[bool]) -> bool {
+    let eval = |f: &Formula| eval_bool(f, atoms, values);
+
+    return match formula {
+        Formula::Not(p) => !eval(p),
+        Formula::And(formulas) => formulas.iter().all(eval),
+        Formula::Or(formulas) => formulas.iter().any(eval),
+        Formula::Xor(formulas) => formulas.iter().fold(false, |acc, f| acc ^ eval(f)),
+        Formula::Implies(p, q) => !eval(p) || eval(q),
+        Formula::Iff(p, q) => eval(p) == eval(q),
+        atom => atoms.iter().position(|a| a == atom).map_or(false, |i| values[i]),
+    };
+}
+
+// An assignment under which `lhs` and `rhs` differ, or None if they are equivalent
+// e.g. `\neg (p \land q)` and `\neg p \lor \neg q` => None, `p \to q` and `q \to p` => Some([(p, true), (q, false)])
+pub fn counterexample(lhs: &Formula, rhs: &Formula) -> Option<Assignment> {
+    let mut atoms = Vec::new();
+    collect_atoms(lhs, &mut atoms);
+    collect_atoms(rhs, &mut atoms);
+
+    let values = if atoms.len() <= TRUTH_TABLE_MAX_ATOMS {
+        truth_table_counterexample(lhs, rhs, &atoms)
+    } else {
+        bdd_counterexample(lhs, rhs, &atoms)
+    };
+
+    let assignment = atoms.into_iter().zip(values?).collect::<Assignment>();
+    debug!("Logic: falsified by {:?}", assignment);
+    return Some(assignment);
+}
+
+fn truth_table_counterexample(lhs: &Formula, rhs: &Formula, atoms: &[Formula]) -> Option<Vec<bool>> {
+    return (0..1usize << atoms.len())
+        .map(|row| (0..atoms.len()).map(|i| row >> i & 1 == 1).collect::<Vec<_>>())
+        .find(|values| eval_bool(lhs, atoms, values) != eval_bool(rhs, atoms, values));
+}
+
+fn bdd_counterexample(lhs: &Formula, rhs: &Formula, atoms: &[Formula]) -> Option<Vec<bool>> {
+    let mut bdd = Bdd::new();
+    let l = bdd.build(lhs, atoms);
+    let r = bdd.build(rhs, atoms);
+
+    // Canonical diagrams: equivalent formulas share the node
+    let difference = bdd.apply('^', |a, b| a ^ b, l, r);
+    return bdd.satisfy(difference, atoms.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formula::Formula;
+    use crate::logic::*;
+
+    fn parse(s: &str) -> Formula {
+        return Formula::parse(s).unwrap();
+    }
+
+    #[test]
+    fn counterexample_test() {
+        assert_eq!(
+            counterexample(&parse("\\neg (p \\land q)"), &parse("\\neg p \\lor \\neg q")),
+            None
+        );
+        assert_eq!(counterexample(&parse("p \\to q"), &parse("\\neg q \\to \\neg p")), None);
+        assert_eq!(
+            counterexample(&parse("p \\to q"), &parse("q \\to p")),
+            Some(vec![(parse("p"), true), (parse("q"), false)])
+        );
+    }
+
+    #[test]
+    fn bdd_test() {
+        // (a_1 ∧ b_1) ∨ ... ∨ (a_6 ∧ b_6) has too many atoms for a truth table
+        let atoms = (1..=6).map(|i| format!("a_{} \\land b_{}", i, i)).collect::<Vec<_>>();
+        let lhs = parse(
+            &atoms
+                .iter()
+                .map(|a| format!("({})", a))
+                .collect::<Vec<_>>()
+                .join(" \\lor "),
+        );
+        let rhs = parse(
+            &atoms
+                .iter()
+                .rev()
+                .map(|a| format!("({})", a))
+                .collect::<Vec<_>>()
+                .join(" \\lor "),
+        );
+        assert_eq!(counterexample(&lhs, &rhs), None);
+
+        let rhs = parse(&format!("{} \\lor a_1", atoms.join(" \\lor ")));
+        let (atoms, values): (Vec<_>, Vec<_>) = counterexample(&lhs, &rhs).unwrap().into_iter().unzip();
+        assert_ne!(eval_bool(&lhs, &atoms, &values), eval_bool(&rhs, &atoms, &values));
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use log::{debug, error};
+
+use crate::delimiter;
+use crate::errors::{ParseFormulaError, ParseMacroError};
+
+// Expansion stops here to avoid looping on recursive definitions like `\def\x{\x}`
+const MAX_EXPANSIONS: usize = 1000;
+
+// A user-defined macro, e.g. `\newcommand{\abs}[1]{\left|#1\right|}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Macro {
+    pub params: usize,           // Number of parameters
+    pub default: Option<String>, // Default of the optional first parameter
+    pub body: String,            // Replacement text with `#1`, `#2`, ...
+}
+
+impl Macro {
+    pub fn new(params: usize, default: Option<&str>, body: &str) -> Self {
+        return Self {
+            params,
+            default: default.map(|s| s.to_string()),
+            body: body.to_string(),
+        };
+    }
+
+    // At most 9 parameters, and one at least for a default, e.g. `[0][x]` is invalid
+    pub fn is_valid(&self) -> bool {
+        return self.params <= 9 && (self.params > 0 || self.default.is_none());
+    }
+
+    // `#1 + #2` with [`x`, `y`] => `x + y`
+    fn substitute(&self, args: &[String]) -> String {
+        let mut result = self.body.clone();
+
+        for (n, arg) in args.iter().enumerate().rev() {
+            result = result.replace(&format!("#{}", n + 1), arg);
+        }
+
+        return result;
+    }
+}
+
+// Read `\newcommand`, `\renewcommand` and `\def` definitions, keyed by the name with the backslash
+pub fn parse_definitions(source: &str) -> Result<BTreeMap<String, Macro>, ParseMacroError> {
+    let chars = source.chars().collect_vec();
+    let mut macros = BTreeMap::new();
+    let mut i = skip_spaces(&chars, 0);
+
+    while i < chars.len() {
+        let (command, len) = read_token(&chars[i..]).ok_or(ParseMacroError)?;
+        i += len;
+
+        let (name, definition, len) = match command.as_str() {
+            "\\newcommand" | "\\renewcommand" | "\\providecommand" => read_newcommand(&chars[i..])?,
+            "\\def" => read_def(&chars[i..])?,
+            _ => {
+                error!("Macro: unknown definition command => {:?}", command);
+                return Err(ParseMacroError);
+            }
+        };
+
+        debug!("Macro: {:?} => {:?}", name, definition);
+        macros.insert(name, definition);
+        i = skip_spaces(&chars, i + len);
+    }
+
+    return Ok(macros);
+}
+
+// Expand macros repeatedly until no macro is left
+pub fn expand(s: &str, macros: &BTreeMap<String, Macro>) -> Result<String, ParseFormulaError> {
+    if macros.is_empty() {
+        return Ok(s.to_string());
+    }
+
+    let mut chars = s.chars().collect_vec();
+    let mut expansions = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (token, len) = match read_token(&chars[i..]) {
+            Some(token) => token,
+            None => break,
+        };
+
+        let definition = match macros.get(&token) {
+            Some(definition) if token.starts_with('\\') => definition,
+            _ => {
+                i += len;
+                continue;
+            }
+        };
+
+        expansions += 1;
+
+        if expansions > MAX_EXPANSIONS {
+            error!("Macro: too many expansions => {:?}", s);
+            return Err(ParseFormulaError);
+        }
+
+        let (args, args_len) = read_args(&chars[i + len..], definition).ok_or_else(|| {
+            error!("Macro: missing arguments of {:?} => {:?}", token, s);
+            return ParseFormulaError;
+        })?;
+
+        // Expanded text is scanned again for nested macros
+        let replacement = definition.substitute(&args).chars().collect_vec();
+        chars.splice(i..i + len + args_len, replacement);
+    }
+
+    let result = chars.into_iter().collect::<String>();
+    debug!("Macro: expanded {:?} => {:?}", s, result);
+    return Ok(result);
+}
+
+// `{\abs}[1]{|#1|}` or `\abs[1]{|#1|}`
+fn read_newcommand(chars: &[char]) -> Result<(String, Macro, usize), ParseMacroError> {
+    let mut i = skip_spaces(chars, 0);
+
+    let name = match delimiter::match_group(&chars[i..]) {
+        Some(len) => {
+            let name = chars[i + 1..i + len - 1].iter().collect::<String>().trim().to_string();
+            i += len;
+            name
+        }
+        None => {
+            let (name, len) = read_token(&chars[i..]).ok_or(ParseMacroError)?;
+            i += len;
+            name
+        }
+    };
+
+    if !name.starts_with('\\') {
+        error!("Macro: invalid name => {:?}", name);
+        return Err(ParseMacroError);
+    }
+
+    let mut params = 0;
+    let mut default = None;
+
+    // `[2]`: number of parameters
+    if let Some((s, len)) = read_optional(&chars[i..]) {
+        params = s.trim().parse::<usize>().map_err(|_| ParseMacroError)?;
+        i += len;
+
+        // `[x]`: default of the first parameter
+        if let Some((s, len)) = read_optional(&chars[i..]) {
+            default = Some(s);
+            i += len;
+        }
+    }
+
+    let (body, len) = read_group(&chars[i..]).ok_or(ParseMacroError)?;
+    i += len;
+
+    let definition = Macro { params, default, body };
+
+    if !definition.is_valid() {
+        error!("Macro: invalid number of parameters => {:?}", params);
+        return Err(ParseMacroError);
+    }
+
+    return Ok((name, definition, i));
+}
+
+// `\abs#1{|#1|}`
+fn read_def(chars: &[char]) -> Result<(String, Macro, usize), ParseMacroError> {
+    let mut i = skip_spaces(chars, 0);
+    let (name, len) = read_token(&chars[i..]).ok_or(ParseMacroError)?;
+    i += len;
+
+    if !name.starts_with('\\') {
+        error!("Macro: invalid name => {:?}", name);
+        return Err(ParseMacroError);
+    }
+
+    let mut params = 0;
+
+    while chars.get(i) == Some(&'#') {
+        match chars.get(i + 1).and_then(|c| c.to_digit(10)) {
+            Some(n) if n as usize == params + 1 => params += 1,
+            _ => return Err(ParseMacroError),
+        }
+
+        i += 2;
+    }
+
+    let (body, len) = read_group(&chars[i..]).ok_or(ParseMacroError)?;
+    return Ok((name, Macro::new(params, None, &body), i + len));
+}
+
+// Arguments of a macro call and their length in `chars`
+fn read_args(chars: &[char], definition: &Macro) -> Option<(Vec<String>, usize)> {
+    let mut args = Vec::new();
+    let mut i = 0;
+    let mut params = definition.params;
+
+    if let Some(default) = &definition.default {
+        match read_optional(chars) {
+            Some((s, len)) => {
+                args.push(s);
+                i += len;
+            }
+            None => args.push(default.clone()),
+        }
+
+        params = params.checked_sub(1)?;
+    }
+
+    for _ in 0..params {
+        i = skip_spaces(chars, i);
+
+        // `{x + 1}` or a single token like `x`, `\alpha`
+        match read_group(&chars[i..]) {
+            Some((s, len)) => {
+                args.push(s);
+                i += len;
+            }
+            None => {
+                let (s, len) = read_token(&chars[i..])?;
+                args.push(s);
+                i += len;
+            }
+        }
+    }
+
+    return Some((args, i));
+}
+
+// `{x + 1} y` => (`x + 1`, 7)
+fn read_group(chars: &[char]) -> Option<(String, usize)> {
+    let i = skip_spaces(chars, 0);
+
+    if chars.get(i) != Some(&'{') {
+        return None;
+    }
+
+    let len = delimiter::match_group(&chars[i..])?;
+    return Some((chars[i + 1..i + len - 1].iter().collect(), i + len));
+}
+
+// `[x] y` => (`x`, 3)
+fn read_optional(chars: &[char]) -> Option<(String, usize)> {
+    let i = skip_spaces(chars, 0);
+
+    if chars.get(i) != Some(&'[') {
+        return None;
+    }
+
+    let len = chars[i..].iter().position(|&c| c == ']')?;
+    return Some((chars[i + 1..i + len].iter().collect(), i + len + 1));
+}
+
+// A control word `\abs`, a control symbol `\,` or a character
+fn read_token(chars: &[char]) -> Option<(String, usize)> {
+    let c = *chars.first()?;
+
+    if c != '\\' {
+        return Some((c.to_string(), 1));
+    }
+
+    let name_len = chars[1..].iter().take_while(|c| c.is_ascii_alphabetic()).count().max(1);
+    let len = (1 + name_len).min(chars.len());
+    return Some((chars[..len].iter().collect(), len));
+}
+
+fn skip_spaces(chars: &[char], i: usize) -> usize {
+    return i + chars[i.min(chars.len())..]
+        .iter()
+        .take_while(|c| c.is_whitespace())
+        .count();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::macros::*;
+
+    fn definitions(source: &str) -> BTreeMap<String, Macro> {
+        return parse_definitions(source).unwrap();
+    }
+
+    #[test]
+    fn parse_newcommand_test() {
+        let macros = definitions("\\newcommand{\\R}{\\mathbb{R}}\n\\renewcommand\\abs[1]{\\left|#1\\right|}");
+        assert_eq!(macros.get("\\R"), Some(&Macro::new(0, None, "\\mathbb{R}")));
+        assert_eq!(macros.get("\\abs"), Some(&Macro::new(1, None, "\\left|#1\\right|")));
+    }
+
+    #[test]
+    fn parse_newcommand_default_test() {
+        let macros = definitions("\\newcommand{\\pow}[2][x]{#1^{#2}}");
+        assert_eq!(macros.get("\\pow"), Some(&Macro::new(2, Some("x"), "#1^{#2}")));
+    }
+
+    #[test]
+    fn parse_def_test() {
+        let macros = definitions("\\def\\R{\\mathbb{R}} \\def\\sq#1#2{#1 #2}");
+        assert_eq!(macros.get("\\R"), Some(&Macro::new(0, None, "\\mathbb{R}")));
+        assert_eq!(macros.get("\\sq"), Some(&Macro::new(2, None, "#1 #2")));
+    }
+
+    #[test]
+    fn parse_fail_test() {
+        assert!(parse_definitions("\\newcommand{R}{x}").is_err());
+        assert!(parse_definitions("\\newcommand{\\R}[a]{x}").is_err());
+        assert!(parse_definitions("\\newcommand{\\R}[10]{x}").is_err());
+        assert!(parse_definitions("\\newcommand{\\R}[0][y]{x}").is_err());
+        assert!(parse_definitions("\\newcommand{\\R}{x").is_err());
+        assert!(parse_definitions("\\def\\R#2{x}").is_err());
+        assert!(parse_definitions("\\let\\R\\x").is_err());
+    }
+
+    #[test]
+    fn expand_test() {
+        let macros = definitions("\\newcommand{\\two}{2}\\newcommand{\\plus}[2]{(#1 + #2)}");
+        assert_eq!(expand("\\two x", &macros).unwrap(), "2 x");
+        assert_eq!(expand("\\plus{x}{y} z", &macros).unwrap(), "(x + y) z");
+        assert_eq!(expand("\\plus x y", &macros).unwrap(), "(x + y)");
+        assert_eq!(expand("\\plus{\\two}{1}", &macros).unwrap(), "(2 + 1)");
+        assert_eq!(expand("\\twox", &macros).unwrap(), "\\twox");
+    }
+
+    #[test]
+    fn expand_default_test() {
+        let macros = definitions("\\newcommand{\\scale}[2][2]{#1 #2}");
+        assert_eq!(expand("\\scale{x}", &macros).unwrap(), "2 x");
+        assert_eq!(expand("\\scale[3]{x}", &macros).unwrap(), "3 x");
+    }
+
+    #[test]
+    fn expand_fail_test() {
+        let macros = definitions("\\def\\loop{\\loop}\\newcommand{\\f}[1]{#1}");
+        assert!(expand("\\loop", &macros).is_err());
+        assert!(expand("\\f", &macros).is_err());
+
+        // Built through the API without checking
+        let macros = BTreeMap::from([("\\g".to_string(), Macro::new(0, Some("y"), "x"))]);
+        assert!(expand("\\g", &macros).is_err());
+    }
+}
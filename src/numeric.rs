@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use log::debug;
+
+use crate::config::Config;
+use crate::formula::Formula;
+use crate::symbol;
+
+// Number of sample points tried for each comparison
+pub(crate) const SAMPLES: usize = 32;
+
+// Formulas defined at fewer sample points than this are not considered equivalent
+pub(crate) const MIN_VALID_SAMPLES: usize = 8;
+
+// Relative tolerance of two values at a sample point
+pub(crate) const TOLERANCE: f64 = 1e-9;
+
+// Groups of sample points sharing the values of all variables but the declared one
+const GROUPS: usize = 4;
+
+// Variables are sampled from [-SAMPLE_RANGE, SAMPLE_RANGE]
+const SAMPLE_RANGE: f64 = 3.0;
+
+// Deterministic pseudo-random numbers (xorshift64), so that a comparison always gives the same result
+pub(crate) struct Sampler {
+    state: u64,
+}
+
+impl Sampler {
+    pub(crate) fn new() -> Self {
+        return Self {
+            state: 0x2545_f491_4f6c_dd1d,
+        };
+    }
+
+    // Uniform in [0, 1)
+    fn next_unit(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        return (self.state >> 11) as f64 / (1u64 << 53) as f64;
+    }
+
+    // Uniform in [-SAMPLE_RANGE, SAMPLE_RANGE]
+    pub(crate) fn next(&mut self) -> f64 {
+        return (self.next_unit() * 2.0 - 1.0) * SAMPLE_RANGE;
+    }
+
+    // Uniform among the integers from 0 up to `max`
+    pub(crate) fn next_integer(&mut self, max: usize) -> usize {
+        return ((self.next_unit() * (max + 1) as f64) as usize).min(max);
+    }
+}
+
+// `lhs` and `rhs` agree at every sample point where either is defined, e.g. `(x + 1)^2` and `x^2 + 2x + 1`
+pub fn equivalent(lhs: &Formula, rhs: &Formula, config: &Config) -> bool {
+    if lhs == rhs {
+        return true;
+    }
+
+    let mut vars = lhs.free_vars();
+    vars.extend(rhs.free_vars());
+
+    let mut sampler = Sampler::new();
+    let mut valid = 0;
+
+    for _ in 0..SAMPLES {
+        let point = vars
+            .iter()
+            .map(|var| (var.clone(), sampler.next()))
+            .collect::<BTreeMap<_, _>>();
+
+        match (lhs.eval(config, &point), rhs.eval(config, &point)) {
+            (Some(l), Some(r)) if is_close(l, r) => valid += 1,
+            (None, None) => continue,
+            (l, r) => {
+                debug!("Numeric: differ at {:?} => {:?} != {:?}", point, l, r);
+                return false;
+            }
+        }
+    }
+
+    debug!("Numeric: {} of {} samples agree", valid, SAMPLES);
+    return valid >= MIN_VALID_SAMPLES;
+}
+
+// `lhs - rhs` does not change with `var`, e.g. `\frac{x^2}{2} + C` and `\frac{x^2 + 1}{2}` for `x`
+pub fn equivalent_up_to_constant(lhs: &Formula, rhs: &Formula, var: &str, config: &Config) -> bool {
+    let lhs = without_arbitrary_constants(lhs.clone());
+    let rhs = without_arbitrary_constants(rhs.clone());
+
+    if lhs == rhs {
+        return true;
+    }
+
+    let mut vars = lhs.free_vars();
+    vars.extend(rhs.free_vars());
+    vars.remove(var);
+
+    let mut sampler = Sampler::new();
+    let mut valid = 0;
+
+    for _ in 0..GROUPS {
+        let mut point = vars
+            .iter()
+            .map(|var| (var.clone(), sampler.next()))
+            .collect::<BTreeMap<_, _>>();
+        let mut first_difference = None;
+
+        for _ in 0..SAMPLES / GROUPS {
+            point.insert(var.to_string(), sampler.next());
+
+            match (lhs.eval(config, &point), rhs.eval(config, &point)) {
+                (Some(l), Some(r)) => {
+                    let difference = l - r;
+                    let first = *first_difference.get_or_insert(difference);
+
+                    // The tolerance follows the values, since their difference loses precision
+                    if (difference - first).abs() > TOLERANCE * (1.0 + l.abs().max(r.abs())) {
+                        debug!(
+                            "Numeric: difference changes at {:?} => {:?} != {:?}",
+                            point, difference, first
+                        );
+                        return false;
+                    }
+
+                    valid += 1;
+                }
+                (None, None) => continue,
+                (l, r) => {
+                    debug!("Numeric: differ at {:?} => {:?} != {:?}", point, l, r);
+                    return false;
+                }
+            }
+        }
+    }
+
+    debug!("Numeric: {} of {} samples differ by a constant", valid, SAMPLES);
+    return valid >= MIN_VALID_SAMPLES;
+}
+
+// Drop arbitrary constant terms, e.g. `x + C` => `x`, `x - K` => `x`
+fn without_arbitrary_constants(formula: Formula) -> Formula {
+    let is_arbitrary = |f: &Formula| match f {
+        Formula::TS(s) => symbol::is_arbitrary_constant(s),
+        Formula::Neg(f) => matches!(&**f, Formula::TS(s) if symbol::is_arbitrary_constant(s)),
+        _ => false,
+    };
+
+    return match formula {
+        Formula::Add(formulas) => {
+            let mut rest = formulas.into_iter().filter(|f| !is_arbitrary(f)).collect::<Vec<_>>();
+
+            match rest.len() {
+                0 => Formula::TS("0".to_string()),
+                1 => rest.remove(0),
+                _ => Formula::Add(rest),
+            }
+        }
+        f if is_arbitrary(&f) => Formula::TS("0".to_string()),
+        f => f,
+    };
+}
+
+pub fn is_close(l: f64, r: f64) -> bool {
+    return (l - r).abs() <= TOLERANCE * (1.0 + l.abs().max(r.abs()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::formula::Formula;
+    use crate::numeric::*;
+
+    fn equivalent_str(l: &str, r: &str) -> bool {
+        let config = Config::default();
+        let lhs = Formula::parse(l).unwrap().fold_constants(&config);
+        let rhs = Formula::parse(r).unwrap().fold_constants(&config);
+        return equivalent(&lhs, &rhs, &config);
+    }
+
+    #[test]
+    fn sampler_test() {
+        let mut sampler = Sampler::new();
+        let values = (0..100).map(|_| sampler.next()).collect::<Vec<_>>();
+        assert!(values.iter().all(|v| v.abs() <= SAMPLE_RANGE));
+        assert!(values.iter().any(|v| *v < 0.0) && values.iter().any(|v| *v > 0.0));
+    }
+
+    #[test]
+    fn equivalent_test() {
+        assert!(equivalent_str("(x + 1)^2", "x^2 + 2x + 1"));
+        assert!(equivalent_str("x y + x", "x (y + 1)"));
+        assert!(equivalent_str("\\int_0^1 t^4 dt", "1 / 5"));
+        assert!(!equivalent_str("|x|", "x"));
+        assert!(!equivalent_str("x + 1", "x"));
+    }
+
+    #[test]
+    fn equivalent_up_to_constant_test() {
+        let config = Config::default();
+        let check = |l: &str, r: &str| {
+            let lhs = Formula::parse(l).unwrap().fold_constants(&config);
+            let rhs = Formula::parse(r).unwrap().fold_constants(&config);
+            return equivalent_up_to_constant(&lhs, &rhs, "x", &config);
+        };
+
+        assert!(check("\\frac{x^2}{2} + C", "\\frac{x^2 + 1}{2}"));
+        assert!(check("a x + K", "a x - c"));
+        assert!(check("\\text{m} + C", "\\text{m}"));
+        assert!(check("x y + 2 y", "x y"));
+        assert!(!check("x^2", "x^2 + x"));
+        assert!(!check("a x", "x"));
+    }
+
+    #[test]
+    fn undefined_test() {
+        // Not evaluable: falls back to the structural comparison
+        assert!(equivalent_str("\\text{m}", "\\text{m}"));
+        assert!(!equivalent_str("\\text{m}", "\\text{s}"));
+        assert!(!equivalent_str("1 / (x - x)", "1 / 0"));
+    }
+}
@@ -5,15 +5,104 @@ use crate::errors::ParseFormulaError;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operator {
     Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    // All notations of the operator, e.g. `\cdot` and `×` for multiplication
+    pub fn symbols(&self) -> &'static [&'static str] {
+        return match self {
+            Self::Add => &["+"],
+            Self::Sub => &["-"],
+            Self::Mul => &["*", "\\cdot", "\\times", "\\ast", "×", "·", "⋅"],
+            Self::Div => &["/", "\\div", "÷"],
+        };
+    }
+}
+
+// Comparison between two sides, e.g. `x \le 1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Relation {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Relation {
+    pub const ALL: [Self; 6] = [Self::Eq, Self::Ne, Self::Lt, Self::Le, Self::Gt, Self::Ge];
+
+    pub fn symbols(&self) -> &'static [&'static str] {
+        return match self {
+            Self::Eq => &["="],
+            Self::Ne => &["\\neq", "\\ne", "≠"],
+            Self::Lt => &["<", "\\lt"],
+            Self::Le => &["\\le", "\\leq", "\\leqslant", "≤"],
+            Self::Gt => &[">", "\\gt"],
+            Self::Ge => &["\\ge", "\\geq", "\\geqslant", "≥"],
+        };
+    }
+
+    // The same relation with the sides swapped, e.g. `>` => `<`
+    pub fn flipped(&self) -> Self {
+        return match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            _ => *self,
+        };
+    }
+}
+
+impl FromStr for Relation {
+    type Err = ParseFormulaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Self::ALL
+            .into_iter()
+            .find(|relation| relation.symbols().contains(&s))
+            .ok_or(ParseFormulaError);
+    }
 }
 
 impl FromStr for Operator {
     type Err = ParseFormulaError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "+" => return Ok(Self::Add),
-            _ => return Err(ParseFormulaError),
-        }
+        return [Self::Add, Self::Sub, Self::Mul, Self::Div]
+            .into_iter()
+            .find(|operator| operator.symbols().contains(&s))
+            .ok_or(ParseFormulaError);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operator::{Operator, Relation};
+
+    #[test]
+    fn from_str_test() {
+        assert_eq!("+".parse::<Operator>().unwrap(), Operator::Add);
+        assert_eq!("-".parse::<Operator>().unwrap(), Operator::Sub);
+        assert_eq!("\\cdot".parse::<Operator>().unwrap(), Operator::Mul);
+        assert_eq!("×".parse::<Operator>().unwrap(), Operator::Mul);
+        assert_eq!("\\div".parse::<Operator>().unwrap(), Operator::Div);
+        assert_eq!("÷".parse::<Operator>().unwrap(), Operator::Div);
+        assert!("\\cdots".parse::<Operator>().is_err());
+    }
+
+    #[test]
+    fn relation_from_str_test() {
+        assert_eq!("=".parse::<Relation>().unwrap(), Relation::Eq);
+        assert_eq!("\\leq".parse::<Relation>().unwrap(), Relation::Le);
+        assert_eq!("≥".parse::<Relation>().unwrap(), Relation::Ge);
+        assert_eq!(Relation::Lt.flipped(), Relation::Gt);
+        assert_eq!(Relation::Ne.flipped(), Relation::Ne);
+        assert!("\\left".parse::<Relation>().is_err());
     }
 }
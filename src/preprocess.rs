@@ -0,0 +1,138 @@
+use itertools::Itertools;
+use log::debug;
+use regex::{Captures, Regex};
+
+use crate::delimiter;
+use crate::symbol;
+
+// Normalise copy-pasted LaTeX before parsing:
+//
+// - strip math mode markers: `$x$`, `$$x$$`, `\(x\)`, `\[x\]`
+// - strip spacing and style macros: `\,`, `\quad`, `\displaystyle`, `~`, ...
+// - resolve pipes into opening and closing ones: `|x|` => `\lvert x\rvert`
+// - resolve half-open intervals into internal delimiters: `(0, 1]` => `\lopen 0, 1 \rclosed`
+// - resolve roundings into internal delimiters: `\lfloor x \rceil` => `\lround x \rround`
+// - write repeating decimals as fractions: `0.1\overline{6}` => `(\frac{16 - 1}{90})`
+//
+// Font and text macros such as `\mathrm{d}` or `\text{m}` are left to the parser.
+pub fn preprocess(s: &str) -> String {
+    let s = strip_math_mode(s.trim());
+    let chars = s.chars().collect_vec();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '~' {
+            result.push(' ');
+            i += 1;
+            continue;
+        }
+
+        if c != '\\' || i + 1 >= chars.len() {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+
+        let name_len = chars[i + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count()
+            .max(1);
+        let name = chars[i + 1..i + 1 + name_len].iter().collect::<String>();
+
+        if symbol::is_formatting_macro(&name) {
+            result.push(' ');
+        } else {
+            result.push('\\');
+            result.push_str(&name);
+        }
+
+        i += 1 + name_len;
+    }
+
+    let result = delimiter::resolve_rounding(&delimiter::resolve_intervals(&delimiter::resolve_pipes(&result)));
+    let result = resolve_repeating_decimals(&result);
+    debug!("Preprocess: {:?} => {:?}", s, result);
+    return result;
+}
+
+// An overline after a decimal point repeats its digits, e.g. `0.\overline{3}` => `(\frac{3 - 0}{9})`
+fn resolve_repeating_decimals(s: &str) -> String {
+    let re = Regex::new(r"(\d*)\.(\d*)\\(?:overline|bar)\s*(?:\{\s*(\d+)\s*\}|(\d))").unwrap();
+    let integer = |digits: &str| match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    return re
+        .replace_all(s, |captures: &Captures| {
+            let (whole, fixed) = (&captures[1], &captures[2]);
+            let repeating = captures.get(3).or_else(|| captures.get(4)).map_or("", |m| m.as_str());
+            return format!(
+                "(\\frac{{{} - {}}}{{{}{}}})",
+                integer(&format!("{}{}{}", whole, fixed, repeating)),
+                integer(&format!("{}{}", whole, fixed)),
+                "9".repeat(repeating.len()),
+                "0".repeat(fixed.len())
+            );
+        })
+        .to_string();
+}
+
+// `$x + 1$` => `x + 1`
+fn strip_math_mode(s: &str) -> &str {
+    for (open, close) in [("$$", "$$"), ("$", "$"), ("\\(", "\\)"), ("\\[", "\\]")] {
+        if s.len() >= open.len() + close.len() && s.starts_with(open) && s.ends_with(close) {
+            return s[open.len()..s.len() - close.len()].trim();
+        }
+    }
+
+    return s;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocess::preprocess;
+
+    #[test]
+    fn repeating_decimal_test() {
+        assert_eq!(preprocess("0.\\overline{3}"), "(\\frac{3 - 0}{9})");
+        assert_eq!(preprocess("1.2\\overline{34}"), "(\\frac{1234 - 12}{990})");
+        assert_eq!(preprocess("0.\\bar 6 x"), "(\\frac{6 - 0}{9}) x");
+        assert_eq!(preprocess("\\overline{3}"), "\\overline{3}");
+    }
+
+    #[test]
+    fn spacing_test() {
+        assert_eq!(preprocess("x\\,y"), "x y");
+        assert_eq!(preprocess("x\\;+\\:1"), "x + 1");
+        assert_eq!(preprocess("x\\quad y\\qquad z"), "x  y  z");
+        assert_eq!(preprocess("2\\!x"), "2 x");
+        assert_eq!(preprocess("x\\ y~z"), "x y z");
+    }
+
+    #[test]
+    fn style_test() {
+        assert_eq!(preprocess("\\displaystyle x"), "  x");
+        assert_eq!(preprocess("\\textstyle\\alpha"), " \\alpha");
+    }
+
+    #[test]
+    fn math_mode_test() {
+        assert_eq!(preprocess("$x + 1$"), "x + 1");
+        assert_eq!(preprocess("$$ x $$"), "x");
+        assert_eq!(preprocess("\\(x\\)"), "x");
+        assert_eq!(preprocess("\\[x\\]"), "x");
+        assert_eq!(preprocess("$"), "$");
+    }
+
+    #[test]
+    fn keep_test() {
+        assert_eq!(preprocess("\\left( x \\right)"), "\\left( x \\right)");
+        assert_eq!(preprocess("\\mathrm{d}x"), "\\mathrm{d}x");
+        assert_eq!(preprocess("\\{x\\}"), "\\{x\\}");
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::f64::consts::{E, PI};
+
+// Greek letters (without the leading backslash)
+pub const GREEK_LETTERS: [&str; 40] = [
+    "alpha",
+    "beta",
+    "gamma",
+    "delta",
+    "epsilon",
+    "varepsilon",
+    "zeta",
+    "eta",
+    "theta",
+    "vartheta",
+    "iota",
+    "kappa",
+    "lambda",
+    "mu",
+    "nu",
+    "xi",
+    "pi",
+    "varpi",
+    "rho",
+    "varrho",
+    "sigma",
+    "varsigma",
+    "tau",
+    "upsilon",
+    "phi",
+    "varphi",
+    "chi",
+    "psi",
+    "omega",
+    "Gamma",
+    "Delta",
+    "Theta",
+    "Lambda",
+    "Xi",
+    "Pi",
+    "Sigma",
+    "Upsilon",
+    "Phi",
+    "Psi",
+    "Omega",
+];
+
+// Other symbol macros that stand for a single identifier
+pub const SYMBOL_MACROS: [&str; 8] = [
+    "infty",
+    "ell",
+    "hbar",
+    "imath",
+    "jmath",
+    "aleph",
+    "emptyset",
+    "varnothing",
+];
+
+// Spacing and style macros without mathematical meaning
+pub const FORMATTING_MACROS: [&str; 20] = [
+    ",",
+    ":",
+    ";",
+    "!",
+    " ",
+    ">",
+    "quad",
+    "qquad",
+    "enspace",
+    "thinspace",
+    "medspace",
+    "thickspace",
+    "negthinspace",
+    "negmedspace",
+    "displaystyle",
+    "textstyle",
+    "scriptstyle",
+    "scriptscriptstyle",
+    "limits",
+    "nolimits",
+];
+
+// Font macros taking one argument, e.g. `\mathrm{d}`
+pub const FONT_MACROS: [&str; 9] = [
+    "mathrm",
+    "mathit",
+    "mathbf",
+    "mathsf",
+    "mathtt",
+    "mathcal",
+    "mathbb",
+    "mathfrak",
+    "boldsymbol",
+];
+
+// Big operators taking a bound variable and a body, e.g. `\sum_{k=1}^{n} k`, `\lim_{x \to 0} x`
+pub const BIG_OPERATORS: [&str; 3] = ["\\sum", "\\prod", "\\lim"];
+
+// Arrows between the variable and the target of a limit, e.g. `x \to 0`
+pub const ARROWS: [&str; 4] = ["\\to", "\\rightarrow", "\\longrightarrow", "→"];
+
+// Integrals taking a body closed by differentials, e.g. `\int_0^1 x^2 dx`
+pub const INTEGRALS: [&str; 4] = ["\\int", "\\iint", "\\iiint", "\\oint"];
+
+// Elementary functions applied to an argument, e.g. `\sin x`, `\ln(x + 1)`, `\Re z`
+pub const FUNCTIONS: [&str; 18] = [
+    "\\sin", "\\cos", "\\tan", "\\cot", "\\sec", "\\csc", "\\arcsin", "\\arccos", "\\arctan", "\\sinh", "\\cosh",
+    "\\tanh", "\\exp", "\\ln", "\\log", "\\Re", "\\Im", "\\arg",
+];
+
+// Fraction macros taking a numerator and a denominator, e.g. `\frac{x}{2}`
+pub const FRACTION_MACROS: [&str; 3] = ["frac", "dfrac", "tfrac"];
+
+// Binomial coefficient macros taking the top and the bottom, e.g. `\binom{n}{k}`
+pub const BINOMIAL_MACROS: [&str; 3] = ["binom", "dbinom", "tbinom"];
+
+// Newton's notation for time derivatives and their order, e.g. `\ddot{x}`
+pub const DOT_MACROS: [(&str, usize); 3] = [("dot", 1), ("ddot", 2), ("dddot", 3)];
+
+// Complex conjugate macros taking an argument, e.g. `\overline{z}`, `\bar z`
+pub const CONJUGATE_MACROS: [&str; 2] = ["overline", "bar"];
+
+// Variable of differentiation implied by primes like `y'` by default and dots like `\dot{x}`
+pub const PRIME_VARIABLE: &str = "x";
+pub const DOT_VARIABLE: &str = "t";
+
+// Letters that may be declared as combinatorial notation, e.g. `C^n_k` and `P(n, k)`, which are symbols otherwise
+pub const COMBINATORIAL_LETTERS: [&str; 2] = ["C", "P"];
+
+// Environments of matrices, e.g. `\begin{pmatrix} 1 & 2 \end{pmatrix}`
+pub const MATRIX_ENVIRONMENTS: [&str; 8] = [
+    "matrix",
+    "pmatrix",
+    "bmatrix",
+    "Bmatrix",
+    "vmatrix",
+    "Vmatrix",
+    "smallmatrix",
+    "array",
+];
+
+// Separators between the element and the condition of a set, e.g. `\{x \mid x > 0\}`, where `|` becomes `\mid`
+pub const SET_BUILDER_SEPARATORS: [&str; 2] = ["\\mid", ":"];
+
+// Symbols of the empty set, equal to `\{\}`
+pub const EMPTY_SETS: [&str; 2] = ["\\emptyset", "\\varnothing"];
+
+// Logical connectives, e.g. `\neg (p \land q)`
+pub const NOT: [&str; 2] = ["\\neg", "\\lnot"];
+pub const AND: [&str; 2] = ["\\land", "\\wedge"];
+pub const OR: [&str; 2] = ["\\lor", "\\vee"];
+pub const XOR: [&str; 1] = ["\\oplus"];
+pub const IMPLIES: [&str; 4] = ["\\to", "\\rightarrow", "\\Rightarrow", "\\implies"];
+pub const IFF: [&str; 3] = ["\\leftrightarrow", "\\Leftrightarrow", "\\iff"];
+
+// Quantifiers binding a variable, e.g. `\forall x \in A, x > 0`
+pub const QUANTIFIERS: [&str; 2] = ["\\forall", "\\exists"];
+pub const QUANTIFIER_SEPARATORS: [&str; 2] = [",", ":"];
+
+// Modular arithmetic, e.g. `a \bmod n` and `a \equiv b \pmod{n}`
+pub const MOD_OPERATORS: [&str; 2] = ["\\bmod", "\\mod"];
+pub const CONGRUENCE: &str = "\\equiv";
+pub const MODULUS_MACROS: [&str; 2] = ["\\pmod", "\\mod"];
+
+// Separators of alternative answers, e.g. `x = 2, x = -3`, after `\text{ or }` is normalised into `\text{or}`
+pub const LIST_SEPARATORS: [&str; 3] = [",", ";", "\\text{or}"];
+
+// Symbols standing for an arbitrary constant, e.g. the constant of integration in `x + C`
+pub const ARBITRARY_CONSTANTS: [&str; 3] = ["C", "K", "c"];
+
+// Font macros whose capital letters stand for matrices, e.g. `\mathbf{A}`
+pub const BOLD_FONTS: [&str; 2] = ["mathbf", "boldsymbol"];
+
+// Text macros taking one argument, e.g. `\text{ m/s }`
+pub const TEXT_MACROS: [&str; 4] = ["text", "textrm", "textit", "mbox"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constant {
+    Real(f64), // Real Number
+    Imaginary, // Imaginary Unit
+}
+
+// e.g. `alpha` => true, `frac` => false
+pub fn is_symbol_macro(name: &str) -> bool {
+    return GREEK_LETTERS.contains(&name) || SYMBOL_MACROS.contains(&name);
+}
+
+pub fn is_big_operator(s: &str) -> bool {
+    return BIG_OPERATORS.contains(&s);
+}
+
+pub fn is_integral(s: &str) -> bool {
+    return INTEGRALS.contains(&s);
+}
+
+// Number of differentials closing an integral, e.g. `\iint` => 2
+pub fn integral_dimension(s: &str) -> usize {
+    return match s {
+        "\\iint" => 2,
+        "\\iiint" => 3,
+        _ => 1,
+    };
+}
+
+pub fn is_function(s: &str) -> bool {
+    return FUNCTIONS.contains(&s);
+}
+
+pub fn is_fraction_macro(name: &str) -> bool {
+    return FRACTION_MACROS.contains(&name);
+}
+
+pub fn is_binomial_macro(name: &str) -> bool {
+    return BINOMIAL_MACROS.contains(&name);
+}
+
+pub fn is_conjugate_macro(name: &str) -> bool {
+    return CONJUGATE_MACROS.contains(&name);
+}
+
+// e.g. `ddot` => Some(2)
+pub fn dot_order(name: &str) -> Option<usize> {
+    return DOT_MACROS.iter().find(|(dot, _)| *dot == name).map(|(_, order)| *order);
+}
+
+pub fn is_matrix_environment(name: &str) -> bool {
+    return MATRIX_ENVIRONMENTS.contains(&name);
+}
+
+pub fn is_empty_set(s: &str) -> bool {
+    return EMPTY_SETS.contains(&s);
+}
+
+pub fn is_arbitrary_constant(s: &str) -> bool {
+    return ARBITRARY_CONSTANTS.contains(&s);
+}
+
+pub fn is_bold_font(name: &str) -> bool {
+    return BOLD_FONTS.contains(&name);
+}
+
+pub fn is_formatting_macro(name: &str) -> bool {
+    return FORMATTING_MACROS.contains(&name);
+}
+
+// e.g. `mathrm` => true, `alpha` => false
+pub fn is_argument_macro(name: &str) -> bool {
+    return FONT_MACROS.contains(&name) || TEXT_MACROS.contains(&name);
+}
+
+pub fn default_unwrapped_fonts() -> BTreeSet<String> {
+    return ["mathrm", "mathit", "mathbf"].iter().map(|s| s.to_string()).collect();
+}
+
+pub fn default_constants() -> BTreeMap<String, Constant> {
+    return BTreeMap::from([
+        ("\\pi".to_string(), Constant::Real(PI)),
+        ("e".to_string(), Constant::Real(E)),
+        ("i".to_string(), Constant::Imaginary),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbol::*;
+
+    #[test]
+    fn is_symbol_macro_test() {
+        assert!(is_symbol_macro("alpha"));
+        assert!(is_symbol_macro("Omega"));
+        assert!(is_symbol_macro("infty"));
+        assert!(!is_symbol_macro("frac"));
+        assert!(!is_symbol_macro("Alpha"));
+    }
+
+    #[test]
+    fn default_constants_test() {
+        let constants = default_constants();
+        assert_eq!(constants.get("\\pi"), Some(&Constant::Real(PI)));
+        assert_eq!(constants.get("e"), Some(&Constant::Real(E)));
+        assert_eq!(constants.get("i"), Some(&Constant::Imaginary));
+        assert_eq!(constants.get("x"), None);
+    }
+}